@@ -16,6 +16,20 @@ pub fn git_parse_args() -> ArgMatches {
                         .help("Path to repository")
                         .required(false),
                 )
+                .arg(
+                    Arg::new("object_format")
+                        .long("object-format")
+                        .help("Object hash algorithm: sha1 or sha256")
+                        .value_parser(["sha1", "sha256"])
+                        .default_value("sha1"),
+                )
+                .arg(
+                    Arg::new("reference")
+                        .long("reference")
+                        .value_name("repo")
+                        .help("Borrow objects from <repo> via objects/info/alternates")
+                        .required(false),
+                )
         )
 
         // 添加文件到暂存区
@@ -101,6 +115,323 @@ pub fn git_parse_args() -> ArgMatches {
                 )
         )
 
+        // 标签管理
+        .subcommand(
+            Command::new("tag")
+                .about("Create, list, or delete tags")
+                .arg(
+                    Arg::new("tag_name")
+                        .help("Tag name (or pattern with -l, or names with -d)")
+                        .required(false)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("annotate")
+                        .short('a')
+                        .long("annotate")
+                        .help("Create an annotated tag object")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("message")
+                        .short('m')
+                        .long("message")
+                        .help("Tag message (implies -a)")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("delete")
+                        .short('d')
+                        .long("delete")
+                        .help("Delete tags")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("list")
+                        .short('l')
+                        .long("list")
+                        .help("List tags matching an optional pattern")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .help("Replace an existing tag")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+        )
+
+        // 打包对象
+        .subcommand(
+            Command::new("repack")
+                .about("Pack objects into a packfile with delta compression")
+                .arg(
+                    Arg::new("delete")
+                        .short('d')
+                        .long("delete")
+                        .help("Delete loose objects and old packs once they are packed")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("write_bitmap")
+                        .short('b')
+                        .long("write-bitmap-index")
+                        .help("Write a reachability bitmap index for the new pack")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("window")
+                        .long("window")
+                        .help("Number of objects to consider as delta bases")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::new("depth")
+                        .long("depth")
+                        .help("Maximum delta chain depth")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("50"),
+                )
+        )
+
+        // 多 pack 索引
+        .subcommand(
+            Command::new("multi-pack-index")
+                .about("Write and verify the multi-pack-index used to look up objects across packs")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("write")
+                        .about("Write a multi-pack-index covering every pack")
+                )
+                .subcommand(
+                    Command::new("verify")
+                        .about("Verify the multi-pack-index against the packs it covers")
+                )
+        )
+
+        // 查看对象
+        .subcommand(
+            Command::new("cat-file")
+                .about("Show type, size or content of repository objects")
+                .arg(
+                    Arg::new("type")
+                        .short('t')
+                        .help("Show the object type")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["size", "pretty", "exists"]),
+                )
+                .arg(
+                    Arg::new("size")
+                        .short('s')
+                        .help("Show the object size")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["pretty", "exists"]),
+                )
+                .arg(
+                    Arg::new("pretty")
+                        .short('p')
+                        .help("Pretty-print the object content")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("exists"),
+                )
+                .arg(
+                    Arg::new("exists")
+                        .short('e')
+                        .help("Exit with zero status if the object exists")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("batch")
+                        .long("batch")
+                        .help("Print info and content of objects named on stdin")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["type", "size", "pretty", "exists", "batch_check"]),
+                )
+                .arg(
+                    Arg::new("batch_check")
+                        .long("batch-check")
+                        .help("Print info of objects named on stdin")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["type", "size", "pretty", "exists"]),
+                )
+                .arg(
+                    Arg::new("object")
+                        .help("Object name (hash, branch, tag, ...)")
+                        .required_unless_present_any(["batch", "batch_check"]),
+                )
+        )
+
+        // 列出 tree 内容
+        .subcommand(
+            Command::new("ls-tree")
+                .about("List the contents of a tree object")
+                .arg(
+                    Arg::new("recursive")
+                        .short('r')
+                        .help("Recurse into subtrees")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("show_trees")
+                        .short('t')
+                        .help("Show tree entries even when recursing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("long")
+                        .short('l')
+                        .long("long")
+                        .help("Show object size of blob entries")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("name_only")
+                        .long("name-only")
+                        .help("List only file names")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("tree_ish")
+                        .help("Tree, commit or tag to list")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("paths")
+                        .help("Only show the given paths")
+                        .num_args(0..),
+                )
+        )
+
+        // 列出提交历史
+        .subcommand(
+            Command::new("rev-list")
+                .about("List commits reachable from a commit, optionally limited to a path")
+                .arg(
+                    Arg::new("objects")
+                        .long("objects")
+                        .help("List all reachable objects, not only commits")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .help("Print the number of commits (or objects) instead of listing them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("commit")
+                        .help("Commit to start from")
+                        .default_value("HEAD"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .help("Only list commits that changed this path")
+                        .last(true),
+                )
+        )
+
+        // 检查仓库完整性
+        .subcommand(
+            Command::new("fsck")
+                .about("Verify the integrity and connectivity of objects in the repository")
+                .arg(
+                    Arg::new("unreachable")
+                        .long("unreachable")
+                        .help("Show all unreachable objects, not only dangling ones")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+
+        // 清理不可达对象
+        .subcommand(
+            Command::new("gc")
+                .about("Remove unreachable loose objects older than the prune expiry")
+                .arg(
+                    Arg::new("prune")
+                        .long("prune")
+                        .value_name("date")
+                        .help("Prune objects older than <date> (default: gc.pruneExpire or 2.weeks.ago)")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .short('n')
+                        .long("dry-run")
+                        .help("Only report what would be removed")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+
+        // 删除不可达对象
+        .subcommand(
+            Command::new("prune")
+                .about("Remove unreachable loose objects")
+                .arg(
+                    Arg::new("expire")
+                        .long("expire")
+                        .value_name("time")
+                        .help("Only remove objects older than <time> (default: now)")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .short('n')
+                        .long("dry-run")
+                        .help("Only report what would be removed")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+
+        // 统计对象数量与大小
+        .subcommand(
+            Command::new("count-objects")
+                .about("Count loose and packed objects and their disk usage")
+                .arg(
+                    Arg::new("verbose")
+                        .short('v')
+                        .long("verbose")
+                        .help("Report packs, prune-packable objects and garbage too")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+
+        // 仓库体积分析
+        .subcommand(
+            Command::new("sizer")
+                .about("Report the largest blobs, trees and commits and the longest history chain")
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .help("Number of objects to list in each section")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                )
+        )
+
+        // 提交图
+        .subcommand(
+            Command::new("commit-graph")
+                .about("Write the commit-graph file used to speed up history traversal")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("write")
+                        .about("Write a commit-graph file for all commits reachable from refs")
+                        .arg(
+                            Arg::new("changed_paths")
+                                .long("changed-paths")
+                                .help("Also compute changed-path Bloom filters")
+                                .action(ArgAction::SetTrue),
+                        )
+                )
+        )
+
         // 合并分支
         .subcommand(
             Command::new("merge")
@@ -5,20 +5,41 @@ use crate::commands::rm::git_rm;
 use crate::commands::commit::git_commit;
 use crate::commands::branch::git_branch;
 use crate::commands::checkout::git_checkout;
-use crate::commands::merge::git_merge;
-use crate::commands::fetch::git_fetch;
-use crate::commands::pull::git_pull;
-use crate::commands::push::git_push;
+use crate::commands::repack::git_repack;
+use crate::commands::multi_pack_index::{git_multi_pack_index_verify, git_multi_pack_index_write};
+use crate::commands::sizer::git_sizer;
+use crate::commands::cat_file::{git_cat_file, git_cat_file_batch, CatFileMode};
+use crate::commands::commit_graph::git_commit_graph_write;
+use crate::commands::count_objects::git_count_objects;
+use crate::commands::fsck::git_fsck;
+use crate::commands::gc::{git_gc, git_prune};
+use crate::commands::ls_tree::{git_ls_tree, LsTreeOptions};
+use crate::commands::rev_list::{git_rev_list, RevListOptions};
+use crate::commands::tag::{git_tag_create, git_tag_delete, git_tag_list};
+use crate::core::pack_writer::PackOptions;
+use crate::utils::fs::remove_locks_on_signal;
+use crate::utils::hash::HashAlgorithm;
 use crate::utils::fs::get_repo_path; // 需要你在 utils/fs.rs 实现
 
 pub fn git_execute() {
+    // === 被中断时清理 index.lock 等锁文件 ===
+    remove_locks_on_signal();
+
     // === 解析命令行参数 ===
     let matches = git_parse_args();
 
     // === 统一获取 repo_path（非 init 命令） ===
     let repo_path = match matches.subcommand_name() {
         Some("init") => None,
-        Some(_) => Some(get_repo_path().expect("❌ Not a git repository")),
+        Some(_) => {
+            let path = get_repo_path().expect("❌ Not a git repository");
+            // 与 Git 一样，对象格式无法识别的仓库拒绝打开
+            if let Err(e) = HashAlgorithm::try_for_repo(path.to_str().unwrap()) {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+            Some(path)
+        }
         None => None,
     };
 
@@ -30,7 +51,9 @@ pub fn git_execute() {
                 .get_one::<String>("path")
                 .map(|s| s.as_str())
                 .unwrap_or(".");
-            git_init(path);
+            let object_format = sub_m.get_one::<String>("object_format").unwrap();
+            let reference = sub_m.get_one::<String>("reference").map(|s| s.as_str());
+            git_init(path, object_format, reference);
         }
 
         // ------------------ add ------------------
@@ -79,6 +102,134 @@ pub fn git_execute() {
             git_checkout(&repo_path.unwrap(), target, create_new);
         }
 
+        // ------------------ tag ------------------
+        Some(("tag", sub_m)) => {
+            let names: Vec<String> = sub_m
+                .get_many::<String>("tag_name")
+                .map(|v| v.map(|s| s.to_string()).collect())
+                .unwrap_or_default();
+            let message = sub_m.get_one::<String>("message").map(|s| s.as_str());
+
+            if sub_m.get_flag("delete") {
+                git_tag_delete(&repo_path.unwrap(), &names);
+            } else if sub_m.get_flag("list") || names.is_empty() {
+                git_tag_list(&repo_path.unwrap(), names.first().map(|s| s.as_str()));
+            } else if names.len() > 2 {
+                println!("❌ Too many arguments. Usage: tag <name> [<commit>]");
+            } else {
+                git_tag_create(
+                    &repo_path.unwrap(),
+                    &names[0],
+                    names.get(1).map(|s| s.as_str()),
+                    sub_m.get_flag("annotate"),
+                    message,
+                    sub_m.get_flag("force"),
+                );
+            }
+        }
+
+        // ------------------ repack ------------------
+        Some(("repack", sub_m)) => {
+            let opts = PackOptions {
+                window: *sub_m.get_one::<usize>("window").unwrap(),
+                depth: *sub_m.get_one::<usize>("depth").unwrap(),
+            };
+            git_repack(&repo_path.unwrap(), sub_m.get_flag("delete"), &opts, sub_m.get_flag("write_bitmap"));
+        }
+
+        // ------------------ multi-pack-index ------------------
+        Some(("multi-pack-index", sub_m)) => match sub_m.subcommand() {
+            Some(("write", _)) => git_multi_pack_index_write(&repo_path.unwrap()),
+            Some(("verify", _)) if !git_multi_pack_index_verify(&repo_path.unwrap()) => std::process::exit(1),
+            _ => {}
+        },
+
+        // ------------------ cat-file ------------------
+        Some(("cat-file", sub_m)) => {
+            let repo_path = repo_path.unwrap();
+            if sub_m.get_flag("batch") || sub_m.get_flag("batch_check") {
+                git_cat_file_batch(&repo_path, sub_m.get_flag("batch"));
+                return;
+            }
+
+            let mode = if sub_m.get_flag("type") {
+                CatFileMode::Type
+            } else if sub_m.get_flag("size") {
+                CatFileMode::Size
+            } else if sub_m.get_flag("exists") {
+                CatFileMode::Exists
+            } else {
+                CatFileMode::Pretty
+            };
+            let object = sub_m.get_one::<String>("object").expect("Missing <object>");
+            if !git_cat_file(&repo_path, mode, object) {
+                std::process::exit(1);
+            }
+        }
+
+        // ------------------ ls-tree ------------------
+        Some(("ls-tree", sub_m)) => {
+            let tree_ish = sub_m.get_one::<String>("tree_ish").expect("Missing <tree-ish>");
+            let paths: Vec<String> = sub_m
+                .get_many::<String>("paths")
+                .map(|v| v.map(|s| s.to_string()).collect())
+                .unwrap_or_default();
+            let opts = LsTreeOptions {
+                recursive: sub_m.get_flag("recursive"),
+                show_trees: sub_m.get_flag("show_trees"),
+                long: sub_m.get_flag("long"),
+                name_only: sub_m.get_flag("name_only"),
+            };
+            if !git_ls_tree(&repo_path.unwrap(), tree_ish, &paths, opts) {
+                std::process::exit(1);
+            }
+        }
+
+        // ------------------ rev-list ------------------
+        Some(("rev-list", sub_m)) => {
+            let commit = sub_m.get_one::<String>("commit").unwrap();
+            let path = sub_m.get_one::<String>("path").map(String::as_str);
+            let opts = RevListOptions {
+                objects: sub_m.get_flag("objects"),
+                count: sub_m.get_flag("count"),
+            };
+            if !git_rev_list(&repo_path.unwrap(), commit, path, opts) {
+                std::process::exit(1);
+            }
+        }
+
+        // ------------------ fsck ------------------
+        Some(("fsck", sub_m)) => {
+            if !git_fsck(&repo_path.unwrap(), sub_m.get_flag("unreachable")) {
+                std::process::exit(1);
+            }
+        }
+
+        // ------------------ gc / prune ------------------
+        Some(("gc", sub_m)) => {
+            let prune = sub_m.get_one::<String>("prune").map(|s| s.as_str());
+            git_gc(&repo_path.unwrap(), prune, sub_m.get_flag("dry_run"));
+        }
+        Some(("prune", sub_m)) => {
+            let expire = sub_m.get_one::<String>("expire").map(|s| s.as_str());
+            git_prune(&repo_path.unwrap(), expire, sub_m.get_flag("dry_run"));
+        }
+
+        // ------------------ count-objects / sizer ------------------
+        Some(("count-objects", sub_m)) => {
+            git_count_objects(&repo_path.unwrap(), sub_m.get_flag("verbose"));
+        }
+        Some(("sizer", sub_m)) => {
+            git_sizer(&repo_path.unwrap(), *sub_m.get_one::<usize>("top").unwrap());
+        }
+
+        // ------------------ commit-graph ------------------
+        Some(("commit-graph", sub_m)) => {
+            if let Some(("write", write_m)) = sub_m.subcommand() {
+                git_commit_graph_write(&repo_path.unwrap(), write_m.get_flag("changed_paths"));
+            }
+        }
+
         // // ------------------ merge ------------------
         // Some(("merge", sub_m)) => {
         //     let branch_name = sub_m.get_one::<String>("branch_name").expect("Missing <branch>");
@@ -1,6 +1,9 @@
+use crate::core::blob::BlobProcessor;
 use crate::core::index::Index;
-use crate::core::object::Object;
-use crate::utils::fs::read_file_bytes;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::utils::fs::path_to_bytes;
+use std::fs::File;
+use std::io;
 use std::path::Path;
 use walkdir::WalkDir; // ✅ 需要在 Cargo.toml 中添加依赖：walkdir = "2"
 
@@ -15,7 +18,15 @@ use walkdir::WalkDir; // ✅ 需要在 Cargo.toml 中添加依赖：walkdir = "2
 /// - `repo_path`: 仓库根路径
 /// - `files`: 要添加的文件或目录路径列表
 pub fn git_add(repo_path: &Path, files: &[String]) {
-    let mut index = Index::load(repo_path);
+    // 整个读取-修改-写回过程持有 index.lock
+    let mut index = match Index::lock(repo_path) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    let db = LooseObjectDatabase::new(repo_path.to_str().unwrap());
 
     for file in files {
         let path = Path::new(file);
@@ -25,43 +36,55 @@ pub fn git_add(repo_path: &Path, files: &[String]) {
             for entry in WalkDir::new(path)
                 .into_iter()
                 .filter_map(Result::ok)
-                .filter(|e| e.file_type().is_file())
+                .filter(|e| e.file_type().is_file() || e.file_type().is_symlink())
             {
                 let file_path = entry.path();
                 // ❌ 排除 .git 目录
                 if file_path.components().any(|c| c.as_os_str() == ".git") {
                     continue;
                 }
-                stage_single_file(repo_path, file_path, &mut index);
+                stage_single_file(&db, file_path, &mut index);
             }
         } else {
             // ✅ 单个文件
-            stage_single_file(repo_path, path, &mut index);
+            stage_single_file(&db, path, &mut index);
         }
     }
 
+    if let Err(e) = index.save() {
+        eprintln!("❌ {}", e);
+        return;
+    }
     println!("✅ Added {} file(s) to staging area", index.entries.len());
 }
 
 /// 单文件暂存逻辑
-fn stage_single_file(repo_path: &Path, file_path: &Path, index: &mut Index) {
-    if !file_path.exists() {
+fn stage_single_file(db: &dyn ObjectDatabase, file_path: &Path, index: &mut Index) {
+    let Ok(metadata) = file_path.symlink_metadata() else {
         eprintln!("⚠️  Skipped: file not found '{}'", file_path.display());
         return;
-    }
+    };
 
-    // 1️⃣ 读取文件内容
-    let content = match read_file_bytes(file_path.to_str().unwrap()) {
-        Ok(c) => c,
+    // 1️⃣ 打开文件（大文件不整体读入内存）；符号链接不跟随，保存的是链接目标路径
+    // 2️⃣ 边读边计算哈希并压缩写入 Blob 对象
+    let result = if metadata.file_type().is_symlink() {
+        std::fs::read_link(file_path).and_then(|target| {
+            BlobProcessor::create_blob(db, path_to_bytes(&target)).map_err(|e| io::Error::other(e.to_string()))
+        })
+    } else {
+        File::open(file_path).and_then(|mut file| {
+            let size = file.metadata()?.len();
+            BlobProcessor::create_blob_from_reader(db, &mut file, size)
+        })
+    };
+    let sha = match result {
+        Ok(sha) => sha,
         Err(e) => {
             eprintln!("⚠️  Failed to read '{}': {}", file_path.display(), e);
             return;
         }
     };
 
-    // 2️⃣ 保存 Blob 对象
-    let sha = Object::Blob(content.clone()).save(repo_path.to_str().unwrap());
-
     // 3️⃣ 更新 Index
     index.stage_file(file_path, &sha);
     println!("✅ Staged file: {}", file_path.display());
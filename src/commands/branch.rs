@@ -14,6 +14,7 @@ use std::path::Path;
 /// - 每个分支对应 `.git/refs/heads/<branch>` 文件，
 ///   文件内容为该分支当前指向的 commit 哈希。
 /// - `.git/HEAD` 文件保存当前检出分支引用（例如：`ref: refs/heads/master`）
+///
 /// ======================================
 pub fn git_branch(repo_path: &Path, branch_name: Option<&str>, delete: bool) {
     let refs_heads_path = repo_path.join(".git/refs/heads");
@@ -26,7 +27,7 @@ pub fn git_branch(repo_path: &Path, branch_name: Option<&str>, delete: bool) {
             Ok(branches) => {
                 // 尝试读取 HEAD 文件内容（可能为空或损坏）
                 let head_ref_path = repo_path.join(".git/HEAD");
-                let head_ref = fs::read_file(&head_ref_path.to_str().unwrap())
+                let head_ref = fs::read_file(head_ref_path.to_str().unwrap())
                     .unwrap_or_default()
                     .trim()
                     .to_string();
@@ -73,7 +74,7 @@ pub fn git_branch(repo_path: &Path, branch_name: Option<&str>, delete: bool) {
 
         // 读取 HEAD 以判断当前分支
         let head_ref_path = repo_path.join(".git/HEAD");
-        let head_ref = fs::read_file(&head_ref_path.to_str().unwrap()).unwrap_or_default();
+        let head_ref = fs::read_file(head_ref_path.to_str().unwrap()).unwrap_or_default();
         let current_branch = head_ref
             .strip_prefix("ref: refs/heads/")
             .unwrap_or("master")
@@ -100,7 +101,7 @@ pub fn git_branch(repo_path: &Path, branch_name: Option<&str>, delete: bool) {
 
         // 尝试读取 HEAD 文件
         let head_ref_path = repo_path.join(".git/HEAD");
-        let head_ref = fs::read_file(&head_ref_path.to_str().unwrap())
+        let head_ref = fs::read_file(head_ref_path.to_str().unwrap())
             .unwrap_or_default()
             .trim()
             .to_string();
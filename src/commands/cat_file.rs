@@ -0,0 +1,159 @@
+use crate::core::object::Object;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::reference::Reference;
+use crate::core::tree::TreeProcessor;
+use crate::utils::fs::quote_path;
+use crate::utils::hash::HashAlgorithm;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// cat-file 的输出模式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatFileMode {
+    /// `-t`：输出对象类型
+    Type,
+    /// `-s`：输出对象大小
+    Size,
+    /// `-p`：按类型美化输出对象内容
+    Pretty,
+    /// `-e`：只检查对象是否存在（通过返回值表示）
+    Exists,
+}
+
+/// git cat-file 命令（查看对象类型、大小和内容）
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `mode`: 输出模式
+/// - `object`: 对象名（完整或短哈希、分支、标签等）
+///
+/// # 返回
+/// - 是否成功（对象不存在时为 false，调用方据此设置退出码）
+pub fn git_cat_file(repo_path: &Path, mode: CatFileMode, object: &str) -> bool {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    let sha = match Reference::try_resolve_revision(repo, object) {
+        Ok(sha) => sha,
+        Err(e) => {
+            if mode != CatFileMode::Exists {
+                eprintln!("❌ {}", e);
+            }
+            return false;
+        }
+    };
+    let Some(obj) = db.read(&sha) else {
+        if mode != CatFileMode::Exists {
+            eprintln!("❌ Object {} not found", sha);
+        }
+        return false;
+    };
+
+    let mut out = io::stdout().lock();
+    let result = match mode {
+        CatFileMode::Type => writeln!(out, "{}", obj.type_name()),
+        CatFileMode::Size => writeln!(out, "{}", obj.data().len()),
+        CatFileMode::Pretty => write_pretty(&mut out, &obj, db.hash_algorithm()),
+        CatFileMode::Exists => Ok(()),
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("❌ {}: {}", sha, e);
+            false
+        }
+    }
+}
+
+/// git cat-file --batch / --batch-check（从标准输入逐行读取对象名）
+///
+/// 每个对象输出 `<sha> <type> <size>`；`with_contents` 为 true 时（--batch）
+/// 随后输出对象原始内容和一个换行。找不到的对象输出 `<name> missing`。
+pub fn git_cat_file_batch(repo_path: &Path, with_contents: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+    let stdin = io::stdin();
+    let mut out = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let name = line.trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let found = Reference::resolve_revision(repo, name)
+            .and_then(|sha| db.read(&sha).map(|obj| (sha, obj)));
+        let written = match found {
+            Some((sha, obj)) => {
+                let mut r = writeln!(out, "{} {} {}", sha, obj.type_name(), obj.data().len());
+                if with_contents {
+                    r = r
+                        .and_then(|_| out.write_all(obj.data()))
+                        .and_then(|_| writeln!(out));
+                }
+                r
+            }
+            None => writeln!(out, "{} missing", name),
+        };
+        // 批处理模式下调用方通常通过管道读取，需要及时刷新
+        if written.and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+}
+
+/// 按对象类型美化输出
+fn write_pretty(out: &mut impl Write, obj: &Object, algo: HashAlgorithm) -> io::Result<()> {
+    match obj {
+        Object::Tree(data) => {
+            let entries = TreeProcessor::try_parse_tree(data, algo)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt tree object"))?;
+            for entry in entries {
+                writeln!(
+                    out,
+                    "{:06o} {} {}\t{}",
+                    entry.mode,
+                    entry.object_type(),
+                    entry.hash,
+                    quote_path(&entry.name)
+                )?;
+            }
+            Ok(())
+        }
+        // blob / commit / tag 直接输出原始内容
+        other => out.write_all(other.data()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+
+    #[test]
+    fn pretty_printing_corrupt_tree_fails_instead_of_panicking() {
+        let repo = TempRepo::new("cat-file-corrupt-tree");
+        // 条目缺少 NUL 和哈希
+        let tree = Object::Tree(b"100644 truncated".to_vec());
+        let sha = tree.save(repo.path()).unwrap();
+
+        let mut out = Vec::new();
+        let err = write_pretty(&mut out, &tree, HashAlgorithm::Sha1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!git_cat_file(Path::new(repo.path()), CatFileMode::Pretty, &sha));
+    }
+
+    #[test]
+    fn pretty_printing_quotes_non_utf8_names() {
+        let blob = "587be6b4c3f93f93c489c0111bba5596147a26cb";
+        let mut data = b"100644 caf\xe9\0".to_vec();
+        data.extend(hex::decode(blob).unwrap());
+
+        let mut out = Vec::new();
+        write_pretty(&mut out, &Object::Tree(data), HashAlgorithm::Sha1).unwrap();
+        // 与 `git cat-file -p` 的输出一致
+        assert_eq!(String::from_utf8(out).unwrap(), format!("100644 blob {}\t\"caf\\351\"\n", blob));
+    }
+}
@@ -1,4 +1,5 @@
-use crate::core::{index::Index, object::Object, reference::Reference, tree::TreeProcessor};
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::{commit::Commit, index::Index, reference::Reference, tree::TreeProcessor};
 use crate::utils::fs;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
@@ -11,14 +12,26 @@ use std::path::{Path, PathBuf};
 /// 3. 检查工作区干净  
 /// 4. 同步 HEAD、index、工作区，删除 commit 中没有的文件和空目录
 pub fn git_checkout(repo_path: &Path, target: &str, create_new: bool) {
-    // ------------------ 1️⃣ 检查工作区是否干净 ------------------
-    if !is_workdir_clean(repo_path) {
+    // ------------------ 1️⃣ 锁定 index，检查工作区是否干净 ------------------
+    // 从检查到写回 index 一直持有 index.lock，避免其他进程同时修改暂存区
+    let mut index = match Index::lock(repo_path) {
+        Ok(index) => index,
+        Err(e) => {
+            println!("❌ {}", e);
+            return;
+        }
+    };
+    if index.has_conflicts() {
+        println!("❌ You need to resolve your current index first");
+        return;
+    }
+    if !is_workdir_clean(&index) {
         panic!("⚠️ Cannot checkout: working directory has uncommitted changes");
     }
 
     // ------------------ 2️⃣ 获取当前 HEAD ------------------
     let head_path = repo_path.join(".git/HEAD");
-    let head_ref = fs::read_file(&head_path.to_str().unwrap())
+    let head_ref = fs::read_file(head_path.to_str().unwrap())
         .unwrap_or_default()
         .trim()
         .to_string();
@@ -28,66 +41,61 @@ pub fn git_checkout(repo_path: &Path, target: &str, create_new: bool) {
     let target_branch_ref = format!("refs/heads/{}", target);
 
     // ------------------ 4️⃣ 获取目标 commit SHA ------------------
+    let branch_path = repo_path.join(".git").join(&target_branch_ref);
+    let is_branch = create_new || branch_path.exists();
     let target_commit_sha = if create_new {
         // 新分支基于当前分支最新 commit
         let base_commit = Reference::resolve(repo_path.to_str().unwrap(), current_branch_ref)
             .expect("Cannot create branch: current branch has no commits");
         Reference::create(repo_path.to_str().unwrap(), &target_branch_ref, &base_commit);
         base_commit
+    } else if is_branch {
+        // 目标是分支
+        Reference::resolve(repo_path.to_str().unwrap(), &target_branch_ref)
+            .expect("Target branch has no commit")
     } else {
-        let branch_path = repo_path.join(".git").join(&target_branch_ref);
-        if branch_path.exists() {
-            // 目标是分支
-            Reference::resolve(repo_path.to_str().unwrap(), &target_branch_ref)
-                .expect("Target branch has no commit")
-        } else {
-            // 目标是 commit SHA
-            target.to_string()
+        // 目标是标签、HEAD、完整或短 commit SHA（附注标签会被剥离到其指向的提交）
+        match Reference::try_resolve_commit(repo_path.to_str().unwrap(), target) {
+            Ok(sha) => sha,
+            Err(e) => {
+                println!("❌ {}", e);
+                return;
+            }
         }
     };
     println!("Target commit SHA: {}", target_commit_sha);
 
     // ------------------ 5️⃣ 移动 HEAD ------------------
-    let new_head_content = if create_new || target_branch_ref.starts_with("refs/heads/") {
+    let new_head_content = if is_branch {
         format!("ref: {}", target_branch_ref)
     } else {
         target_commit_sha.clone() // detached HEAD
     };
-    fs::write_file_bytes(&head_path.to_str().unwrap(), new_head_content.as_bytes())
-        .expect("Failed to update HEAD");
+    Reference::create(repo_path.to_str().unwrap(), "HEAD", &new_head_content);
 
     // ------------------ 6️⃣ 更新 index 和工作区 ------------------
-    restore_index_and_workdir(repo_path, &target_commit_sha);
+    restore_index_and_workdir(repo_path, &mut index, &target_commit_sha);
 
     println!("✅ Checked out {}", target);
 }
 
 /// 检查工作区是否干净（工作区与 index 比对）
-fn is_workdir_clean(repo_path: &Path) -> bool {
-    let index = Index::load(repo_path);
-    for entry in index.entries.values() {
-        if let Ok(content) = fs::read_file_bytes(&entry.path.to_str().unwrap()) {
-            let sha = Object::Blob(content).save(repo_path.to_str().unwrap());
-            if sha != entry.sha {
-                return false;
-            }
-        }
-    }
-    true
+///
+/// stat 信息未变的文件不读取内容；已删除的文件不算未提交的修改（检出时会被恢复）。
+fn is_workdir_clean(index: &Index) -> bool {
+    index
+        .entries
+        .values()
+        .all(|entry| index.repo_path.join(&entry.path).symlink_metadata().is_err() || !index.is_modified(entry))
 }
 
 /// 更新 index 和工作区，使其与目标 commit 对齐，同时删除多余文件和空目录
-fn restore_index_and_workdir(repo_path: &Path, commit_sha: &str) {
+fn restore_index_and_workdir(repo_path: &Path, index: &mut Index, commit_sha: &str) {
     // 1️⃣ 加载 commit 对应 tree
-    let commit_obj = Object::load(repo_path.to_str().unwrap(), commit_sha)
+    let db = LooseObjectDatabase::new(repo_path.to_str().unwrap());
+    let commit = Commit::load(&db, commit_sha)
         .expect("Failed to load commit object");
-    let commit_content = String::from_utf8(commit_obj).unwrap();
-    let tree_sha = commit_content
-        .lines()
-        .find(|l| l.starts_with("tree "))
-        .expect("Commit object missing tree")
-        .strip_prefix("tree ")
-        .unwrap();
+    let tree_sha = commit.tree.as_str();
     println!("Restoring tree: {}", tree_sha);
 
     // 2️⃣ 记录工作区现有文件和目录（排除 .git）
@@ -107,12 +115,14 @@ fn restore_index_and_workdir(repo_path: &Path, commit_sha: &str) {
     }
 
     // 3️⃣ 清空 index
-    let mut index = Index::load(repo_path);
     index.clear();
 
-    // 4️⃣ 递归恢复 tree 到工作区并更新 index
+    // 4️⃣ 递归恢复 tree 到工作区并更新 index（写回后释放 index.lock）
     let mut commit_paths = HashSet::new();
-    restore_tree(repo_path, Path::new("."), tree_sha, &mut index, &mut commit_paths);
+    restore_tree(&db, Path::new("."), tree_sha, index, &mut commit_paths);
+    if let Err(e) = index.save() {
+        println!("❌ {}", e);
+    }
 
     // 5️⃣ 删除工作区中不属于 commit 的文件和空目录
     //    先删除文件，再尝试删除空目录
@@ -132,19 +142,16 @@ fn restore_index_and_workdir(repo_path: &Path, commit_sha: &str) {
 /// 递归恢复 tree
 /// - 目录和文件都会加入 commit_paths，用于后续删除未在 commit 中的路径
 fn restore_tree(
-    repo_path: &Path,
+    db: &dyn ObjectDatabase,
     current_dir: &Path,
     tree_sha: &str,
     index: &mut Index,
     commit_paths: &mut HashSet<PathBuf>,
 ) {
-    let tree_obj = Object::load(repo_path.to_str().unwrap(), tree_sha)
-        .expect("Failed to load tree object");
-
-    let entries = TreeProcessor::parse_tree(&tree_obj);
+    let entries = TreeProcessor::read_tree(db, tree_sha).expect("Failed to load tree object");
 
     for entry in entries {
-        let path = current_dir.join(&entry.name);
+        let path = current_dir.join(fs::path_from_bytes(&entry.name));
         println!(
             "Restoring {}: {}",
             if entry.is_dir { "dir" } else { "file" },
@@ -154,14 +161,34 @@ fn restore_tree(
         if entry.is_dir {
             fs::create_dir_all(&path).expect("Failed to create directory");
             commit_paths.insert(path.clone()); // 目录也加入 commit_paths
-            restore_tree(repo_path, &path, &entry.hash, index, commit_paths);
+            restore_tree(db, &path, &entry.hash, index, commit_paths);
+        } else if entry.mode == 0o120000 {
+            let target = db.read(&entry.hash).expect("Failed to load blob object").into_data();
+            write_symlink(&target, &path).expect("Failed to create symlink");
+            index.stage_file(&path, &entry.hash);
+            commit_paths.insert(path);
         } else {
-            let blob_obj = Object::load(repo_path.to_str().unwrap(), &entry.hash)
-                .expect("Failed to load blob object");
-            fs::write_file_bytes(&path.to_str().unwrap(), &blob_obj)
-                .expect("Failed to write file");
+            // 边解压边写出，大文件不整体读入内存
+            let mut blob = db.read_stream(&entry.hash).expect("Failed to load blob object");
+            let mut file = std::fs::File::create(&path).expect("Failed to write file");
+            std::io::copy(&mut blob, &mut file).expect("Failed to write file");
             index.stage_file(&path, &entry.hash);
             commit_paths.insert(path); // 文件加入 commit_paths
         }
     }
 }
+
+/// 检出符号链接：blob 内容即链接目标
+///
+/// 不支持符号链接的平台上写成内容为目标路径的普通文件（与 Git 的 `core.symlinks = false` 相同）。
+fn write_symlink(target: &[u8], path: &Path) -> std::io::Result<()> {
+    let _ = std::fs::remove_file(path);
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(fs::path_from_bytes(target), path)
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, target)
+    }
+}
@@ -1,10 +1,8 @@
-use crate::core::object::Object;
+use crate::core::commit::{Commit, Signature};
 use crate::core::index::Index;
-use crate::core::tree::TreeProcessor;
+use crate::core::odb::LooseObjectDatabase;
 use crate::core::reference::Reference;
-use crate::utils::fs::write_file;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /// git commit 命令（提交当前暂存区）
 ///
@@ -18,12 +16,36 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// - `repo_path`: 仓库根路径
 /// - `message`: 提交信息
 pub fn git_commit(repo_path: &Path, message: &str) {
-    // 1️⃣ 加载 index，准备构造 tree
-    let index = Index::load(repo_path);
+    // 1️⃣ 加载 index，准备构造 tree（提交完成前一直持有 index.lock）
+    let mut index = match Index::lock(repo_path) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    let db = LooseObjectDatabase::new(repo_path.to_str().unwrap());
+
+    // 存在未解决的冲突时不能提交（返回时自动释放 index.lock）
+    if index.has_conflicts() {
+        eprintln!("❌ Committing is not possible because you have unmerged files:");
+        for path in index.unmerged_paths() {
+            eprintln!("U\t{}", path.display());
+        }
+        eprintln!("hint: Fix them up in the work tree, and then use 'add/rm <file>'");
+        eprintln!("hint: as appropriate to mark resolution and make a commit.");
+        return;
+    }
 
-    // 2️⃣ 构造 tree 对象内容
+    // 2️⃣ 构造 tree 对象内容（未改动的目录复用 index 中缓存的 tree）
     // 3️⃣ 写入 tree 对象
-    let tree_sha = TreeProcessor::create_tree_from_index(&repo_path.to_str().unwrap(), &index.entries);
+    let tree_sha = match index.write_tree(&db) {
+        Ok(sha) => sha,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
 
     // 4️⃣ 获取当前分支
     let head_path = repo_path.join(".git/HEAD");
@@ -41,25 +63,113 @@ pub fn git_commit(repo_path: &Path, message: &str) {
         None
     };
 
-    // 6️⃣ 构造 commit 对象
-    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-    let mut commit_content = format!("tree {}\n", tree_sha);
-    if let Some(parent_sha) = &parent {
-        commit_content.push_str(&format!("parent {}\n", parent_sha));
+    // 6️⃣ 构造 commit 对象（提交信息与 Git 一样以换行结尾）
+    let mut message = message.to_string();
+    if !message.ends_with('\n') {
+        message.push('\n');
     }
-    commit_content.push_str(&format!("author You <you@example.com> {}\n\n{}", timestamp, message));
+    let commit = Commit::new(
+        tree_sha,
+        parent.into_iter().collect(),
+        Signature::from_env("AUTHOR"),
+        Signature::from_env("COMMITTER"),
+        message.into_bytes(),
+    );
 
     // 7️⃣ 保存 commit 对象
-    let commit_sha = Object::Commit(commit_content.as_bytes().to_vec()).save(repo_path.to_str().unwrap());
+    let commit_sha = match commit.save(&db) {
+        Ok(sha) => sha,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
 
     // 8️⃣ 更新分支引用（若 HEAD 是分支）
     if is_branch {
-        let ref_path = repo_path.join(".git").join(&branch_name);
-        write_file(ref_path.to_str().unwrap(), &commit_sha).expect("Failed to update branch ref");
+        Reference::create(repo_path.to_str().unwrap(), &branch_name, &commit_sha);
         println!("✅ Commit saved to branch '{}': {}", branch_name, commit_sha);
     } else {
         // Detached HEAD
-        write_file(head_path.to_str().unwrap(), &commit_sha).expect("Failed to update HEAD");
+        Reference::create(repo_path.to_str().unwrap(), "HEAD", &commit_sha);
         println!("⚠️ Detached HEAD now at {}", commit_sha);
     }
+
+    // 9️⃣ 写回 index（保存本次生成的 tree 缓存）并释放锁
+    if let Err(e) = index.save() {
+        eprintln!("⚠️ {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::add::git_add;
+    use crate::core::object::Object;
+    use crate::core::repository::Repository;
+    use crate::core::tree::TreeProcessor;
+    use crate::utils::fs::TempRepo;
+    use crate::utils::hash::HashAlgorithm;
+
+    #[test]
+    fn sha256_repository_add_commit_and_read_back() {
+        let repo = TempRepo::new("sha256-commit");
+        Repository::init_with_format(repo.path(), HashAlgorithm::Sha256).unwrap();
+        let repo_path = Path::new(repo.path());
+        let file = repo_path.join("hello.txt");
+        std::fs::write(&file, b"hello sha256\n").unwrap();
+
+        git_add(repo_path, &[file.to_str().unwrap().to_string()]);
+        git_commit(repo_path, "first");
+
+        assert_eq!(HashAlgorithm::for_repo(repo.path()), HashAlgorithm::Sha256);
+        let head = Reference::resolve_head(repo.path()).expect("HEAD points to the new commit");
+        assert_eq!(head.len(), 64);
+
+        let commit = Commit::load(&LooseObjectDatabase::new(repo.path()), &head).unwrap();
+        assert_eq!(commit.message, b"first\n");
+        let tree = Object::load_object(repo.path(), &commit.tree).unwrap();
+        let entries = TreeProcessor::try_parse_tree(tree.data(), HashAlgorithm::Sha256).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, b"hello.txt");
+        // 与 SHA-256 仓库中 `git hash-object` 的结果一致
+        assert_eq!(entries[0].hash, "2928cdcdc8b78c930378ceba09ce9ca8b888fbfe1bffb2cceb42bdff9421cb52");
+        let blob = Object::load_object(repo.path(), &entries[0].hash).unwrap();
+        assert_eq!(blob.data(), b"hello sha256\n");
+    }
+
+    #[test]
+    fn sha256_repository_nested_tree_matches_git() {
+        let repo = TempRepo::new("sha256-nested");
+        Repository::init_with_format(repo.path(), HashAlgorithm::Sha256).unwrap();
+        let repo_path = Path::new(repo.path());
+        std::fs::create_dir(repo_path.join("dir")).unwrap();
+        std::fs::write(repo_path.join("a.txt"), b"a\n").unwrap();
+        std::fs::write(repo_path.join("dir/b.txt"), b"b\n").unwrap();
+
+        let files = ["a.txt", "dir/b.txt"].map(|f| repo_path.join(f).to_str().unwrap().to_string());
+        git_add(repo_path, &files);
+        // 与 SHA-256 仓库中 `git ls-files -s` 的结果一致
+        let index = Index::load(repo_path);
+        assert_eq!(index.entries[Path::new("a.txt")].sha, "f8625e43f9e04f24291f77cdbe4c71b3c2a3b0003f60419b3ed06a058d766c8b");
+        assert_eq!(index.entries[Path::new("dir/b.txt")].sha, "9b69d308c97f2c5933fdd0e8ce04acce91c09cb969e36a1f86756fc5a5d3323a");
+
+        git_commit(repo_path, "nested");
+        let db = LooseObjectDatabase::new(repo.path());
+        let commit = Commit::load(&db, &Reference::resolve_head(repo.path()).unwrap()).unwrap();
+        // 与 `git write-tree` 的结果一致
+        assert_eq!(commit.tree, "2414ecbc21dd32504739c1d3dd248d2e6518582e48cf98cb75576b90e2de3add");
+        let entries = TreeProcessor::read_tree(&db, &commit.tree).unwrap();
+        let names: Vec<(&[u8], &str)> = entries.iter().map(|e| (e.name.as_slice(), e.hash.as_str())).collect();
+        let dir = "159b5a6f699657e18469156e40e1b92fca0cd55d3091cb4e630705c7fe7f7299";
+        assert_eq!(
+            names,
+            [(&b"a.txt"[..], "f8625e43f9e04f24291f77cdbe4c71b3c2a3b0003f60419b3ed06a058d766c8b"), (b"dir", dir)]
+        );
+        let sub = TreeProcessor::read_tree(&db, dir).unwrap();
+        assert_eq!(sub[0].name, b"b.txt");
+
+        // 提交后写回的 index 中，TREE 扩展记录了同一个根 tree
+        assert_eq!(Index::load(repo_path).cache_tree.sha.as_deref(), Some(commit.tree.as_str()));
+    }
 }
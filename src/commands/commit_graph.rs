@@ -0,0 +1,30 @@
+use crate::core::commit_graph::{write_commit_graph, CommitGraph};
+use crate::core::odb::LooseObjectDatabase;
+use crate::core::walk::{reachable_commits, ref_roots};
+use std::path::Path;
+
+/// git commit-graph write 命令（为所有可达提交写出 `.git/objects/info/commit-graph`）
+///
+/// commit-graph 记录每个提交的父提交、根 tree、提交时间和拓扑层级，
+/// 之后遍历历史（gc、repack、sizer 等）时无需逐个解压和解析提交对象。
+/// 文件格式与 Git 相同，可以用 `git commit-graph verify` 校验。
+///
+/// `changed_paths` 为 true 时为每个提交计算 changed-path Bloom 过滤器（BIDX / BDAT），
+/// 按路径查询历史时可跳过一定没有改动该路径的提交；已有的 commit-graph
+/// 带有过滤器时，重新写入也会保留。
+pub fn git_commit_graph_write(repo_path: &Path, changed_paths: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    let commits = reachable_commits(&db, &ref_roots(repo));
+    if commits.is_empty() {
+        println!("⚠️  No commits to write.");
+        return;
+    }
+
+    let changed_paths = changed_paths || CommitGraph::for_repo(repo).is_some_and(|g| g.has_bloom_filters());
+    match write_commit_graph(repo, &db, &commits, changed_paths) {
+        Ok(count) => println!("✅ Wrote commit-graph with {} commits", count),
+        Err(e) => println!("❌ Failed to write commit-graph: {}", e),
+    }
+}
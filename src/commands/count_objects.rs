@@ -0,0 +1,169 @@
+use crate::core::alternates::{alternate_dirs, objects_dir};
+use crate::core::object::Object;
+use crate::core::pack;
+use crate::utils::hash::HashAlgorithm;
+use std::path::{Path, PathBuf};
+
+/// pack 目录中除 `.pack` / `.idx` 外 Git 认可的辅助文件
+const PACK_AUX_EXTENSIONS: [&str; 5] = ["keep", "rev", "bitmap", "promisor", "mtimes"];
+
+/// git count-objects 命令（统计松散对象和 pack 的数量与磁盘占用）
+///
+/// # 输出
+/// - 默认：`<count> objects, <size> kilobytes`（仅松散对象）
+/// - `verbose`：同 `git count-objects -v`，额外输出 pack 中的对象数、pack 数量与大小、
+///   已被打包而可删除的松散对象数（prune-packable）、对象目录中的无效文件（garbage），
+///   以及 alternates 中的备用对象目录
+pub fn git_count_objects(repo_path: &Path, verbose: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let counts = count_objects(repo, verbose);
+
+    if !verbose {
+        println!("{} objects, {} kilobytes", counts.count, counts.size / 1024);
+        return;
+    }
+
+    for path in &counts.garbage {
+        eprintln!("warning: garbage found: {}", path.display());
+    }
+    println!("count: {}", counts.count);
+    println!("size: {}", counts.size / 1024);
+    println!("in-pack: {}", counts.in_pack);
+    println!("packs: {}", counts.packs);
+    println!("size-pack: {}", counts.size_pack / 1024);
+    println!("prune-packable: {}", counts.prune_packable);
+    println!("garbage: {}", counts.garbage.len());
+    println!("size-garbage: {}", counts.size_garbage / 1024);
+    for dir in alternate_dirs(repo).iter() {
+        println!("alternate: {}", dir.display());
+    }
+}
+
+/// count-objects 的统计结果（大小单位为字节）
+#[derive(Debug, Default)]
+struct ObjectCounts {
+    /// 松散对象个数及占用空间
+    count: usize,
+    size: u64,
+    /// pack 中的对象个数、pack 个数及 pack（含 .idx）大小
+    in_pack: usize,
+    packs: usize,
+    size_pack: u64,
+    /// 已被打包、可以删除的松散对象个数
+    prune_packable: usize,
+    /// 对象目录中的无效文件及其占用空间
+    garbage: Vec<PathBuf>,
+    size_garbage: u64,
+}
+
+/// 统计松散对象；`verbose` 为 true 时还统计 pack 与无效文件
+fn count_objects(repo: &str, verbose: bool) -> ObjectCounts {
+    let objects = objects_dir(repo);
+    let algo = HashAlgorithm::for_repo(repo);
+
+    // 1️⃣ 松散对象
+    let loose = Object::list_loose(repo);
+    let mut counts = ObjectCounts {
+        count: loose.len(),
+        size: loose.iter().map(|sha| file_size(&Object::loose_path(repo, sha))).sum(),
+        ..Default::default()
+    };
+    if !verbose {
+        return counts;
+    }
+    counts.prune_packable = loose.iter().filter(|sha| pack::has_packed_object(repo, sha)).count();
+
+    // 2️⃣ pack
+    let packs = pack::rescan_packs(repo);
+    counts.packs = packs.len();
+    counts.in_pack = packs.iter().map(|p| p.index.len()).sum();
+    counts.size_pack = packs
+        .iter()
+        .map(|p| file_len(&p.pack_path) + file_len(&p.pack_path.with_extension("idx")))
+        .sum();
+
+    // 3️⃣ 对象目录中不属于任何对象或 pack 的文件（例如中断写入留下的临时文件）
+    for entry in walkdir::WalkDir::new(&objects)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let rel = entry.path().strip_prefix(&objects).unwrap();
+        let parts: Vec<String> = rel.iter().map(|c| c.to_string_lossy().to_string()).collect();
+        let valid = match parts.as_slice() {
+            [dir, _] if dir == "info" => true,
+            [dir, name] if dir == "pack" && name == "multi-pack-index" => true,
+            [dir, name] if dir == "pack" => {
+                let ext = Path::new(name).extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+                let has_index = entry.path().with_extension("idx").exists();
+                (ext == "pack" || ext == "idx" || PACK_AUX_EXTENSIONS.contains(&ext.as_str())) && has_index
+            }
+            [fanout, rest] => {
+                fanout.len() == 2
+                    && rest.len() == algo.hex_len() - 2
+                    && format!("{}{}", fanout, rest).chars().all(|c| c.is_ascii_hexdigit())
+            }
+            _ => false,
+        };
+        if !valid {
+            counts.size_garbage += file_size(entry.path());
+            counts.garbage.push(entry.path().to_path_buf());
+        }
+    }
+    counts
+}
+
+/// 文件长度（pack 大小按文件长度统计）
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |m| m.len())
+}
+
+/// 文件实际占用的磁盘空间（与 Git 一样，松散对象按分配的块计算）
+fn file_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::metadata(path) else { return 0 };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        meta.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+
+    #[test]
+    fn counts_loose_and_packed_objects() {
+        let repo = TempRepo::new("count-objects");
+        let dir = pack::pack_dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pack/pack-ec8a003d24bea4e9c52612ad45a951deddae618a");
+        for ext in ["pack", "idx"] {
+            std::fs::copy(fixture.with_extension(ext), dir.join("pack-test").with_extension(ext)).unwrap();
+        }
+        // pack 中的 "x\n" 同时存在松散副本，可以被 prune-packed 删除（save 会跳过已打包的对象，直接写文件）
+        let packed = Object::loose_path(repo.path(), "587be6b4c3f93f93c489c0111bba5596147a26cb");
+        std::fs::create_dir_all(packed.parent().unwrap()).unwrap();
+        std::fs::write(&packed, crate::utils::zlib::compress(&Object::Blob(b"x\n".to_vec()).encode())).unwrap();
+        Object::Blob(b"only loose\n".to_vec()).save(repo.path()).unwrap();
+        std::fs::write(dir.join("tmp_pack_123"), b"interrupted").unwrap();
+
+        let counts = count_objects(repo.path(), false);
+        assert_eq!(counts.count, 2);
+        assert!(counts.size > 0);
+        assert_eq!((counts.in_pack, counts.packs), (0, 0));
+
+        let counts = count_objects(repo.path(), true);
+        assert_eq!(counts.count, 2);
+        assert_eq!((counts.in_pack, counts.packs, counts.prune_packable), (15, 1, 1));
+        let pack_len = file_len(&dir.join("pack-test.pack")) + file_len(&dir.join("pack-test.idx"));
+        assert_eq!(counts.size_pack, pack_len);
+        assert_eq!(counts.garbage, vec![dir.join("tmp_pack_123")]);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::core::commit::Commit;
+use crate::core::object::Object;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::pack;
+use crate::core::tag::Tag;
+use crate::core::tree::{TreeEntry, TreeProcessor};
+use crate::core::walk::{repo_roots, RootSource};
+use crate::utils::fs::read_file_bytes;
+use crate::utils::hash::HashAlgorithm;
+use crate::utils::zlib;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// 对象之间的引用：(被引用对象的哈希, 期望的对象类型)
+type Links = Vec<(String, &'static str)>;
+
+/// git fsck 命令（检查对象库的完整性与连通性）
+///
+/// # 检查内容
+/// - 松散对象能否解压、header 是否合法、内容哈希是否与文件名一致
+/// - pack 校验和是否正确，pack 中每个对象能否读出且哈希正确
+/// - commit / tag / tree 能否解析；tree 条目的模式、名字、排序与重复
+/// - 对象之间的引用是否指向存在且类型正确的对象（broken link / missing）
+/// - 引用（含 packed-refs）、HEAD、暂存区（含 TREE 扩展）与 reflog 是否指向存在的对象
+/// - 从这些起点出发不可达的对象：默认只报告 dangling
+///   （没有被其他不可达对象引用的），`show_unreachable` 为 true 时报告全部
+///
+/// # 返回
+/// - 是否没有发现错误（dangling / unreachable 和警告只是提示，不算错误）
+pub fn git_fsck(repo_path: &Path, show_unreachable: bool) -> bool {
+    let mut out = io::stdout().lock();
+    fsck(repo_path, show_unreachable, &mut out).unwrap_or(false)
+}
+
+/// 执行检查并把结果写入 `out`，返回是否没有发现错误
+fn fsck(repo_path: &Path, show_unreachable: bool, out: &mut impl Write) -> io::Result<bool> {
+    let repo = repo_path.to_str().unwrap();
+    let algo = HashAlgorithm::for_repo(repo);
+    let mut ok = true;
+
+    // 1️⃣ 逐个读取并校验对象，记录类型和对外引用
+    let mut kinds: BTreeMap<String, &'static str> = BTreeMap::new();
+    let mut links: BTreeMap<String, Links> = BTreeMap::new();
+
+    for sha in Object::list_loose(repo) {
+        match read_loose(repo, &sha, algo) {
+            Ok(obj) => {
+                ok &= check_object(out, &sha, &obj, algo, &mut links)?;
+                kinds.insert(sha, obj.type_name());
+            }
+            Err(e) => {
+                writeln!(out, "error: {}: {}", sha, e)?;
+                ok = false;
+            }
+        }
+    }
+
+    for p in pack::rescan_packs(repo).iter() {
+        let name = p.pack_path.file_name().unwrap().to_string_lossy();
+        if !p.verify_checksum() {
+            writeln!(out, "error: {}: pack checksum mismatch", name)?;
+            ok = false;
+        }
+        for i in 0..p.index.len() {
+            let sha = hex::encode(p.index.sha_at(i));
+            let Some(obj) = p.read(repo, p.index.sha_at(i)) else {
+                let offset = p.index.offset_at(i).map_or("?".to_string(), |o| o.to_string());
+                writeln!(out, "error: {}: cannot read object {} at offset {}", name, sha, offset)?;
+                ok = false;
+                continue;
+            };
+            match obj.try_hash(algo) {
+                Ok(actual) if actual == sha => {}
+                Ok(_) => {
+                    writeln!(out, "error: {}: hash mismatch for packed object {}", name, sha)?;
+                    ok = false;
+                    continue;
+                }
+                Err(e) => {
+                    writeln!(out, "error: {}: packed object {}: {}", name, sha, e)?;
+                    ok = false;
+                    continue;
+                }
+            }
+            if let Entry::Vacant(slot) = kinds.entry(sha) {
+                ok &= check_object(out, slot.key(), &obj, algo, &mut links)?;
+                slot.insert(obj.type_name());
+            }
+        }
+    }
+
+    // 2️⃣ 连通性：被引用的对象必须存在且类型正确
+    // 不在本仓库的对象可能借用自 alternates 中的对象目录
+    let db = LooseObjectDatabase::new(repo);
+    let kind_of = |sha: &str| kinds.get(sha).copied().or_else(|| db.read(sha).map(|o| o.type_name()));
+    let mut missing: BTreeMap<String, &'static str> = BTreeMap::new();
+    for (from, targets) in &links {
+        for (to, expected) in targets {
+            match kind_of(to) {
+                Some(actual) if actual == *expected => {}
+                Some(actual) => {
+                    writeln!(
+                        out,
+                        "error in {} {}: {} is a {}, not a {}",
+                        kinds[from], from, to, actual, expected
+                    )?;
+                    ok = false;
+                }
+                None => {
+                    writeln!(out, "broken link from {:>7} {}", kinds[from], from)?;
+                    writeln!(out, "              to {:>7} {}", expected, to)?;
+                    missing.insert(to.clone(), expected);
+                    ok = false;
+                }
+            }
+        }
+    }
+    for (sha, kind) in &missing {
+        writeln!(out, "missing {} {}", kind, sha)?;
+    }
+
+    // 3️⃣ 可达性起点：与 gc 相同（引用、HEAD、暂存区及缓存的 tree、reflog），并检查它们指向的对象
+    let mut roots = Vec::new();
+    let repo_roots = match repo_roots(repo) {
+        Ok(repo_roots) => repo_roots,
+        Err(e) => {
+            writeln!(out, "error: {}", e)?;
+            return Ok(false);
+        }
+    };
+    for root in repo_roots {
+        let kind = kind_of(&root.sha);
+        let problem = match (&root.source, kind) {
+            (RootSource::Ref(name), None) => Some(format!("{}: invalid sha1 pointer {}", name, root.sha)),
+            (RootSource::Ref(name), Some(kind)) if name.starts_with("refs/heads/") && kind != "commit" => {
+                Some(format!("{}: not a commit", name))
+            }
+            (RootSource::Index(path), None) => Some(format!("{}: invalid sha1 pointer in index", path.display())),
+            (RootSource::CacheTree, kind) if kind != Some("tree") => {
+                Some(format!("{}: invalid sha1 pointer in cache-tree", root.sha))
+            }
+            (RootSource::Reflog(name), None) => Some(format!("{}: invalid reflog entry {}", name, root.sha)),
+            _ => None,
+        };
+        match problem {
+            Some(problem) => {
+                writeln!(out, "error: {}", problem)?;
+                ok = false;
+            }
+            None => roots.push(root.sha),
+        }
+    }
+
+    // 4️⃣ 报告不可达对象
+    let reachable = mark_reachable(&roots, &links);
+    let unreachable: BTreeSet<&String> = kinds.keys().filter(|sha| !reachable.contains(*sha)).collect();
+    // 被其他不可达对象引用的对象不是 dangling（它所在的整条链只报告链头）
+    let referenced: HashSet<&String> = unreachable
+        .iter()
+        .filter_map(|sha| links.get(*sha))
+        .flatten()
+        .map(|(to, _)| to)
+        .collect();
+    for sha in &unreachable {
+        if show_unreachable {
+            writeln!(out, "unreachable {} {}", kinds[*sha], sha)?;
+        } else if !referenced.contains(*sha) {
+            writeln!(out, "dangling {} {}", kinds[*sha], sha)?;
+        }
+    }
+
+    Ok(ok)
+}
+
+/// 读取松散对象文件，校验压缩数据、header 以及内容哈希
+fn read_loose(repo_path: &str, sha: &str, algo: HashAlgorithm) -> Result<Object, String> {
+    let path = Object::loose_path(repo_path, sha);
+    let compressed = read_file_bytes(path.to_str().unwrap()).map_err(|e| format!("unable to read object file: {}", e))?;
+    let raw = zlib::decompress(&compressed).map_err(|_| "unable to unpack object".to_string())?;
+    let obj = Object::decode(&raw).ok_or("object corrupt or has invalid header")?;
+    let actual = algo.try_hash(&raw).map_err(|e| e.to_string())?;
+    if actual != sha {
+        return Err(format!("hash mismatch, object content hashes to {}", actual));
+    }
+    Ok(obj)
+}
+
+/// 解析单个对象并检查其格式，把它对其他对象的引用记入 `links`
+///
+/// 返回是否没有发现错误。
+fn check_object(
+    out: &mut impl Write,
+    sha: &str,
+    obj: &Object,
+    algo: HashAlgorithm,
+    links: &mut BTreeMap<String, Links>,
+) -> io::Result<bool> {
+    let targets: Links = match obj {
+        Object::Blob(_) => return Ok(true),
+        Object::Commit(data) => match Commit::parse(data) {
+            Some(commit) => std::iter::once((commit.tree, "tree"))
+                .chain(commit.parents.into_iter().map(|p| (p, "commit")))
+                .collect(),
+            None => {
+                writeln!(out, "error in commit {}: malformed commit object", sha)?;
+                return Ok(false);
+            }
+        },
+        Object::Tag(data) => match Tag::parse(data) {
+            Some(tag) => match pack::type_id(&tag.kind).and_then(pack::type_name) {
+                Some(kind) => vec![(tag.object, kind)],
+                None => {
+                    writeln!(out, "error in tag {}: invalid type '{}'", sha, tag.kind)?;
+                    return Ok(false);
+                }
+            },
+            None => {
+                writeln!(out, "error in tag {}: malformed tag object", sha)?;
+                return Ok(false);
+            }
+        },
+        Object::Tree(data) => match TreeProcessor::try_parse_tree(data, algo) {
+            Some(entries) => {
+                let ok = check_tree_entries(out, sha, &entries)?;
+                links.insert(
+                    sha.to_string(),
+                    entries
+                        .iter()
+                        // 子模块（gitlink）指向其他仓库的提交，不检查连通性
+                        .filter(|e| e.has_valid_mode() && e.mode != 0o160000)
+                        .map(|e| (e.hash.clone(), e.object_type()))
+                        .collect(),
+                );
+                return Ok(ok);
+            }
+            None => {
+                writeln!(out, "error in tree {}: malformed tree object", sha)?;
+                return Ok(false);
+            }
+        },
+    };
+    links.insert(sha.to_string(), targets);
+    Ok(true)
+}
+
+/// 检查 tree 条目：模式、名字、排序与重复
+///
+/// 旧版 Git 写入的组可写文件模式 100664 只给出警告，不算错误（Git 中 badFilemode 也是警告级别）。
+fn check_tree_entries(out: &mut impl Write, sha: &str, entries: &[TreeEntry]) -> io::Result<bool> {
+    let mut problems = Vec::new();
+
+    if entries.iter().any(|e| e.mode == 0o100664) {
+        writeln!(out, "warning in tree {}: badFilemode: contains bad file modes", sha)?;
+    }
+    if entries.iter().any(|e| !e.has_valid_mode()) {
+        problems.push("badFilemode: contains bad file modes");
+    }
+    if entries.iter().any(|e| e.name.is_empty()) {
+        problems.push("emptyName: contains empty pathname");
+    }
+    if entries.iter().any(|e| e.name.contains(&b'/')) {
+        problems.push("fullPathname: contains full pathnames");
+    }
+    if entries.iter().any(|e| e.name == b"." || e.name == b"..") {
+        problems.push("hasDot: contains '.' or '..'");
+    }
+    if entries.iter().any(|e| e.name.eq_ignore_ascii_case(b".git")) {
+        problems.push("hasDotgit: contains '.git'");
+    }
+    let mut names = HashSet::new();
+    if !entries.iter().all(|e| names.insert(e.name.as_slice())) {
+        problems.push("duplicateEntries: contains duplicate file entries");
+    }
+    if entries.windows(2).any(|w| w[0].git_cmp(&w[1]).is_gt()) {
+        problems.push("treeNotSorted: not properly sorted");
+    }
+
+    for problem in &problems {
+        writeln!(out, "error in tree {}: {}", sha, problem)?;
+    }
+    Ok(problems.is_empty())
+}
+
+/// 从起点出发沿对象引用遍历，返回所有可达对象
+fn mark_reachable(roots: &[String], links: &BTreeMap<String, Links>) -> HashSet<String> {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&str> = roots.iter().map(String::as_str).collect();
+    while let Some(sha) = stack.pop() {
+        if !seen.insert(sha.to_string()) {
+            continue;
+        }
+        if let Some(targets) = links.get(sha) {
+            stack.extend(targets.iter().map(|(to, _)| to.as_str()));
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+
+    fn run(repo: &TempRepo) -> (bool, String) {
+        let mut out = Vec::new();
+        let ok = fsck(Path::new(repo.path()), false, &mut out).unwrap();
+        (ok, String::from_utf8(out).unwrap())
+    }
+
+    /// 直接拼出 tree 数据，不经过排序和去重
+    fn raw_tree(entries: &[(&str, &str)]) -> Object {
+        let mut data = Vec::new();
+        for (mode_and_name, sha) in entries {
+            data.extend(mode_and_name.as_bytes());
+            data.push(0);
+            data.extend(hex::decode(sha).unwrap());
+        }
+        Object::Tree(data)
+    }
+
+    #[test]
+    fn reports_hash_mismatch() {
+        let repo = TempRepo::new("fsck-hash-mismatch");
+        let real = Object::Blob(b"real\n".to_vec()).save(repo.path()).unwrap();
+        let claimed = Object::Blob(b"claimed\n".to_vec()).try_hash(HashAlgorithm::Sha1).unwrap();
+        let path = Object::loose_path(repo.path(), &claimed);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::rename(Object::loose_path(repo.path(), &real), &path).unwrap();
+
+        let (ok, out) = run(&repo);
+        assert!(!ok);
+        assert!(out.contains(&format!("error: {}: hash mismatch, object content hashes to {}", claimed, real)), "{}", out);
+    }
+
+    #[test]
+    fn reports_missing_blob() {
+        let repo = TempRepo::new("fsck-missing-blob");
+        let missing = Object::Blob(b"never written\n".to_vec()).try_hash(HashAlgorithm::Sha1).unwrap();
+        let tree = raw_tree(&[("100644 gone.txt", &missing)]).save(repo.path()).unwrap();
+
+        let (ok, out) = run(&repo);
+        assert!(!ok);
+        assert!(out.contains(&format!("broken link from    tree {}\n              to    blob {}\n", tree, missing)), "{}", out);
+        assert!(out.contains(&format!("missing blob {}", missing)), "{}", out);
+    }
+
+    #[test]
+    fn dangling_objects_are_reported_but_not_errors() {
+        let repo = TempRepo::new("fsck-dangling");
+        let blob = Object::Blob(b"dangling\n".to_vec()).save(repo.path()).unwrap();
+        let tree = raw_tree(&[("100644 a.txt", &blob)]).save(repo.path()).unwrap();
+
+        let (ok, out) = run(&repo);
+        assert!(ok, "{}", out);
+        // blob 被不可达的 tree 引用，只报告链头
+        assert_eq!(out, format!("dangling tree {}\n", tree));
+    }
+
+    #[test]
+    fn reports_unsorted_and_duplicate_tree_entries() {
+        let repo = TempRepo::new("fsck-tree-order");
+        let blob = Object::Blob(b"x\n".to_vec()).save(repo.path()).unwrap();
+        let unsorted = raw_tree(&[("100644 b", &blob), ("100644 a", &blob)]).save(repo.path()).unwrap();
+        let duplicate = raw_tree(&[("100644 a", &blob), ("100644 a", &blob)]).save(repo.path()).unwrap();
+
+        let (ok, out) = run(&repo);
+        assert!(!ok);
+        assert!(out.contains(&format!("error in tree {}: treeNotSorted: not properly sorted", unsorted)), "{}", out);
+        assert!(out.contains(&format!("error in tree {}: duplicateEntries: contains duplicate file entries", duplicate)), "{}", out);
+    }
+
+    #[test]
+    fn group_writable_mode_is_only_a_warning() {
+        let repo = TempRepo::new("fsck-100664");
+        let blob = Object::Blob(b"x\n".to_vec()).save(repo.path()).unwrap();
+        let tree = raw_tree(&[("100664 a.txt", &blob)]).save(repo.path()).unwrap();
+
+        let (ok, out) = run(&repo);
+        assert!(ok, "{}", out);
+        assert!(out.contains(&format!("warning in tree {}: badFilemode: contains bad file modes", tree)), "{}", out);
+        // 100664 条目仍按 blob 检查连通性，blob 不是 dangling
+        assert!(!out.contains(&format!("dangling blob {}", blob)), "{}", out);
+    }
+}
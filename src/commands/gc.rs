@@ -0,0 +1,219 @@
+use crate::core::config::Config;
+use crate::core::object::Object;
+use crate::core::odb::LooseObjectDatabase;
+use crate::core::walk::{reachable_set, repo_roots, root_shas};
+use chrono::{Local, NaiveDate, TimeZone};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// gc 未配置 `gc.pruneExpire` 时的默认宽限期（与 Git 相同）
+const DEFAULT_PRUNE_EXPIRE: &str = "2.weeks.ago";
+
+/// git gc 命令（清理不可达的松散对象）
+///
+/// 宽限期依次取自 `--prune=<date>`、配置项 `gc.pruneExpire`，默认 2 周：
+/// 刚写入、尚未被引用的对象（例如正在进行的 add / commit）不会被误删。
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `prune`: 覆盖配置的过期时间
+/// - `dry_run`: 只报告将被删除的对象
+pub fn git_gc(repo_path: &Path, prune: Option<&str>, dry_run: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let expire = match prune {
+        Some(expire) => expire.to_string(),
+        None => Config::load(repo)
+            .get("gc.pruneExpire")
+            .unwrap_or(DEFAULT_PRUNE_EXPIRE)
+            .to_string(),
+    };
+    git_prune(repo_path, Some(&expire), dry_run);
+}
+
+/// git prune 命令（删除不可达且早于过期时间的松散对象）
+///
+/// # 功能
+/// - 从所有引用（含 packed-refs）、HEAD、暂存区（含缓存的 tree）以及 reflog 出发标记可达对象
+/// - 删除修改时间早于 `expire` 的不可达松散对象，并清理空的 fan-out 目录
+/// - `dry_run` 为 true 时只输出 `<sha> <type>`，不删除
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `expire`: 过期时间（`now`、`never`、`2.weeks.ago`、`2024-01-31`、Unix 时间戳），默认 `now`
+/// - `dry_run`: 只报告，不删除
+pub fn git_prune(repo_path: &Path, expire: Option<&str>, dry_run: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let expire = expire.unwrap_or("now");
+    let cutoff = match parse_expiry(expire) {
+        Some(cutoff) => cutoff,
+        None => {
+            println!("❌ Invalid expiry date '{}'", expire);
+            return;
+        }
+    };
+    // never：不删除任何对象
+    let Some(cutoff) = cutoff else { return };
+
+    // 1️⃣ 标记可达对象（有可达性位图时直接由位图得出）
+    // 起点不完整时会误删对象，因此 index 损坏时直接放弃
+    let roots = match repo_roots(repo) {
+        Ok(roots) => root_shas(&roots),
+        Err(e) => {
+            println!("❌ {}", e);
+            return;
+        }
+    };
+    let db = LooseObjectDatabase::new(repo);
+    // 有对象缺失或损坏时可达集合不完整，删除任何对象都可能丢失数据
+    let reachable = match reachable_set(&db, &roots) {
+        Ok(reachable) => reachable,
+        Err(e) => {
+            println!("❌ {}, not pruning", e);
+            return;
+        }
+    };
+
+    // 2️⃣ 删除过期的不可达松散对象
+    let mut pruned = 0;
+    for sha in Object::list_loose(repo) {
+        if reachable.contains(&sha) {
+            continue;
+        }
+        let path = Object::loose_path(repo, &sha);
+        let mtime = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        if mtime > cutoff {
+            continue;
+        }
+
+        if dry_run {
+            let kind = Object::load_object(repo, &sha).map_or("unknown", |o| o.type_name());
+            println!("{} {}", sha, kind);
+        } else if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("❌ Failed to remove {}: {}", path.display(), e);
+            continue;
+        } else {
+            // 目录非空时删除失败，忽略即可
+            let _ = std::fs::remove_dir(path.parent().unwrap());
+        }
+        pruned += 1;
+    }
+
+    if !dry_run {
+        println!("🗑️  Pruned {} unreachable object(s)", pruned);
+    }
+}
+
+/// 解析过期时间，返回 Unix 时间戳；`never` 返回 `Some(None)`，无法解析返回 None
+///
+/// 支持：`now`、`never`、`<n>.<unit>.ago`（或空格分隔）、`YYYY-MM-DD`、Unix 时间戳。
+fn parse_expiry(expire: &str) -> Option<Option<i64>> {
+    let now = Local::now().timestamp();
+    let expire = expire.trim();
+    match expire {
+        "now" | "all" => return Some(Some(now)),
+        "never" | "false" => return Some(None),
+        _ => {}
+    }
+    if let Ok(ts) = expire.parse::<i64>() {
+        return Some(Some(ts));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(expire, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0)?;
+        return Some(Some(Local.from_local_datetime(&midnight).single()?.timestamp()));
+    }
+
+    // 例如 2.weeks.ago / 3 days ago / 1.hour.ago
+    let parts: Vec<&str> = expire.split(['.', ' ']).filter(|s| !s.is_empty()).collect();
+    let [count, unit, "ago"] = parts.as_slice() else { return None };
+    let count: i64 = count.parse().ok()?;
+    let seconds = match unit.trim_end_matches('s') {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" => 3600,
+        "day" => 86400,
+        "week" => 7 * 86400,
+        "month" => 30 * 86400,
+        "year" => 365 * 86400,
+        _ => return None,
+    };
+    Some(Some(now - count * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::odb::ObjectDatabase;
+    use crate::core::tree::{TreeEntry, TreeProcessor};
+    use crate::utils::fs::TempRepo;
+
+    #[test]
+    fn prune_keeps_objects_reachable_only_from_packed_refs() {
+        let repo = TempRepo::new("prune-packed-refs");
+        let db = LooseObjectDatabase::new(repo.path());
+        let blob = db.write(&Object::Blob(b"kept\n".to_vec())).unwrap();
+        let entry = TreeEntry { name: b"kept.txt".to_vec(), hash: blob.clone(), mode: 0o100644, is_dir: false };
+        let tree = TreeProcessor::create_tree(&db, vec![entry]).unwrap();
+        let commit = format!("tree {}\nauthor A <a@example.com> 0 +0000\ncommitter A <a@example.com> 0 +0000\n\nkept\n", tree);
+        let commit = db.write(&Object::Commit(commit.into_bytes())).unwrap();
+        let garbage = db.write(&Object::Blob(b"garbage\n".to_vec())).unwrap();
+        std::fs::write(
+            Path::new(repo.path()).join(".git/packed-refs"),
+            format!("# pack-refs with: peeled fully-peeled sorted \n{} refs/heads/main\n", commit),
+        )
+        .unwrap();
+
+        git_prune(Path::new(repo.path()), Some("now"), false);
+
+        for sha in [&blob, &tree, &commit] {
+            assert!(db.contains(sha), "{} should survive prune", sha);
+        }
+        assert!(!db.contains(&garbage));
+    }
+
+    #[test]
+    fn prune_deletes_nothing_when_history_is_corrupt() {
+        let repo = TempRepo::new("prune-corrupt-commit");
+        let db = LooseObjectDatabase::new(repo.path());
+        let tree = TreeProcessor::create_empty_tree(&db).unwrap();
+        let mut parent: Option<String> = None;
+        let mut commits = Vec::new();
+        for n in 1..=3 {
+            let parent_line = parent.map(|p| format!("parent {}\n", p)).unwrap_or_default();
+            let commit = format!(
+                "tree {}\n{}author A <a@example.com> 0 +0000\ncommitter A <a@example.com> 0 +0000\n\nc{}\n",
+                tree, parent_line, n
+            );
+            let sha = db.write(&Object::Commit(commit.into_bytes())).unwrap();
+            parent = Some(sha.clone());
+            commits.push(sha);
+        }
+        let garbage = db.write(&Object::Blob(b"garbage\n".to_vec())).unwrap();
+        std::fs::create_dir_all(Path::new(repo.path()).join(".git/refs/heads")).unwrap();
+        std::fs::write(Path::new(repo.path()).join(".git/refs/heads/main"), format!("{}\n", commits[2])).unwrap();
+
+        // c2 损坏：不知道 c1 是否可达，prune 必须整体放弃
+        let c2 = Object::loose_path(repo.path(), &commits[1]);
+        std::fs::remove_file(&c2).unwrap();
+        std::fs::write(&c2, b"not a zlib stream").unwrap();
+
+        git_prune(Path::new(repo.path()), Some("now"), false);
+        for sha in commits.iter().chain([&tree, &garbage]) {
+            assert!(Object::loose_path(repo.path(), sha).exists(), "{} should survive prune", sha);
+        }
+    }
+
+    #[test]
+    fn prune_gives_up_on_corrupt_index() {
+        let repo = TempRepo::new("prune-corrupt-index");
+        let db = LooseObjectDatabase::new(repo.path());
+        let staged_only = db.write(&Object::Blob(b"only in the index\n".to_vec())).unwrap();
+        std::fs::write(Path::new(repo.path()).join(".git/index"), b"DIRC garbage").unwrap();
+
+        git_prune(Path::new(repo.path()), Some("now"), false);
+        assert!(db.contains(&staged_only));
+    }
+}
@@ -1,9 +1,13 @@
 // commands/init.rs
+use crate::core::alternates::add_alternate;
 use crate::core::repository::Repository; // 引入核心仓库模块
-use std::path::Path;
+use crate::utils::hash::HashAlgorithm;
+use std::path::{Path, PathBuf};
 
 /// Git 初始化命令处理器
-pub fn git_init(target_path: &str) {
+/// - `object_format`: 对象哈希算法（`sha1` / `sha256`）
+/// - `reference`: 借用其对象的仓库（写入 `objects/info/alternates`）
+pub fn git_init(target_path: &str, object_format: &str, reference: Option<&str>) {
     // 1️⃣ 检查是否已存在 .git 仓库
     let git_dir = Path::new(target_path).join(".git");
     if git_dir.exists() {
@@ -11,9 +15,57 @@ pub fn git_init(target_path: &str) {
         return;
     }
 
-    // 2️⃣ 调用核心模块初始化仓库
-    let _repo = Repository::init(target_path);
+    let Some(algo) = HashAlgorithm::from_name(object_format) else {
+        println!("Error: unknown object format '{}' (expected sha1 or sha256)", object_format);
+        return;
+    };
+
+    // 2️⃣ 检查被借用的仓库：对象目录存在且哈希算法一致
+    let reference_objects = match reference {
+        Some(reference) => {
+            let Some(dir) = reference_objects_dir(reference) else {
+                println!("Error: reference repository '{}' is not a git repository", reference);
+                return;
+            };
+            if Path::new(reference).join(".git").exists() {
+                match HashAlgorithm::try_for_repo(reference) {
+                    Ok(reference_algo) if reference_algo == algo => {}
+                    Ok(_) => {
+                        println!("Error: reference repository '{}' uses a different object format", reference);
+                        return;
+                    }
+                    Err(e) => {
+                        println!("Error: reference repository '{}': {}", reference, e);
+                        return;
+                    }
+                }
+            }
+            Some(dir)
+        }
+        None => None,
+    };
+
+    // 3️⃣ 调用核心模块初始化仓库
+    if let Err(e) = Repository::init_with_format(target_path, algo) {
+        println!("Error: failed to initialize repository: {}", e);
+        return;
+    }
+    if let Some(dir) = reference_objects {
+        if let Err(e) = add_alternate(target_path, &dir) {
+            println!("Error: failed to write alternates: {}", e);
+            return;
+        }
+        println!("Borrowing objects from {}", dir.display());
+    }
 
-    // 3️⃣ 输出成功信息
+    // 4️⃣ 输出成功信息
     println!("Initialized empty Git repository in {}/.git", target_path);
 }
+
+/// 被借用仓库的对象目录：可以是工作区根目录、`.git` 目录或对象目录本身
+fn reference_objects_dir(reference: &str) -> Option<PathBuf> {
+    let path = Path::new(reference);
+    [path.join(".git").join("objects"), path.join("objects"), path.to_path_buf()]
+        .into_iter()
+        .find(|dir| dir.join("pack").is_dir() || dir.join("info").is_dir())
+}
@@ -0,0 +1,164 @@
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::reference::Reference;
+use crate::core::tag::peel_to_tree;
+use crate::core::tree::{TreeEntry, TreeProcessor};
+use crate::utils::fs::quote_path;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// ls-tree 的输出选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LsTreeOptions {
+    /// `-r`：递归进入子树
+    pub recursive: bool,
+    /// `-t`：进入子树时也输出子树本身
+    pub show_trees: bool,
+    /// `-l`：输出 blob 大小
+    pub long: bool,
+    /// `--name-only`：只输出路径
+    pub name_only: bool,
+}
+
+/// git ls-tree 命令（列出 tree 对象的内容）
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `tree_ish`: tree、commit 或指向它们的标签 / 引用
+/// - `paths`: 只列出这些路径（目录名以 `/` 结尾时列出其内容）
+/// - `opts`: 输出选项
+///
+/// # 返回
+/// - 是否成功
+pub fn git_ls_tree(repo_path: &Path, tree_ish: &str, paths: &[String], opts: LsTreeOptions) -> bool {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    let tree_sha = match Reference::try_resolve_revision(repo, tree_ish) {
+        Ok(sha) => peel_to_tree(&db, &sha),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return false;
+        }
+    };
+    let Some(tree_sha) = tree_sha else {
+        eprintln!("❌ '{}' is not a tree object", tree_ish);
+        return false;
+    };
+
+    let lister = Lister {
+        db: &db,
+        paths,
+        opts,
+    };
+    let mut out = io::stdout().lock();
+    match lister.list(&mut out, &tree_sha, b"") {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => false,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            false
+        }
+    }
+}
+
+struct Lister<'a> {
+    db: &'a dyn ObjectDatabase,
+    paths: &'a [String],
+    opts: LsTreeOptions,
+}
+
+impl Lister<'_> {
+    /// 递归列出 tree，`prefix` 为当前 tree 相对根目录的路径（原始字节）
+    ///
+    /// 子树缺失或损坏时返回错误，不输出不完整的列表后当作成功。
+    fn list(&self, out: &mut impl Write, tree_sha: &str, prefix: &[u8]) -> io::Result<()> {
+        let Some(entries) = TreeProcessor::read_tree(self.db, tree_sha) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Tree {} not found or corrupt", tree_sha)));
+        };
+
+        for entry in entries {
+            let path = [prefix, &entry.name].concat();
+
+            if entry.is_dir && self.should_descend(&path) {
+                if self.opts.show_trees {
+                    self.print(out, &entry, &path)?;
+                }
+                self.list(out, &entry.hash, &[&path[..], b"/"].concat())?;
+            } else if self.is_selected(&path) {
+                self.print(out, &entry, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 条目本身是否应当输出
+    fn is_selected(&self, path: &[u8]) -> bool {
+        if self.paths.is_empty() {
+            return true;
+        }
+        self.paths.iter().any(|p| {
+            let dir = p.trim_end_matches('/').as_bytes();
+            let list_contents = p.ends_with('/');
+            (path == dir && !list_contents)
+                || path
+                    .strip_prefix(dir)
+                    .and_then(|rest| rest.strip_prefix(b"/"))
+                    .is_some_and(|rest| self.opts.recursive || !rest.contains(&b'/'))
+        })
+    }
+
+    /// 是否需要进入该子树
+    fn should_descend(&self, path: &[u8]) -> bool {
+        let inside = |p: &String| {
+            let dir = p.trim_end_matches('/').as_bytes();
+            dir.strip_prefix(path).is_some_and(|rest| rest.starts_with(b"/"))
+                || (p.ends_with('/') && dir == path)
+        };
+        if self.opts.recursive {
+            self.paths.is_empty()
+                || self.paths.iter().any(|p| {
+                    let dir = p.trim_end_matches('/').as_bytes();
+                    inside(p) || path == dir || path.starts_with(&[dir, b"/"].concat())
+                })
+        } else {
+            self.paths.iter().any(inside)
+        }
+    }
+
+    fn print(&self, out: &mut impl Write, entry: &TreeEntry, path: &[u8]) -> io::Result<()> {
+        let path = quote_path(path);
+        if self.opts.name_only {
+            return writeln!(out, "{}", path);
+        }
+        let kind = entry.object_type();
+        if self.opts.long {
+            let size = match kind {
+                "blob" => self.db.read(&entry.hash).map_or("-".to_string(), |o| o.data().len().to_string()),
+                _ => "-".to_string(),
+            };
+            writeln!(out, "{:06o} {} {} {:>7}\t{}", entry.mode, kind, entry.hash, size, path)
+        } else {
+            writeln!(out, "{:06o} {} {}\t{}", entry.mode, kind, entry.hash, path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::utils::fs::TempRepo;
+
+    #[test]
+    fn missing_subtree_is_an_error() {
+        let repo = TempRepo::new("ls-tree-missing-subtree");
+        let mut data = b"40000 dir\0".to_vec();
+        data.extend([0xab; 20]);
+        let tree = Object::Tree(data).save(repo.path()).unwrap();
+
+        let opts = LsTreeOptions { recursive: true, ..Default::default() };
+        assert!(!git_ls_tree(Path::new(repo.path()), &tree, &[], opts));
+        // 不递归时不读取子树
+        assert!(git_ls_tree(Path::new(repo.path()), &tree, &[], LsTreeOptions::default()));
+    }
+}
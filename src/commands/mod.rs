@@ -5,6 +5,17 @@ pub mod rm;
 pub mod commit;
 pub mod branch;
 pub mod checkout;
+pub mod repack;
+pub mod multi_pack_index;
+pub mod tag;
+pub mod cat_file;
+pub mod ls_tree;
+pub mod rev_list;
+pub mod fsck;
+pub mod gc;
+pub mod count_objects;
+pub mod sizer;
+pub mod commit_graph;
 pub mod merge;
 
 pub mod fetch;
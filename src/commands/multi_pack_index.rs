@@ -0,0 +1,30 @@
+use crate::core::midx::{verify_midx, write_midx};
+use std::path::Path;
+
+/// git multi-pack-index write 命令（为所有 pack 写出 `.git/objects/pack/multi-pack-index`）
+///
+/// 之后查找对象时先在 multi-pack-index 中二分查找，只有之后新写入、
+/// 尚未收录的 pack 才需要逐个探测。文件格式与 Git 相同。
+pub fn git_multi_pack_index_write(repo_path: &Path) {
+    let repo = repo_path.to_str().unwrap();
+    match write_midx(repo) {
+        Ok((0, _)) => println!("⚠️  No packs to index."),
+        Ok((packs, objects)) => println!("✅ Wrote multi-pack-index ({} packs, {} objects)", packs, objects),
+        Err(e) => println!("❌ Failed to write multi-pack-index: {}", e),
+    }
+}
+
+/// git multi-pack-index verify 命令（校验 multi-pack-index 与各个 pack 是否一致）
+///
+/// # 返回
+/// - 是否通过校验
+pub fn git_multi_pack_index_verify(repo_path: &Path) -> bool {
+    let errors = verify_midx(repo_path.to_str().unwrap());
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if errors.is_empty() {
+        println!("✅ multi-pack-index OK");
+    }
+    errors.is_empty()
+}
@@ -0,0 +1,177 @@
+use crate::core::bitmap::write_bitmap;
+use crate::core::config::Config;
+use crate::core::midx::midx_path;
+use crate::core::object::Object;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::pack::{self, PackFile};
+use crate::core::pack_writer::{write_pack, PackObject, PackOptions};
+use crate::core::tag::peel_to_commit;
+use crate::core::walk::{commit_links, reachable_commits, reachable_objects, repo_roots, root_shas};
+use crate::utils::hash::HashAlgorithm;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// git repack 命令（把对象打包成一个新的 pack 文件）
+///
+/// # 功能
+/// - 收集所有松散对象和已有 pack 中的对象
+/// - 按类型、路径、大小在滑动窗口内寻找 delta 基础对象
+/// - 写出 `.pack` 与对应的 `.idx`
+/// - `delete_loose` 为 true 时，在校验新 pack 后删除已打包的松散对象和旧 pack
+/// - 需要时为新 pack 写出可达性位图（`.bitmap`），gc 等计算可达对象时无需完整遍历
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `delete_loose`: 是否删除已被打包的松散对象
+/// - `opts`: 窗口大小与最大 delta 深度
+/// - `bitmap`: 是否写出位图；未指定时取配置项 `repack.writeBitmaps`
+pub fn git_repack(repo_path: &Path, delete_loose: bool, opts: &PackOptions, bitmap: bool) {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    // 1️⃣ 收集所有对象：松散对象 + 已有 pack 中的对象
+    let loose = Object::list_loose(repo);
+    let old_packs = pack::rescan_packs(repo);
+    let mut all: BTreeSet<String> = loose.iter().cloned().collect();
+    for p in old_packs.iter() {
+        for i in 0..p.index.len() {
+            all.insert(hex::encode(p.index.sha_at(i)));
+        }
+    }
+    if all.is_empty() {
+        println!("⚠️  Nothing to pack.");
+        return;
+    }
+
+    // 2️⃣ 通过可达性遍历为对象记录路径（用于 delta 基础对象选择），起点与 gc / fsck 相同
+    let roots = match repo_roots(repo) {
+        Ok(roots) => root_shas(&roots),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    // 遍历不完整时不知道哪些对象可达，不能继续（-d 会删除松散对象）
+    let reachable = match reachable_objects(&db, &roots) {
+        Ok(reachable) => reachable,
+        Err(e) => {
+            eprintln!("❌ {}, aborting repack", e);
+            return;
+        }
+    };
+    let paths: HashMap<String, String> = reachable
+        .into_iter()
+        .filter_map(|o| o.path.map(|p| (o.sha, p)))
+        .collect();
+
+    // 这里只读取类型和大小，内容在写入 pack 时按 delta 窗口逐个读取
+    let mut objects = Vec::new();
+    for sha in &all {
+        match db.read_header(sha) {
+            Some((kind, size)) => {
+                objects.push(PackObject::new(sha.clone(), kind, size, paths.get(sha).map(String::as_str)))
+            }
+            None => {
+                eprintln!("❌ Cannot read object {}, aborting repack", sha);
+                return;
+            }
+        }
+    }
+
+    // 3️⃣ 写出 pack
+    let result = match write_pack(repo, &db, &objects, opts) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("❌ Failed to write pack: {}", e);
+            return;
+        }
+    };
+    println!(
+        "📦 Wrote pack-{} ({} objects, {} deltas)",
+        result.checksum, result.object_count, result.delta_count
+    );
+
+    let Some(new_pack) = PackFile::open(&result.idx_path, HashAlgorithm::for_repo(repo)) else {
+        eprintln!("❌ Cannot reopen new pack, keeping loose objects");
+        return;
+    };
+
+    // 4️⃣ 可达性位图：新 pack 包含所有对象，因此每个提交的可达闭包都在其中
+    if bitmap || Config::load(repo).get_bool("repack.writeBitmaps") == Some(true) {
+        let kinds: HashMap<String, &'static str> = objects.iter().map(|o| (o.sha.clone(), o.kind)).collect();
+        let tips: Vec<String> = roots.iter().filter_map(|sha| peel_to_commit(&db, sha)).collect();
+        let mut commits = reachable_commits(&db, &roots);
+        commits.sort_by_key(|sha| commit_links(&db, sha).map_or(0, |c| c.commit_time));
+        match write_bitmap(repo, &db, &new_pack, &kinds, &tips, &commits) {
+            Ok(count) => println!("🗺️  Wrote reachability bitmaps for {} commit(s)", count),
+            Err(e) => eprintln!("⚠️  Skipping bitmap: {}", e),
+        }
+    }
+
+    if !delete_loose {
+        pack::rescan_packs(repo);
+        return;
+    }
+
+    // 5️⃣ 删除前先校验：pack 校验和正确，新 pack 中的每个对象都能读出且哈希正确
+    //    大对象写入时已经边读边校验过哈希，这里只核对类型和大小，避免整体读入内存
+    if !new_pack.verify_checksum() {
+        eprintln!("❌ New pack failed checksum verification, keeping loose objects");
+        return;
+    }
+    let algo = HashAlgorithm::for_repo(repo);
+    let big_file_threshold = Config::load(repo).big_file_threshold();
+    for obj in &objects {
+        let sha_bytes = hex::decode(&obj.sha).unwrap();
+        let ok = if obj.size > big_file_threshold {
+            new_pack.read_header(repo, &sha_bytes) == Some((obj.kind, obj.size))
+        } else {
+            new_pack
+                .read(repo, &sha_bytes)
+                .is_some_and(|o| o.try_hash(algo).is_ok_and(|h| h == obj.sha))
+        };
+        if !ok {
+            eprintln!("❌ Object {} failed verification, keeping loose objects", obj.sha);
+            return;
+        }
+    }
+
+    // 6️⃣ 删除已打包的松散对象和被新 pack 完全覆盖的旧 pack
+    let mut removed = 0;
+    for sha in &loose {
+        if std::fs::remove_file(Object::loose_path(repo, sha)).is_ok() {
+            removed += 1;
+        }
+    }
+    let mut removed_packs = 0;
+    for old in old_packs.iter() {
+        if old.pack_path == result.pack_path {
+            continue;
+        }
+        std::fs::remove_file(old.pack_path.with_extension("bitmap")).ok();
+        std::fs::remove_file(old.pack_path.with_extension("idx")).ok();
+        std::fs::remove_file(&old.pack_path).ok();
+        removed_packs += 1;
+    }
+    // multi-pack-index 引用了被删除的 pack，已经过期
+    if removed_packs > 0 {
+        std::fs::remove_file(midx_path(repo)).ok();
+    }
+    remove_empty_fanout_dirs(repo_path);
+    pack::rescan_packs(repo);
+
+    println!("🗑️  Removed {} loose object(s) and {} old pack(s)", removed, removed_packs);
+}
+
+/// 删除 `.git/objects/xx` 下已清空的目录
+fn remove_empty_fanout_dirs(repo_path: &Path) {
+    let objects_dir = repo_path.join(".git").join("objects");
+    if let Ok(dirs) = std::fs::read_dir(objects_dir) {
+        for dir in dirs.filter_map(Result::ok) {
+            if dir.file_name().len() == 2 {
+                // 非空目录删除会失败，直接忽略
+                std::fs::remove_dir(dir.path()).ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::reference::Reference;
+use crate::core::walk::{path_history, reachable_objects};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// rev-list 的输出选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RevListOptions {
+    /// `--objects`：列出所有可达对象，而不只是提交
+    pub objects: bool,
+    /// `--count`：只输出数量
+    pub count: bool,
+}
+
+/// git rev-list 命令（按提交时间从新到旧列出提交）
+///
+/// 仓库有可达性位图时，`--objects` 与不带路径的 `--count` 直接由位图得出，
+/// 只遍历位图没有覆盖的新对象（此时 `--objects` 不输出路径，与 Git 相同）。
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `commit`: 起始提交（分支、标签、HEAD 或哈希）
+/// - `path`: 只列出改动过该路径的提交（`rev-list <commit> -- <path>`）；
+///   commit-graph 带有 Bloom 过滤器时会跳过一定没有改动该路径的提交
+/// - `opts`: 输出选项
+///
+/// # 返回
+/// - 是否成功
+pub fn git_rev_list(repo_path: &Path, commit: &str, path: Option<&str>, opts: RevListOptions) -> bool {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    if opts.objects && path.is_some() {
+        eprintln!("❌ --objects cannot be combined with a path");
+        return false;
+    }
+    // 列出对象时保留附注标签本身，只列提交时剥离到其指向的提交
+    let resolved = if opts.objects {
+        Reference::try_resolve_revision(repo, commit)
+    } else {
+        Reference::try_resolve_commit(repo, commit)
+    };
+    let start = match resolved {
+        Ok(sha) => sha,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return false;
+        }
+    };
+    let starts = [start];
+
+    let mut out = io::stdout().lock();
+    let lines: Vec<String> = match (db.pack_bitmap(), path) {
+        (Some(bitmap), None) if opts.objects || opts.count => {
+            let reachable = match bitmap.reachable(&db, &starts) {
+                Ok(reachable) => reachable,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    return false;
+                }
+            };
+            if opts.count {
+                let count = if opts.objects {
+                    reachable.bits.count() + reachable.extra.len()
+                } else {
+                    bitmap.count_type(&reachable, "commit")
+                };
+                writeln!(out, "{}", count).ok();
+                return true;
+            }
+            let in_pack = reachable.bits.ones().map(|bit| bitmap.sha_at(bit).to_string());
+            in_pack.chain(reachable.extra.into_iter().map(|(sha, _)| sha)).collect()
+        }
+        _ if opts.objects => {
+            // 与 Git 相同：先输出提交，再输出 tree / blob / tag
+            let reachable = match reachable_objects(&db, &starts) {
+                Ok(reachable) => reachable,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    return false;
+                }
+            };
+            let (commits, others): (Vec<_>, Vec<_>) = reachable.into_iter().partition(|o| o.kind == "commit");
+            commits.into_iter().chain(others).map(|o| match o.path {
+                Some(path) if !path.is_empty() => format!("{} {}", o.sha, path),
+                Some(_) => format!("{} ", o.sha),
+                None => o.sha,
+            })
+            .collect()
+        }
+        _ => path_history(&db, &starts, path.unwrap_or("")),
+    };
+
+    if opts.count {
+        writeln!(out, "{}", lines.len()).ok();
+        return true;
+    }
+    for line in lines {
+        if writeln!(out, "{}", line).is_err() {
+            break;
+        }
+    }
+    true
+}
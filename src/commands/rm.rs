@@ -15,7 +15,14 @@ use walkdir::WalkDir; // 需要在 Cargo.toml 添加依赖 walkdir = "2"
 /// - `paths`: 要删除的文件或目录路径列表
 /// - `keep_in_workdir`: 是否保留工作区文件
 pub fn git_rm(repo_path: &Path, paths: &[String], keep_in_workdir: bool) {
-    let mut index = Index::load(repo_path);
+    // 整个读取-修改-写回过程持有 index.lock
+    let mut index = match Index::lock(repo_path) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
 
     for path_str in paths {
         let path = Path::new(path_str);
@@ -38,11 +45,15 @@ pub fn git_rm(repo_path: &Path, paths: &[String], keep_in_workdir: bool) {
             remove_file_from_index_and_workdir(path, &mut index, keep_in_workdir);
         }
     }
+
+    if let Err(e) = index.save() {
+        eprintln!("❌ {}", e);
+    }
 }
 
 /// 单文件删除逻辑
 fn remove_file_from_index_and_workdir(file_path: &Path, index: &mut Index, keep_in_workdir: bool) {
-    if !index.entries.contains_key(file_path) {
+    if !index.entries.contains_key(file_path) && !index.unmerged.contains_key(file_path) {
         eprintln!("⚠️  Skipped: file not staged '{}'", file_path.display());
         return;
     }
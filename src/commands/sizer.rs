@@ -0,0 +1,259 @@
+use crate::core::commit::Commit;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::tree::TreeProcessor;
+use crate::core::walk::{reachable_objects, read_reachable, ref_roots};
+use crate::utils::error::GitError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 单个对象的统计信息
+struct ObjectStat {
+    sha: String,
+    path: Option<String>,
+    /// 对象内容大小（字节，delta 已还原）
+    size: u64,
+    /// tree 的条目数（其他类型为 0）
+    entries: usize,
+}
+
+/// 各类型对象的数量与总大小
+#[derive(Default)]
+struct TypeTotal {
+    count: usize,
+    size: u64,
+}
+
+/// 可达对象的统计结果
+#[derive(Default)]
+struct Stats {
+    totals: HashMap<&'static str, TypeTotal>,
+    blobs: Vec<ObjectStat>,
+    trees: Vec<ObjectStat>,
+    commits: Vec<ObjectStat>,
+    /// 提交 -> 父提交
+    parents: HashMap<String, Vec<String>>,
+}
+
+/// sizer 命令（分析仓库中哪些对象导致仓库变大）
+///
+/// 从所有引用和 HEAD 出发遍历可达对象（松散对象与 pack 均可），输出：
+/// - 各类型对象的数量与总大小
+/// - 最大的 blob 及其路径
+/// - 最深（路径层级最多）和最宽（条目最多）的 tree
+/// - 最大的 commit（通常是提交信息或头部异常大）
+/// - 最长的提交历史链（从根提交到某个提交经过的最多提交数）
+///
+/// # 参数
+/// - `repo_path`: 仓库根路径
+/// - `top`: 每项列出的对象个数
+pub fn git_sizer(repo_path: &Path, top: usize) {
+    let repo = repo_path.to_str().unwrap();
+    let db = LooseObjectDatabase::new(repo);
+
+    // 1️⃣ 遍历可达对象，记录大小、路径和 tree 宽度
+    let Stats { mut totals, mut blobs, mut trees, mut commits, parents } = match collect_stats(&db, &ref_roots(repo)) {
+        Ok(stats) => stats,
+        Err(e) => {
+            println!("❌ {}", e);
+            return;
+        }
+    };
+
+    if totals.is_empty() {
+        println!("⚠️  No reachable objects.");
+        return;
+    }
+
+    // 2️⃣ 概览
+    println!("📊 Reachable objects");
+    for kind in ["commit", "tree", "blob", "tag"] {
+        let total = totals.remove(kind).unwrap_or_default();
+        println!("  {:<7} {:>8} objects  {:>12}", kind, total.count, human_size(total.size));
+    }
+
+    // 3️⃣ 各项排行
+    blobs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.sha.cmp(&b.sha)));
+    println!("\n📦 Largest blobs");
+    for stat in blobs.iter().take(top) {
+        println!("  {:>12}  {}  {}", human_size(stat.size), stat.sha, stat.path.as_deref().unwrap_or("?"));
+    }
+
+    trees.sort_by(|a, b| depth(b).cmp(&depth(a)).then_with(|| a.sha.cmp(&b.sha)));
+    println!("\n🌲 Deepest trees");
+    for stat in trees.iter().take(top) {
+        println!("  depth {:>4}  {}  {}", depth(stat), stat.sha, display_path(stat));
+    }
+
+    trees.sort_by(|a, b| b.entries.cmp(&a.entries).then_with(|| a.sha.cmp(&b.sha)));
+    println!("\n🌳 Widest trees");
+    for stat in trees.iter().take(top) {
+        println!("  {:>6} entries  {}  {}", stat.entries, stat.sha, display_path(stat));
+    }
+
+    commits.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.sha.cmp(&b.sha)));
+    println!("\n📝 Largest commits");
+    for stat in commits.iter().take(top) {
+        println!("  {:>12}  {}", human_size(stat.size), stat.sha);
+    }
+
+    if let Some((tip, length)) = longest_chain(&parents) {
+        println!("\n⛓️  Longest history chain: {} commits (ending at {})", length, tip);
+    }
+}
+
+/// 遍历从 `roots` 可达的对象，统计大小、路径、tree 宽度和提交的父子关系
+///
+/// 只有 tree 和 commit 需要读取内容；blob（以及 tag）只读取 header 得到大小，不解压内容。
+fn collect_stats(db: &dyn ObjectDatabase, roots: &[String]) -> Result<Stats, GitError> {
+    let mut stats = Stats::default();
+    for reachable in reachable_objects(db, roots)? {
+        let obj = match reachable.kind {
+            "tree" | "commit" => Some(read_reachable(db, &reachable.sha)?),
+            _ => None,
+        };
+        let size = match &obj {
+            Some(obj) => obj.data().len() as u64,
+            None => db.read_header(&reachable.sha).ok_or_else(|| GitError::CorruptObject(reachable.sha.clone()))?.1,
+        };
+        let total = stats.totals.entry(reachable.kind).or_default();
+        total.count += 1;
+        total.size += size;
+
+        let mut stat = ObjectStat { sha: reachable.sha, path: reachable.path, size, entries: 0 };
+        match (reachable.kind, obj) {
+            ("blob", _) => stats.blobs.push(stat),
+            ("tree", Some(obj)) => {
+                stat.entries = TreeProcessor::try_parse_tree(obj.data(), db.hash_algorithm()).map_or(0, |e| e.len());
+                stats.trees.push(stat);
+            }
+            ("commit", Some(obj)) => {
+                if let Some(commit) = Commit::parse(obj.data()) {
+                    stats.parents.insert(stat.sha.clone(), commit.parents);
+                }
+                stats.commits.push(stat);
+            }
+            _ => {}
+        }
+    }
+    Ok(stats)
+}
+
+/// tree 所在路径的层级（根 tree 为 0）
+fn depth(stat: &ObjectStat) -> usize {
+    match stat.path.as_deref() {
+        None | Some("") => 0,
+        Some(path) => path.split('/').count(),
+    }
+}
+
+fn display_path(stat: &ObjectStat) -> &str {
+    match stat.path.as_deref() {
+        Some("") => "/",
+        Some(path) => path,
+        None => "?",
+    }
+}
+
+/// 计算提交图中最长的链：链长 = 1 + 父提交中最长的链长，返回 (链尾提交, 链长)
+///
+/// 使用显式栈做后序遍历，避免很长的历史导致递归栈溢出。
+fn longest_chain(parents: &HashMap<String, Vec<String>>) -> Option<(String, usize)> {
+    let mut lengths: HashMap<&str, usize> = HashMap::new();
+    let mut shas: Vec<&String> = parents.keys().collect();
+    shas.sort();
+
+    for start in shas {
+        let mut stack = vec![(start.as_str(), false)];
+        while let Some((sha, expanded)) = stack.pop() {
+            if lengths.contains_key(sha) {
+                continue;
+            }
+            // 不在遍历结果中的父提交（例如浅克隆）不计入链长
+            let known: Vec<&str> = parents
+                .get(sha)
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .filter(|p| parents.contains_key(*p))
+                .collect();
+            if expanded {
+                let longest = known.iter().filter_map(|p| lengths.get(p)).max().copied().unwrap_or(0);
+                lengths.insert(sha, longest + 1);
+            } else {
+                stack.push((sha, true));
+                stack.extend(known.into_iter().filter(|p| !lengths.contains_key(p)).map(|p| (p, false)));
+            }
+        }
+    }
+
+    lengths
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(sha, length)| (sha.to_string(), length))
+}
+
+/// 以 B / KiB / MiB / GiB 显示字节数
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::core::odb::MemoryObjectDatabase;
+    use crate::core::tree::TreeEntry;
+    use crate::utils::hash::HashAlgorithm;
+
+    fn commit(db: &dyn ObjectDatabase, tree: &str, parents: &[&str], message: &str) -> String {
+        let mut text = format!("tree {}\n", tree);
+        for parent in parents {
+            text.push_str(&format!("parent {}\n", parent));
+        }
+        text.push_str(&format!("author A <a@example.com> 0 +0000\ncommitter A <a@example.com> 0 +0000\n\n{}\n", message));
+        db.write(&Object::Commit(text.into_bytes())).unwrap()
+    }
+
+    fn entry(name: &str, hash: &str, is_dir: bool) -> TreeEntry {
+        TreeEntry { name: name.as_bytes().to_vec(), hash: hash.to_string(), mode: if is_dir { 0o40000 } else { 0o100644 }, is_dir }
+    }
+
+    #[test]
+    fn ranks_blobs_and_finds_longest_chain() {
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let small = db.write(&Object::Blob(b"small\n".to_vec())).unwrap();
+        let big = db.write(&Object::Blob(vec![b'x'; 5000])).unwrap();
+        let dir = TreeProcessor::create_tree(&db, vec![entry("big.bin", &big, false)]).unwrap();
+        let root = TreeProcessor::create_tree(&db, vec![entry("a.txt", &small, false), entry("dir", &dir, true)]).unwrap();
+
+        // c1 ← c2 ← c3，另有一个合并进来的旁支 side（父提交为 c1）
+        let c1 = commit(&db, &root, &[], "c1");
+        let c2 = commit(&db, &root, &[&c1], "c2");
+        let side = commit(&db, &root, &[&c1], "side");
+        let c3 = commit(&db, &root, &[&c2, &side], "c3");
+
+        let stats = collect_stats(&db, std::slice::from_ref(&c3)).unwrap();
+        assert_eq!(stats.totals["commit"].count, 4);
+        assert_eq!(stats.totals["blob"].count, 2);
+        assert_eq!(stats.totals["blob"].size, 5000 + 6);
+
+        let largest = stats.blobs.iter().max_by_key(|b| b.size).unwrap();
+        assert_eq!(largest.sha, big);
+        assert_eq!(largest.path.as_deref(), Some("dir/big.bin"));
+        let widest = stats.trees.iter().max_by_key(|t| t.entries).unwrap();
+        assert_eq!((widest.sha.as_str(), widest.entries), (root.as_str(), 2));
+
+        assert_eq!(longest_chain(&stats.parents), Some((c3, 3)));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::core::commit::Signature;
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::reference::Reference;
+use crate::core::tag::Tag;
+use std::path::Path;
+
+/// ======================================
+/// 🏷️ Git tag 命令实现
+///
+/// ✅ 支持功能：
+/// 1. `git tag` / `git tag -l [pattern]`   —— 列出标签（支持 `*` `?` 通配）
+/// 2. `git tag <name> [<commit>]`          —— 创建轻量标签（只写引用）
+/// 3. `git tag -a <name> -m <msg> [<commit>]` —— 创建附注标签对象
+/// 4. `git tag -d <name>...`               —— 删除标签
+///
+/// ⚙️ 实现原理：
+/// - 标签对应 `.git/refs/tags/<name>` 文件
+/// - 轻量标签直接指向提交；附注标签指向一个 tag 对象，
+///   其中记录 object / type / tag / tagger 头部和标签信息
+///
+/// ======================================
+pub fn git_tag_list(repo_path: &Path, pattern: Option<&str>) {
+    for tag_name in tag_names(repo_path.to_str().unwrap(), pattern) {
+        println!("{}", tag_name);
+    }
+}
+
+/// 匹配 `pattern` 的标签名（松散引用和 packed-refs 中的标签），按名字排序
+fn tag_names(repo: &str, pattern: Option<&str>) -> Vec<String> {
+    Reference::list_all(repo)
+        .into_iter()
+        .filter_map(|(name, _)| name.strip_prefix("refs/tags/").map(str::to_string))
+        .filter(|tag_name| pattern.is_none_or(|p| wildmatch(p, tag_name)))
+        .collect()
+}
+
+/// 创建轻量标签或附注标签
+///
+/// # 参数
+/// - `name`: 标签名
+/// - `target`: 被标记的修订（默认 HEAD）
+/// - `annotate`: 是否创建附注标签（提供 `message` 时自动视为附注标签）
+/// - `message`: 附注标签的信息
+/// - `force`: 标签已存在时是否覆盖
+pub fn git_tag_create(
+    repo_path: &Path,
+    name: &str,
+    target: Option<&str>,
+    annotate: bool,
+    message: Option<&str>,
+    force: bool,
+) {
+    let repo = repo_path.to_str().unwrap();
+    let ref_name = format!("refs/tags/{}", name);
+
+    if !is_valid_tag_name(name) {
+        println!("❌ '{}' is not a valid tag name", name);
+        return;
+    }
+    if Reference::resolve(repo, &ref_name).is_some() && !force {
+        println!("❌ Tag '{}' already exists", name);
+        return;
+    }
+
+    // 1️⃣ 解析被标记的对象
+    let target = target.unwrap_or("HEAD");
+    let object = match Reference::try_resolve_revision(repo, target) {
+        Ok(sha) => sha,
+        Err(e) => {
+            println!("❌ {}", e);
+            return;
+        }
+    };
+
+    // 2️⃣ 轻量标签：直接指向对象
+    if !annotate && message.is_none() {
+        Reference::create(repo, &ref_name, &object);
+        println!("🏷️  Created tag '{}' at {}", name, object);
+        return;
+    }
+
+    // 3️⃣ 附注标签：创建 tag 对象，引用指向该对象
+    let Some(message) = message else {
+        println!("❌ Annotated tag '{}' requires a message (-m)", name);
+        return;
+    };
+    let db = LooseObjectDatabase::new(repo);
+    let Some(kind) = db.read(&object).map(|o| o.type_name()) else {
+        println!("❌ Cannot read object {}", object);
+        return;
+    };
+    let mut message = message.to_string();
+    if !message.ends_with('\n') {
+        message.push('\n');
+    }
+    let tag = Tag::new(
+        object.clone(),
+        kind.to_string(),
+        name,
+        Some(Signature::from_env("COMMITTER")),
+        message.into_bytes(),
+    );
+    let tag_sha = match tag.save(&db) {
+        Ok(sha) => sha,
+        Err(e) => {
+            println!("❌ {}", e);
+            return;
+        }
+    };
+    Reference::create(repo, &ref_name, &tag_sha);
+    println!("🏷️  Created annotated tag '{}' ({}) for {}", name, tag_sha, object);
+}
+
+/// 删除标签
+pub fn git_tag_delete(repo_path: &Path, names: &[String]) {
+    let repo = repo_path.to_str().unwrap();
+    for name in names {
+        let ref_name = format!("refs/tags/{}", name);
+        match Reference::resolve(repo, &ref_name) {
+            Some(sha) => {
+                Reference::delete(repo, &ref_name);
+                println!("🗑️  Deleted tag '{}' (was {})", name, &sha[..7.min(sha.len())]);
+            }
+            None => println!("⚠️  Tag '{}' not found", name),
+        }
+    }
+}
+
+/// 标签名校验（参考 git check-ref-format 的主要规则）
+fn is_valid_tag_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.starts_with('/')
+        && !name.ends_with('/')
+        && !name.ends_with(".lock")
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name.chars().any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c))
+}
+
+/// 简单通配匹配：`*` 匹配任意串，`?` 匹配单个字符
+fn wildmatch(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::utils::fs::TempRepo;
+
+    #[test]
+    fn lists_resolves_and_deletes_packed_tags() {
+        let repo = TempRepo::new("tag-packed");
+        let db = LooseObjectDatabase::new(repo.path());
+        let blob = Object::Blob(b"tagged\n".to_vec()).save(repo.path()).unwrap();
+        let tag = Tag::new(blob.clone(), "blob".to_string(), "v1", None, b"packed\n".to_vec());
+        let tag_sha = tag.save(&db).unwrap();
+        std::fs::write(
+            Path::new(repo.path()).join(".git/packed-refs"),
+            format!("# pack-refs with: peeled fully-peeled sorted \n{} refs/tags/v1\n^{}\n", tag_sha, blob),
+        )
+        .unwrap();
+        Reference::create(repo.path(), "refs/tags/v2", &blob);
+
+        assert_eq!(tag_names(repo.path(), None), vec!["v1", "v2"]);
+        assert_eq!(tag_names(repo.path(), Some("v1*")), vec!["v1"]);
+        assert_eq!(Reference::resolve_revision(repo.path(), "v1").unwrap(), tag_sha);
+
+        // 已存在的 packed 标签不会被悄悄覆盖
+        git_tag_create(Path::new(repo.path()), "v1", Some(&blob), false, None, false);
+        assert_eq!(Reference::resolve(repo.path(), "refs/tags/v1"), Some(tag_sha));
+
+        git_tag_delete(Path::new(repo.path()), &["v1".to_string()]);
+        assert_eq!(tag_names(repo.path(), None), vec!["v2"]);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::utils::fs::write_file_atomic;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// alternates 链的最大深度（与 Git 相同）
+const MAX_ALTERNATE_DEPTH: usize = 5;
+
+thread_local! {
+    /// 仓库路径 -> 展开后的备用对象目录列表
+    static ALTERNATES: RefCell<HashMap<String, Rc<Vec<PathBuf>>>> = RefCell::new(HashMap::new());
+}
+
+/// 仓库自己的对象目录：`.git/objects`
+pub fn objects_dir(repo_path: &str) -> PathBuf {
+    Path::new(repo_path).join(".git").join("objects")
+}
+
+/// 仓库可借用对象的备用对象目录（按 `objects/info/alternates` 展开，结果会被缓存）
+///
+/// alternates 文件每行一个对象目录，相对路径相对于该文件所在的 objects 目录；
+/// 空行和 `#` 开头的注释行被忽略。备用目录自身的 alternates 也会被继续展开，
+/// 最多 5 层，重复或指向自己的目录会被跳过。
+pub fn alternate_dirs(repo_path: &str) -> Rc<Vec<PathBuf>> {
+    if let Some(cached) = ALTERNATES.with(|a| a.borrow().get(repo_path).cloned()) {
+        return cached;
+    }
+
+    let own = objects_dir(repo_path);
+    let mut seen = vec![own.canonicalize().unwrap_or(own.clone())];
+    let mut dirs = Vec::new();
+    collect(&own, 0, &mut seen, &mut dirs);
+
+    let dirs = Rc::new(dirs);
+    ALTERNATES.with(|a| a.borrow_mut().insert(repo_path.to_string(), dirs.clone()));
+    dirs
+}
+
+fn collect(objects_dir: &Path, depth: usize, seen: &mut Vec<PathBuf>, out: &mut Vec<PathBuf>) {
+    if depth >= MAX_ALTERNATE_DEPTH {
+        return;
+    }
+    let Ok(content) = std::fs::read_to_string(objects_dir.join("info").join("alternates")) else { return };
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let dir = objects_dir.join(line);
+        let Ok(dir) = dir.canonicalize() else {
+            eprintln!("⚠️  Ignoring missing alternate object store {}", dir.display());
+            continue;
+        };
+        if seen.contains(&dir) {
+            continue;
+        }
+        seen.push(dir.clone());
+        out.push(dir.clone());
+        collect(&dir, depth + 1, seen, out);
+    }
+}
+
+/// 在仓库的 alternates 文件中追加一个备用对象目录（保存为绝对路径）
+pub fn add_alternate(repo_path: &str, alternate: &Path) -> io::Result<()> {
+    let alternate = alternate.canonicalize()?;
+    let file = objects_dir(repo_path).join("info").join("alternates");
+    let mut content = std::fs::read_to_string(&file).unwrap_or_default();
+    if content.lines().any(|l| Path::new(l.trim()) == alternate) {
+        return Ok(());
+    }
+    content.push_str(&format!("{}\n", alternate.display()));
+    std::fs::create_dir_all(file.parent().unwrap())?;
+    write_file_atomic(&file, content.as_bytes(), false)?;
+
+    ALTERNATES.with(|a| a.borrow_mut().remove(repo_path));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+    use crate::utils::fs::TempRepo;
+
+    /// 7 个仓库依次借用下一个：repo0 -> repo1 -> ... -> repo6
+    fn chain(name: &str) -> Vec<TempRepo> {
+        let repos: Vec<TempRepo> = (0..7).map(|i| TempRepo::new(&format!("{}-{}", name, i))).collect();
+        for repo in &repos {
+            std::fs::create_dir_all(objects_dir(repo.path())).unwrap();
+        }
+        for pair in repos.windows(2) {
+            add_alternate(pair[0].path(), &objects_dir(pair[1].path())).unwrap();
+        }
+        repos
+    }
+
+    #[test]
+    fn follows_alternates_chain_up_to_depth_limit() {
+        let repos = chain("alternates-chain");
+        let dirs = alternate_dirs(repos[0].path());
+        let expected: Vec<PathBuf> =
+            repos[1..=MAX_ALTERNATE_DEPTH].iter().map(|r| objects_dir(r.path()).canonicalize().unwrap()).collect();
+        assert_eq!(*dirs, expected);
+
+        // 链中的对象可以从最前面的仓库读到，超过深度限制的不行
+        let near = Object::Blob(b"in repo2\n".to_vec());
+        let far = Object::Blob(b"in repo6\n".to_vec());
+        let near_sha = LooseObjectDatabase::new(repos[2].path()).write(&near).unwrap();
+        let far_sha = LooseObjectDatabase::new(repos[6].path()).write(&far).unwrap();
+        let db = LooseObjectDatabase::new(repos[0].path());
+        assert_eq!(db.read(&near_sha), Some(near));
+        assert!(!db.contains(&far_sha));
+    }
+
+    #[test]
+    fn skips_cycles_and_resolves_relative_paths() {
+        let a = TempRepo::new("alternates-cycle-a");
+        let b = TempRepo::new("alternates-cycle-b");
+        for repo in [&a, &b] {
+            std::fs::create_dir_all(objects_dir(repo.path()).join("info")).unwrap();
+        }
+        // a 用相对路径借用 b，b 又借用 a
+        let relative = Path::new("..").join("..").join("..").join(Path::new(b.path()).file_name().unwrap());
+        std::fs::write(
+            objects_dir(a.path()).join("info").join("alternates"),
+            format!("# comment\n\n{}\n", relative.join(".git").join("objects").display()),
+        )
+        .unwrap();
+        add_alternate(b.path(), &objects_dir(a.path())).unwrap();
+
+        let dirs = alternate_dirs(a.path());
+        assert_eq!(*dirs, vec![objects_dir(b.path()).canonicalize().unwrap()]);
+    }
+}
@@ -0,0 +1,555 @@
+use crate::core::commit::Commit;
+use crate::core::config::Config;
+use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::core::pack::{self, PackFile};
+use crate::core::tree::TreeProcessor;
+use crate::core::walk::read_reachable;
+use crate::utils::error::GitError;
+use crate::utils::fs::write_file_atomic;
+use crate::utils::hash::HashAlgorithm;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const SIGNATURE: &[u8; 4] = b"BITM";
+/// 位图覆盖了 pack 中对象的完整闭包（Git 要求必须设置）
+const BITMAP_OPT_FULL_DAG: u16 = 0x1;
+/// 每个 EWAH 运行长度字（RLW）中连续相同字的最大个数（32 位）
+const RLW_RUNNING_MAX: u64 = (1 << 32) - 1;
+/// 每个 RLW 后跟随的字面字最大个数（31 位）
+const RLW_LITERAL_MAX: u64 = (1 << 31) - 1;
+/// 除引用指向的提交外，每隔多少个提交再选一个提交生成位图
+const COMMIT_INTERVAL: usize = 100;
+
+/// 按对象类型划分的位图顺序（与 Git 的 .bitmap 文件一致）
+const TYPE_ORDER: [&str; 4] = ["commit", "tree", "blob", "tag"];
+
+/// 未压缩的位图：第 i 位对应 pack 中按偏移排序后的第 i 个对象
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    pub fn set(&mut self, bit: usize) {
+        let word = bit / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (bit % 64);
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        self.words.get(bit / 64).is_some_and(|w| w & (1 << (bit % 64)) != 0)
+    }
+
+    /// self |= other
+    pub fn or(&mut self, other: &Bitmap) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+
+    /// self ^= other（用于还原 XOR 压缩的位图）
+    fn xor(&mut self, other: &Bitmap) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a ^= b;
+        }
+    }
+
+    /// 置位的个数
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// 与 `other` 同时置位的个数
+    pub fn count_and(&self, other: &Bitmap) -> usize {
+        self.words.iter().zip(&other.words).map(|(a, b)| (a & b).count_ones() as usize).sum()
+    }
+
+    /// 依次返回所有置位的位置
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64).filter(move |b| word & (1 << b) != 0).map(move |b| i * 64 + b)
+        })
+    }
+
+    /// 解析 EWAH 压缩的位图：`bit_size` `word_count` `words...` `rlw_pos`（均为大端）
+    ///
+    /// 每个 RLW 的第 0 位为重复字的值（全 0 或全 1），第 1~32 位为重复字个数，
+    /// 第 33~63 位为其后紧跟的字面字个数。`pos` 会移动到位图之后。
+    pub fn read_ewah(data: &[u8], pos: &mut usize) -> Option<Bitmap> {
+        let word_count = read_u32(data, *pos + 4)? as usize;
+        let mut compressed = Vec::with_capacity(word_count);
+        for i in 0..word_count {
+            compressed.push(read_u64(data, *pos + 8 + i * 8)?);
+        }
+        read_u32(data, *pos + 8 + word_count * 8)?;
+        *pos += 12 + word_count * 8;
+
+        let mut words = Vec::new();
+        let mut i = 0;
+        while i < compressed.len() {
+            let rlw = compressed[i];
+            let fill = if rlw & 1 != 0 { u64::MAX } else { 0 };
+            let running = (rlw >> 1) & RLW_RUNNING_MAX;
+            let literals = (rlw >> 33) as usize;
+            words.extend(std::iter::repeat_n(fill, running as usize));
+            words.extend(compressed.get(i + 1..i + 1 + literals)?);
+            i += 1 + literals;
+        }
+        Some(Bitmap { words })
+    }
+
+    /// 以 EWAH 格式压缩，`bit_size` 为位图覆盖的对象数
+    pub fn write_ewah(&self, bit_size: usize, out: &mut Vec<u8>) {
+        let mut compressed: Vec<u64> = Vec::new();
+        let mut rlw_pos = 0;
+        let mut i = 0;
+        while i < self.words.len() {
+            // 1️⃣ 连续的全 0 / 全 1 字
+            let fill = self.words[i];
+            let mut running = 0u64;
+            if fill == 0 || fill == u64::MAX {
+                while i < self.words.len() && self.words[i] == fill && running < RLW_RUNNING_MAX {
+                    running += 1;
+                    i += 1;
+                }
+            }
+            // 2️⃣ 紧随其后的字面字
+            let start = i;
+            while i < self.words.len()
+                && self.words[i] != 0
+                && self.words[i] != u64::MAX
+                && ((i - start) as u64) < RLW_LITERAL_MAX
+            {
+                i += 1;
+            }
+            rlw_pos = compressed.len();
+            let bit = (running > 0 && fill == u64::MAX) as u64;
+            compressed.push(bit | (running << 1) | (((i - start) as u64) << 33));
+            compressed.extend(&self.words[start..i]);
+        }
+        if compressed.is_empty() {
+            compressed.push(0);
+        }
+
+        out.extend((bit_size as u32).to_be_bytes());
+        out.extend((compressed.len() as u32).to_be_bytes());
+        for word in &compressed {
+            out.extend(word.to_be_bytes());
+        }
+        out.extend((rlw_pos as u32).to_be_bytes());
+    }
+}
+
+/// 从位图出发的可达性查询结果
+pub struct ReachableBitmap {
+    /// pack 中可达的对象
+    pub bits: Bitmap,
+    /// 不在 pack 中的可达对象（例如 repack 之后新写入的松散对象）及其类型
+    pub extra: Vec<(String, &'static str)>,
+}
+
+/// 已加载的 pack 可达性位图（`pack-<checksum>.bitmap`）
+///
+/// 文件格式与 Git 相同：header（`BITM`、版本 1、选项、条目数、pack 校验和）、
+/// commit / tree / blob / tag 四个类型位图、各提交的可达性位图（可用 XOR 相对
+/// 前面的条目压缩），最后是文件校验和。位图只对包含完整对象闭包的 pack 有效。
+pub struct PackBitmap {
+    /// 第 i 位对应的对象哈希（pack 中按偏移排序）
+    shas: Vec<String>,
+    positions: HashMap<String, usize>,
+    types: Vec<Bitmap>,
+    commits: HashMap<String, Bitmap>,
+}
+
+thread_local! {
+    /// .bitmap 路径 -> 已加载的位图
+    static BITMAPS: RefCell<HashMap<PathBuf, Option<Rc<PackBitmap>>>> = RefCell::new(HashMap::new());
+}
+
+/// pack 对应的 .bitmap 路径
+pub fn bitmap_path(pack: &PackFile) -> PathBuf {
+    pack.pack_path.with_extension("bitmap")
+}
+
+/// pack 中的对象按偏移排序后的索引位置（即位图中每一位对应 .idx 中的第几个对象）
+fn pack_order(pack: &PackFile) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pack.index.len()).collect();
+    order.sort_by_key(|&i| pack.index.offset_at(i));
+    order
+}
+
+impl PackBitmap {
+    /// 获取仓库中带有位图的 pack 的位图（只使用第一个，结果会被缓存）
+    pub fn for_repo(repo_path: &str) -> Option<Rc<PackBitmap>> {
+        let packs = pack::packs(repo_path);
+        packs
+            .iter()
+            .filter(|p| bitmap_path(p).exists())
+            .find_map(PackBitmap::for_pack)
+    }
+
+    /// 加载 pack 的位图（结果会被缓存）
+    pub fn for_pack(pack: &PackFile) -> Option<Rc<PackBitmap>> {
+        let path = bitmap_path(pack);
+        if let Some(cached) = BITMAPS.with(|b| b.borrow().get(&path).cloned()) {
+            return cached;
+        }
+        let bitmap = std::fs::read(&path)
+            .ok()
+            .and_then(|data| PackBitmap::parse(&data, pack))
+            .map(Rc::new);
+        BITMAPS.with(|b| b.borrow_mut().insert(path, bitmap.clone()));
+        bitmap
+    }
+
+    /// 解析 .bitmap 文件内容，格式不符或与 pack 不匹配时返回 None
+    pub fn parse(data: &[u8], pack: &PackFile) -> Option<PackBitmap> {
+        let hash_len = pack.algo.raw_len();
+        if data.len() < 12 + 2 * hash_len || &data[0..4] != SIGNATURE || read_u16(data, 4)? != 1 {
+            return None;
+        }
+        let options = read_u16(data, 6)?;
+        if options & BITMAP_OPT_FULL_DAG == 0 || data[12..12 + hash_len] != pack.index.pack_checksum[..] {
+            return None;
+        }
+        let entry_count = read_u32(data, 8)? as usize;
+
+        let mut pos = 12 + hash_len;
+        let mut types = Vec::with_capacity(4);
+        for _ in TYPE_ORDER {
+            types.push(Bitmap::read_ewah(data, &mut pos)?);
+        }
+
+        // 各提交的位图：XOR 偏移非 0 时，实际位图 = 本条目 ^ 往前数第 xor_offset 个条目
+        let mut entries: Vec<(String, Bitmap)> = Vec::with_capacity(entry_count);
+        for i in 0..entry_count {
+            let index_pos = read_u32(data, pos)? as usize;
+            let xor_offset = *data.get(pos + 4)? as usize;
+            pos += 6;
+            let mut bitmap = Bitmap::read_ewah(data, &mut pos)?;
+            if xor_offset > 0 {
+                bitmap.xor(&entries.get(i.checked_sub(xor_offset)?)?.1);
+            }
+            if index_pos >= pack.index.len() {
+                return None;
+            }
+            entries.push((hex::encode(pack.index.sha_at(index_pos)), bitmap));
+        }
+
+        let shas: Vec<String> = pack_order(pack).into_iter().map(|i| hex::encode(pack.index.sha_at(i))).collect();
+        let positions = shas.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect();
+        Some(PackBitmap { shas, positions, types, commits: entries.into_iter().collect() })
+    }
+
+    /// 位图覆盖的对象数（即 pack 中的对象数）
+    pub fn len(&self) -> usize {
+        self.shas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shas.is_empty()
+    }
+
+    /// 带有位图的提交个数
+    pub fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    /// 第 `bit` 位对应的对象哈希
+    pub fn sha_at(&self, bit: usize) -> &str {
+        &self.shas[bit]
+    }
+
+    /// 可达对象中某一类型的个数
+    pub fn count_type(&self, reachable: &ReachableBitmap, kind: &str) -> usize {
+        let in_pack = TYPE_ORDER
+            .iter()
+            .position(|t| *t == kind)
+            .map_or(0, |i| reachable.bits.count_and(&self.types[i]));
+        in_pack + reachable.extra.iter().filter(|(_, k)| *k == kind).count()
+    }
+
+    /// 从 `roots` 出发计算可达对象
+    ///
+    /// 遇到带位图的提交时直接合并其位图，不再向下遍历；只有位图未覆盖的部分
+    /// （新提交、不在 pack 中的对象）才需要读取对象。与 `reachable_objects` 相同，
+    /// 需要读取的对象缺失或损坏时返回错误。
+    pub fn reachable(&self, db: &dyn ObjectDatabase, roots: &[String]) -> Result<ReachableBitmap, GitError> {
+        let mut result = ReachableBitmap { bits: Bitmap::default(), extra: Vec::new() };
+        let mut extra_seen = HashSet::new();
+        let mut stack: Vec<String> = roots.to_vec();
+
+        while let Some(sha) = stack.pop() {
+            if let Some(bitmap) = self.commits.get(&sha) {
+                result.bits.or(bitmap);
+                continue;
+            }
+            match self.positions.get(&sha) {
+                Some(&bit) if result.bits.get(bit) => continue,
+                Some(&bit) => result.bits.set(bit),
+                None if !extra_seen.insert(sha.clone()) => continue,
+                None => {}
+            }
+            let obj = read_reachable(db, &sha)?;
+            stack.extend(links(&obj, db.hash_algorithm()).ok_or_else(|| GitError::CorruptObject(sha.clone()))?);
+            if !self.positions.contains_key(&sha) {
+                result.extra.push((sha, obj.type_name()));
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// 对象直接引用的其他对象（子模块提交不在本仓库中，跳过），对象无法解析时返回 None
+fn links(obj: &Object, algo: HashAlgorithm) -> Option<Vec<String>> {
+    match obj {
+        Object::Commit(data) => Commit::parse(data).map(|c| std::iter::once(c.tree).chain(c.parents).collect()),
+        Object::Tree(data) => TreeProcessor::try_parse_tree(data, algo)
+            .map(|entries| entries.into_iter().filter(|e| e.mode != 0o160000).map(|e| e.hash).collect()),
+        Object::Tag(data) => String::from_utf8_lossy(data)
+            .lines()
+            .find_map(|l| l.strip_prefix("object "))
+            .map(|t| vec![t.to_string()]),
+        Object::Blob(_) => Some(Vec::new()),
+    }
+}
+
+/// 为 pack 写出可达性位图，返回生成位图的提交个数
+///
+/// # 参数
+/// - `pack`: 包含所有可达对象的 pack（通常是 repack 刚写出的 pack）
+/// - `kinds`: pack 中每个对象的类型
+/// - `tips`: 引用指向的提交，总会生成位图
+/// - `commits`: pack 中的所有提交（按提交时间从旧到新），每隔一段再选一个生成位图
+///
+/// 按从旧到新的顺序计算，较新的提交遍历到已有位图的祖先时直接合并，
+/// 不必重复遍历整段历史。
+pub fn write_bitmap(
+    repo_path: &str,
+    db: &dyn ObjectDatabase,
+    pack: &PackFile,
+    kinds: &HashMap<String, &'static str>,
+    tips: &[String],
+    commits: &[String],
+) -> io::Result<usize> {
+    let order = pack_order(pack);
+    let shas: Vec<String> = order.iter().map(|&i| hex::encode(pack.index.sha_at(i))).collect();
+    let positions: HashMap<String, usize> = shas.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect();
+
+    // 1️⃣ 选出需要位图的提交：引用指向的提交 + 每隔 COMMIT_INTERVAL 个提交
+    let tips: HashSet<&String> = tips.iter().collect();
+    let selected: Vec<&String> = commits
+        .iter()
+        .enumerate()
+        .filter(|(i, sha)| tips.contains(sha) || (i + 1) % COMMIT_INTERVAL == 0)
+        .map(|(_, sha)| sha)
+        .filter(|sha| positions.contains_key(*sha))
+        .collect();
+
+    // 2️⃣ 类型位图
+    let mut types = vec![Bitmap::default(); TYPE_ORDER.len()];
+    for (bit, sha) in shas.iter().enumerate() {
+        if let Some(t) = kinds.get(sha).and_then(|k| TYPE_ORDER.iter().position(|t| t == k)) {
+            types[t].set(bit);
+        }
+    }
+
+    // 3️⃣ 各提交的可达性位图（复用已算出的祖先位图）
+    let mut partial = PackBitmap {
+        shas: shas.clone(),
+        positions: positions.clone(),
+        types: Vec::new(),
+        commits: HashMap::new(),
+    };
+    for sha in &selected {
+        let reachable = partial
+            .reachable(db, &[sha.to_string()])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if let Some((missing, _)) = reachable.extra.first() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("object {} reachable from {} is not in the pack", missing, sha),
+            ));
+        }
+        partial.commits.insert(sha.to_string(), reachable.bits);
+    }
+
+    // 4️⃣ header + 类型位图 + 提交位图 + 校验和
+    let algo = pack.algo;
+    let mut out = SIGNATURE.to_vec();
+    out.extend(1u16.to_be_bytes());
+    out.extend(BITMAP_OPT_FULL_DAG.to_be_bytes());
+    out.extend((selected.len() as u32).to_be_bytes());
+    out.extend(&pack.index.pack_checksum);
+    for bitmap in &types {
+        bitmap.write_ewah(shas.len(), &mut out);
+    }
+    for sha in &selected {
+        let index_pos = pack.index.position(&hex::decode(sha).unwrap()).unwrap();
+        out.extend((index_pos as u32).to_be_bytes());
+        out.extend([0, 0]); // 不使用 XOR 压缩，flags 为 0
+        partial.commits[sha.as_str()].write_ewah(shas.len(), &mut out);
+    }
+    let checksum = algo.digest(&out);
+    out.extend(checksum);
+
+    let path = bitmap_path(pack);
+    write_file_atomic(&path, &out, Config::load(repo_path).fsync("pack-metadata"))?;
+    BITMAPS.with(|b| b.borrow_mut().remove(&path));
+    Ok(selected.len())
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(pos..pos + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::odb::MemoryObjectDatabase;
+    use crate::utils::fs::TempRepo;
+    use std::path::Path;
+
+    /// `git repack -adb` 生成的 pack 及位图：6 个提交、6 个 tree、6 个 blob、1 个附注标签
+    const FIXTURE: &str = "tests/fixtures/bitmap/pack-7a21f599e451f3dfe33edd44f5cdc730b046f4a0";
+    const HEAD: &str = "65d4dc8994c1cfb384caf8ec7dc03b1047604706";
+    const FIRST: &str = "3679a3daaeeb1aaef4128e63034c7d1eb502a95e";
+
+    fn fixture_path(ext: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE).with_extension(ext)
+    }
+
+    /// 把 pack 中的对象全部读入内存对象库，同时返回每个对象的类型
+    fn load_objects(pack: &PackFile) -> (MemoryObjectDatabase, HashMap<String, &'static str>) {
+        let db = MemoryObjectDatabase::new(pack.algo);
+        let mut kinds = HashMap::new();
+        for i in 0..pack.index.len() {
+            let obj = pack.read("", pack.index.sha_at(i)).unwrap();
+            kinds.insert(hex::encode(pack.index.sha_at(i)), obj.type_name());
+            db.write(&obj).unwrap();
+        }
+        (db, kinds)
+    }
+
+    fn bitmap_of(bits: &[usize]) -> Bitmap {
+        let mut bitmap = Bitmap::default();
+        for &bit in bits {
+            bitmap.set(bit);
+        }
+        bitmap
+    }
+
+    #[test]
+    fn ewah_round_trips() {
+        let mut long_run = vec![3];
+        long_run.extend(64..64 * 40);
+        long_run.extend([5000, 5001, 64 * 200]);
+        for bits in [vec![], vec![0], vec![63, 64, 127], long_run] {
+            let bitmap = bitmap_of(&bits);
+            let mut out = Vec::new();
+            bitmap.write_ewah(64 * 201, &mut out);
+            let mut pos = 0;
+            let read = Bitmap::read_ewah(&out, &mut pos).unwrap();
+            assert_eq!(pos, out.len());
+            assert_eq!(read.ones().collect::<Vec<_>>(), bits);
+        }
+    }
+
+    #[test]
+    fn ewah_encodes_runs_like_git() {
+        // 2 个全 0 字 + 1 个字面字 + 3 个全 1 字（ewah_serialize 的写法：每个 RLW 先重复字再字面字）
+        let mut bitmap = bitmap_of(&[128 + 1]);
+        for bit in 192..384 {
+            bitmap.set(bit);
+        }
+        let mut out = Vec::new();
+        bitmap.write_ewah(384, &mut out);
+        let words: Vec<u64> = out[8..out.len() - 4].chunks(8).map(|c| u64::from_be_bytes(c.try_into().unwrap())).collect();
+        assert_eq!(words, vec![(1 << 33) | (2 << 1), 2, (3 << 1) | 1]);
+        assert_eq!(&out[out.len() - 4..], &2u32.to_be_bytes());
+    }
+
+    #[test]
+    fn reads_git_bitmap() {
+        let pack = PackFile::open(&fixture_path("idx"), HashAlgorithm::Sha1).unwrap();
+        let bitmap = PackBitmap::parse(&std::fs::read(fixture_path("bitmap")).unwrap(), &pack).expect("bitmap");
+        assert_eq!(bitmap.len(), 19);
+        assert!(bitmap.commit_count() > 0);
+
+        let (db, _) = load_objects(&pack);
+        let all = bitmap.reachable(&db, &[HEAD.to_string()]).unwrap();
+        assert!(all.extra.is_empty());
+        let counts: Vec<usize> = TYPE_ORDER.iter().map(|t| bitmap.count_type(&all, t)).collect();
+        // `git rev-list --objects` 的结果：HEAD 可达 18 个对象，标签对象不可达
+        assert_eq!(all.bits.count(), 18);
+        assert_eq!(counts, vec![6, 6, 6, 0]);
+        assert_eq!(bitmap.reachable(&db, &[FIRST.to_string()]).unwrap().bits.count(), 7);
+
+        // 位图没有覆盖的对象缺失时返回错误，不能当作遍历的终点
+        let missing = "e93dc9a683581d9bd5ef76a734c014e08fc19aa4";
+        assert!(matches!(bitmap.reachable(&db, &[missing.to_string()]), Err(GitError::ObjectNotFound(_))));
+        let commit = format!(
+            "tree {}\nparent {}\nauthor A <a@example.com> 0 +0000\ncommitter A <a@example.com> 0 +0000\n\nnew\n",
+            missing, HEAD
+        );
+        let commit = db.write(&Object::Commit(commit.into_bytes())).unwrap();
+        let result = bitmap.reachable(&db, &[commit]);
+        assert!(matches!(result, Err(GitError::ObjectNotFound(ref sha)) if sha == missing));
+    }
+
+    #[test]
+    fn type_bitmaps_reencode_to_git_bytes() {
+        let data = std::fs::read(fixture_path("bitmap")).unwrap();
+        let mut pos = 12 + 20;
+        for _ in TYPE_ORDER {
+            let start = pos;
+            let bitmap = Bitmap::read_ewah(&data, &mut pos).unwrap();
+            let bit_size = read_u32(&data, start).unwrap() as usize;
+            let mut out = Vec::new();
+            bitmap.write_ewah(bit_size, &mut out);
+            assert_eq!(out, &data[start..pos]);
+        }
+    }
+
+    #[test]
+    fn written_bitmap_reads_back() {
+        let repo = TempRepo::new("bitmap");
+        let dir = pack::pack_dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        for ext in ["pack", "idx"] {
+            std::fs::copy(fixture_path(ext), dir.join("pack-test").with_extension(ext)).unwrap();
+        }
+        let pack = PackFile::open(&dir.join("pack-test.idx"), HashAlgorithm::Sha1).unwrap();
+        let (db, kinds) = load_objects(&pack);
+
+        let commits = vec![FIRST.to_string(), HEAD.to_string()];
+        assert_eq!(write_bitmap(repo.path(), &db, &pack, &kinds, &[HEAD.to_string()], &commits).unwrap(), 1);
+        let bitmap = PackBitmap::parse(&std::fs::read(bitmap_path(&pack)).unwrap(), &pack).expect("bitmap");
+        assert_eq!(bitmap.commit_count(), 1);
+
+        let all = bitmap.reachable(&db, &[HEAD.to_string()]).unwrap();
+        assert_eq!(all.bits.count(), 18);
+        assert_eq!(bitmap.count_type(&all, "blob"), 6);
+        assert_eq!(bitmap.reachable(&db, &[FIRST.to_string()]).unwrap().bits.count(), 7);
+    }
+}
@@ -1,4 +1,11 @@
 use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::utils::error::GitError;
+use crate::utils::hash::HashAlgorithm;
+use std::io::{self, Read, Write};
+
+/// 流式读写时每次处理的字节数
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
 
 /// Blob 对象处理器
 pub struct BlobProcessor;
@@ -6,12 +13,59 @@ pub struct BlobProcessor;
 impl BlobProcessor {
     /// 创建 Blob 对象（支持二进制文件）
     /// # 参数
-    /// - `repo_path`: 仓库路径
+    /// - `db`: 对象数据库
     /// - `content`: 文件内容（可二进制）
     /// # 返回值
-    /// - Blob 对象 SHA1 哈希
-    pub fn create_blob(repo_path: &str, content: &[u8]) -> String {
+    /// - Blob 对象哈希；内容带有 SHA-1 碰撞攻击特征时返回错误
+    pub fn create_blob(db: &dyn ObjectDatabase, content: &[u8]) -> Result<String, GitError> {
         let blob_obj = Object::Blob(content.to_vec()); // 创建 Blob 对象
-        blob_obj.save(repo_path) // 保存到 .git/objects 并返回哈希
+        db.write(&blob_obj) // 保存到对象库并返回哈希
+    }
+
+    /// 从 reader 流式创建 Blob 对象，内容不会整体读入内存
+    /// # 参数
+    /// - `db`: 对象数据库
+    /// - `reader`: 内容来源（通常是打开的文件）
+    /// - `size`: 内容长度（写入 header 需要预先知道）
+    pub fn create_blob_from_reader(db: &dyn ObjectDatabase, reader: &mut dyn Read, size: u64) -> io::Result<String> {
+        db.write_blob_stream(reader, size)
+    }
+
+    /// 流式计算 reader 内容作为 Blob 的对象哈希（不写入对象库）
+    pub fn hash_reader(algo: HashAlgorithm, reader: &mut dyn Read, size: u64) -> io::Result<String> {
+        Self::copy_hashed(algo, reader, size, &mut io::sink())
+    }
+
+    /// 把 `blob <size>\0` header 和 reader 中恰好 `size` 字节的内容分块写入 `out`，
+    /// 同时计算对象哈希
+    ///
+    /// 实际读到的长度与 `size` 不符（文件在读取过程中被修改）或检测到 SHA-1 碰撞攻击时返回错误。
+    pub fn copy_hashed(algo: HashAlgorithm, reader: &mut dyn Read, size: u64, out: &mut dyn Write) -> io::Result<String> {
+        let header = format!("blob {}\0", size);
+        let mut hasher = algo.hasher();
+        hasher.update(header.as_bytes());
+        out.write_all(header.as_bytes())?;
+
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut remaining = size;
+        while remaining > 0 {
+            let want = remaining.min(buf.len() as u64) as usize;
+            let n = reader.read(&mut buf[..want])?;
+            if n == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file shrank while being read"));
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n])?;
+            remaining -= n as u64;
+        }
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "file grew while being read"));
+        }
+
+        // 内容带有 SHA-1 碰撞攻击特征时拒绝，而不是写入一个可能被伪造的对象
+        hasher
+            .finalize_checked()
+            .map(hex::encode)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
     }
 }
@@ -0,0 +1,199 @@
+use crate::core::odb::ObjectDatabase;
+use crate::core::tree::TreeProcessor;
+use std::collections::BTreeSet;
+
+/// 每个路径使用的哈希函数个数（与 Git 默认值相同）
+pub const NUM_HASHES: u32 = 7;
+/// 每个路径占用的位数
+pub const BITS_PER_ENTRY: u32 = 10;
+/// 改动路径超过该数量时不再记录具体路径，过滤器置为全 1
+pub const MAX_CHANGED_PATHS: usize = 512;
+
+const SEED_1: u32 = 0x293a_e76f;
+const SEED_2: u32 = 0x7e64_6e2c;
+
+/// 单个提交的 changed-path Bloom 过滤器
+///
+/// 记录提交相对第一个父提交改动过的路径（含各级父目录）。查询结果为“一定没有改动”
+/// 或“可能改动过”，因此只能用来跳过提交，不能代替 tree diff。
+pub struct BloomFilter<'a> {
+    data: &'a [u8],
+    version: u32,
+    num_hashes: u32,
+}
+
+impl<'a> BloomFilter<'a> {
+    /// 从 commit-graph 的 BDAT 数据构造过滤器
+    pub fn new(data: &'a [u8], version: u32, num_hashes: u32) -> Self {
+        BloomFilter { data, version, num_hashes }
+    }
+
+    /// 路径是否可能被改动过（false 表示一定没有改动）
+    pub fn maybe_contains(&self, path: &str) -> bool {
+        if self.data.is_empty() {
+            return true;
+        }
+        let bits = self.data.len() as u64 * 8;
+        key_hashes(path.as_bytes(), self.version, self.num_hashes).into_iter().all(|hash| {
+            let pos = hash as u64 % bits;
+            self.data[(pos / 8) as usize] & (1 << (pos % 8)) != 0
+        })
+    }
+}
+
+/// 计算提交相对第一个父提交的 Bloom 过滤器数据（Git 兼容格式，版本 `version`）
+///
+/// 改动的文件及其所有父目录都会加入过滤器；没有改动时为 1 个全 0 字节，
+/// 改动路径过多时为 1 个全 1 字节（查询总是返回“可能改动过”）。
+pub fn compute_filter(db: &dyn ObjectDatabase, tree: &str, first_parent_tree: Option<&str>, version: u32) -> Vec<u8> {
+    let mut paths = BTreeSet::new();
+    for path in TreeProcessor::changed_paths(db, first_parent_tree, Some(tree)) {
+        let mut end = path.len();
+        paths.insert(path.clone());
+        while let Some(slash) = path[..end].iter().rposition(|&b| b == b'/') {
+            paths.insert(path[..slash].to_vec());
+            end = slash;
+        }
+    }
+
+    // 与 Git 相同，父目录也计入上限：一个目录下 512 个新文件共 513 个路径，已经超限
+    if paths.len() > MAX_CHANGED_PATHS {
+        return vec![0xff];
+    }
+    let len = (paths.len() * BITS_PER_ENTRY as usize).div_ceil(8).max(1);
+    let mut data = vec![0u8; len];
+    let bits = len as u64 * 8;
+    for path in &paths {
+        for hash in key_hashes(path, version, NUM_HASHES) {
+            let pos = hash as u64 % bits;
+            data[(pos / 8) as usize] |= 1 << (pos % 8);
+        }
+    }
+    data
+}
+
+/// 路径对应的 `num_hashes` 个哈希值：h0 + i * h1（双重哈希）
+fn key_hashes(path: &[u8], version: u32, num_hashes: u32) -> Vec<u32> {
+    let h0 = murmur3(SEED_1, path, version);
+    let h1 = murmur3(SEED_2, path, version);
+    (0..num_hashes).map(|i| h0.wrapping_add(i.wrapping_mul(h1))).collect()
+}
+
+/// 32 位 MurmurHash3
+///
+/// 版本 1 与 Git 早期实现保持一致：字节按有符号 char 读取，非 ASCII 路径的结果
+/// 与标准 MurmurHash3 不同；版本 2 为标准实现。
+fn murmur3(seed: u32, data: &[u8], version: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let byte = |b: u8| if version == 1 { b as i8 as i32 as u32 } else { b as u32 };
+
+    let mut hash = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = byte(chunk[0]) | byte(chunk[1]) << 8 | byte(chunk[2]) << 16 | byte(chunk[3]) << 24;
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, &b) in tail.iter().enumerate().rev() {
+            k ^= byte(b) << (8 * i);
+        }
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::core::odb::MemoryObjectDatabase;
+    use crate::core::tree::TreeEntry;
+    use crate::utils::hash::HashAlgorithm;
+
+    fn blob(db: &dyn ObjectDatabase, content: &str) -> String {
+        db.write(&Object::Blob(content.as_bytes().to_vec())).unwrap()
+    }
+
+    /// 条目需按 Git 的顺序给出
+    fn tree(db: &dyn ObjectDatabase, entries: &[(&str, &str, bool)]) -> String {
+        let entries = entries
+            .iter()
+            .map(|&(name, hash, is_dir)| TreeEntry {
+                name: name.as_bytes().to_vec(),
+                hash: hash.to_string(),
+                mode: if is_dir { 0o40000 } else { 0o100644 },
+                is_dir,
+            })
+            .collect();
+        TreeProcessor::create_tree(db, entries).unwrap()
+    }
+
+    #[test]
+    fn murmur3_matches_git_test_vectors() {
+        // t0095-bloom.sh
+        assert_eq!(murmur3(0, b"", 1), 0x0000_0000);
+        assert_eq!(murmur3(0, b"Hello world!", 1), 0x627b_0c2c);
+        assert_eq!(murmur3(0, b"The quick brown fox jumps over the lazy dog", 1), 0x2e4f_f723);
+        // 非 ASCII 字节：版本 2 为标准 MurmurHash3，版本 1 沿用 Git 早期的有符号 char 实现
+        assert_eq!(murmur3(0, b"\x99\xaa\xbb\xcc\xdd\xee\xff", 2), 0xa183_ccfd);
+        assert_ne!(murmur3(0, b"\x99\xaa\xbb\xcc\xdd\xee\xff", 1), 0xa183_ccfd);
+    }
+
+    #[test]
+    fn filters_match_git_commit_graph() {
+        // `git commit-graph write --reachable --changed-paths` 生成的 BDAT 内容：
+        // 第一个提交新增 a/b.txt、c；第二个提交修改 a/b.txt 并新增 x/y/z
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let a1 = tree(&db, &[("b.txt", &blob(&db, "1\n"), false)]);
+        let one = tree(&db, &[("a", &a1, true), ("c", &blob(&db, "2\n"), false)]);
+        assert_eq!(one, "2c48771b7d8181f57913f90dc0946a29bf1bc16c");
+
+        let a2 = tree(&db, &[("b.txt", &blob(&db, "3\n"), false)]);
+        let y = tree(&db, &[("z", &blob(&db, "4\n"), false)]);
+        let x = tree(&db, &[("y", &y, true)]);
+        let two = tree(&db, &[("a", &a2, true), ("c", &blob(&db, "2\n"), false), ("x", &x, true)]);
+        assert_eq!(two, "6cd6a7ff2b5bd471ce7292924f40e05bc7293e5d");
+
+        assert_eq!(hex::encode(compute_filter(&db, &one, None, 1)), "a6fad705");
+        let filter = compute_filter(&db, &two, Some(&one), 1);
+        assert_eq!(hex::encode(&filter), "a0fbc30d4d6f20");
+
+        let bloom = BloomFilter::new(&filter, 1, NUM_HASHES);
+        for path in ["a", "a/b.txt", "x", "x/y", "x/y/z"] {
+            assert!(bloom.maybe_contains(path));
+        }
+    }
+
+    #[test]
+    fn empty_and_oversized_changes() {
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let root = tree(&db, &[("r", &blob(&db, "r\n"), false)]);
+        assert_eq!(compute_filter(&db, &root, Some(&root), 1), vec![0]);
+
+        // Git 2.39：一个目录下 511 个新文件（加上目录共 512 个路径）仍记录过滤器，512 个则置为全 1
+        let with_files = |count: usize| {
+            let mut files: Vec<(String, String)> =
+                (0..count).map(|i| (format!("f{}", i), blob(&db, &format!("{}\n", i)))).collect();
+            files.sort();
+            let entries: Vec<(&str, &str, bool)> = files.iter().map(|(n, h)| (n.as_str(), h.as_str(), false)).collect();
+            let dir = tree(&db, &entries);
+            tree(&db, &[("d", &dir, true), ("r", &blob(&db, "r\n"), false)])
+        };
+        assert_eq!(compute_filter(&db, &with_files(511), Some(&root), 1).len(), 640);
+        assert_eq!(compute_filter(&db, &with_files(512), Some(&root), 1), vec![0xff]);
+    }
+}
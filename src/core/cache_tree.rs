@@ -0,0 +1,210 @@
+use crate::utils::hash::HashAlgorithm;
+use std::collections::BTreeMap;
+
+/// index 的 TREE 扩展（cache tree）：记录每个目录对应的 tree 对象
+///
+/// 提交时未改动的目录直接复用缓存的 tree 哈希，只有被 `stage_file` / `unstage_file`
+/// 改动过的目录（及其各级父目录）才需要重新生成 tree 对象。
+#[derive(Debug, Clone)]
+pub struct CacheTree {
+    /// 该目录覆盖的 index 条目数（递归），-1 表示缓存已失效
+    pub entry_count: i32,
+    /// 目录对应的 tree 哈希，失效时为 None
+    pub sha: Option<String>,
+    /// 子目录名（原始字节）-> 子目录的缓存
+    pub children: BTreeMap<Vec<u8>, CacheTree>,
+}
+
+impl Default for CacheTree {
+    /// 已失效的空缓存
+    fn default() -> Self {
+        CacheTree { entry_count: -1, sha: None, children: BTreeMap::new() }
+    }
+}
+
+impl CacheTree {
+    /// 缓存的 tree 哈希是否可以直接使用
+    pub fn is_valid(&self) -> bool {
+        self.entry_count >= 0 && self.sha.is_some()
+    }
+
+    /// 标记缓存已失效（子目录的缓存保留）
+    pub fn invalidate(&mut self) {
+        self.entry_count = -1;
+        self.sha = None;
+    }
+
+    /// 路径（`a/b/c`）被改动：使其所在目录及各级父目录的缓存失效
+    ///
+    /// 若同名的子目录存在（例如目录被替换为文件），一并移除。
+    pub fn invalidate_path(&mut self, path: &[u8]) {
+        self.invalidate();
+        match path.iter().position(|&b| b == b'/') {
+            Some(slash) => {
+                if let Some(child) = self.children.get_mut(&path[..slash]) {
+                    child.invalidate_path(&path[slash + 1..]);
+                }
+            }
+            None => {
+                self.children.remove(path);
+            }
+        }
+    }
+
+    /// 所有有效缓存记录的 tree 哈希（递归包含子目录）
+    ///
+    /// 这些 tree 可能还没有被任何提交引用，prune / fsck 需要把它们当作可达起点。
+    pub fn valid_shas(&self) -> Vec<&str> {
+        let mut shas = Vec::new();
+        self.collect_valid_shas(&mut shas);
+        shas
+    }
+
+    fn collect_valid_shas<'a>(&'a self, out: &mut Vec<&'a str>) {
+        if let Some(sha) = self.sha.as_deref().filter(|_| self.is_valid()) {
+            out.push(sha);
+        }
+        for child in self.children.values() {
+            child.collect_valid_shas(out);
+        }
+    }
+
+    /// 解析 TREE 扩展内容，格式不符时返回 None（与 Git 一样直接丢弃缓存）
+    pub fn parse(data: &[u8], algo: HashAlgorithm) -> Option<CacheTree> {
+        let mut pos = 0;
+        let (_, tree) = Self::parse_node(data, &mut pos, algo)?;
+        (pos == data.len()).then_some(tree)
+    }
+
+    /// 节点格式：`<名字>\0<条目数> <子目录数>\n`，条目数非负时后面跟 tree 的二进制哈希，
+    /// 之后依次是各子目录节点
+    fn parse_node(data: &[u8], pos: &mut usize, algo: HashAlgorithm) -> Option<(Vec<u8>, CacheTree)> {
+        let nul = *pos + data[*pos..].iter().position(|&b| b == 0)?;
+        let name = data[*pos..nul].to_vec();
+        let newline = nul + data[nul..].iter().position(|&b| b == b'\n')?;
+        let header = std::str::from_utf8(&data[nul + 1..newline]).ok()?;
+        let (count, subtrees) = header.split_once(' ')?;
+        let entry_count: i32 = count.parse().ok()?;
+        let subtrees: usize = subtrees.parse().ok()?;
+        *pos = newline + 1;
+
+        let sha = if entry_count >= 0 {
+            let raw = data.get(*pos..*pos + algo.raw_len())?;
+            *pos += algo.raw_len();
+            Some(hex::encode(raw))
+        } else {
+            None
+        };
+        let mut children = BTreeMap::new();
+        for _ in 0..subtrees {
+            let (child_name, child) = Self::parse_node(data, pos, algo)?;
+            children.insert(child_name, child);
+        }
+        Some((name, CacheTree { entry_count, sha, children }))
+    }
+
+    /// 生成 TREE 扩展内容（子目录按名字长度、再按字节序排列，与 Git 相同）
+    pub fn write(&self, out: &mut Vec<u8>) {
+        self.write_node(b"", out);
+    }
+
+    fn write_node(&self, name: &[u8], out: &mut Vec<u8>) {
+        out.extend(name);
+        out.push(0);
+        match &self.sha {
+            Some(sha) if self.entry_count >= 0 => {
+                out.extend(format!("{} {}\n", self.entry_count, self.children.len()).as_bytes());
+                out.extend(hex::decode(sha).unwrap());
+            }
+            _ => out.extend(format!("-1 {}\n", self.children.len()).as_bytes()),
+        }
+        let mut children: Vec<(&Vec<u8>, &CacheTree)> = self.children.iter().collect();
+        children.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(b.0)));
+        for (child_name, child) in children {
+            child.write_node(child_name, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "ab64f1ba6b75bd1800bfa5d8d0627bb51857f989";
+    const D: &str = "8d919e7c2c845a06d0583ec0ebca677b5a533c92";
+    const D_E: &str = "8fecaa0af926d864d8e55f05104cabb500c3c239";
+
+    /// 取出 git 写出的 index 中 TREE 扩展的内容
+    fn tree_extension(name: &str) -> Vec<u8> {
+        let index = std::fs::read(format!("{}/tests/fixtures/index/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap();
+        let pos = index.windows(4).position(|w| w == b"TREE").unwrap();
+        let size = u32::from_be_bytes(index[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        index[pos + 8..pos + 8 + size].to_vec()
+    }
+
+    fn write(tree: &CacheTree) -> Vec<u8> {
+        let mut out = Vec::new();
+        tree.write(&mut out);
+        out
+    }
+
+    #[test]
+    fn git_tree_extension_round_trips() {
+        // a.txt d/e/f d/g h，git write-tree 之后
+        let data = tree_extension("v2-tree");
+        let tree = CacheTree::parse(&data, HashAlgorithm::Sha1).unwrap();
+        assert_eq!((tree.entry_count, tree.sha.as_deref()), (4, Some(ROOT)));
+        let d = &tree.children[b"d".as_slice()];
+        assert_eq!((d.entry_count, d.sha.as_deref()), (2, Some(D)));
+        let e = &d.children[b"e".as_slice()];
+        assert_eq!((e.entry_count, e.sha.as_deref()), (1, Some(D_E)));
+        assert!(e.children.is_empty());
+        assert_eq!(write(&tree), data);
+        assert_eq!(tree.valid_shas(), [ROOT, D, D_E]);
+    }
+
+    #[test]
+    fn git_partially_invalid_tree_round_trips() {
+        // 之后修改并暂存 d/g：根目录和 d 失效，d/e 仍然有效
+        let data = tree_extension("v2-partial");
+        let tree = CacheTree::parse(&data, HashAlgorithm::Sha1).unwrap();
+        assert!(!tree.is_valid());
+        assert!(!tree.children[b"d".as_slice()].is_valid());
+        assert!(tree.children[b"d".as_slice()].children[b"e".as_slice()].is_valid());
+        assert_eq!(write(&tree), data);
+        assert_eq!(tree.valid_shas(), [D_E]);
+    }
+
+    #[test]
+    fn invalidate_path_matches_git() {
+        let mut tree = CacheTree::parse(&tree_extension("v2-tree"), HashAlgorithm::Sha1).unwrap();
+        tree.invalidate_path(b"d/g");
+        assert_eq!(write(&tree), tree_extension("v2-partial"));
+
+        // d 被替换为文件：整个子目录的缓存被移除
+        tree.invalidate_path(b"d");
+        assert!(tree.children.is_empty());
+        assert!(tree.valid_shas().is_empty());
+    }
+
+    #[test]
+    fn children_are_written_shortest_name_first() {
+        let mut tree = CacheTree::default();
+        for name in ["bb", "c", "a"] {
+            tree.children.insert(name.as_bytes().to_vec(), CacheTree::default());
+        }
+        assert_eq!(write(&tree), b"\0-1 3\na\0-1 0\nc\0-1 0\nbb\0-1 0\n");
+    }
+
+    #[test]
+    fn malformed_extension_is_dropped() {
+        let data = tree_extension("v2-tree");
+        assert!(CacheTree::parse(&data[..data.len() - 1], HashAlgorithm::Sha1).is_none());
+        let mut trailing = data.clone();
+        trailing.push(0);
+        assert!(CacheTree::parse(&trailing, HashAlgorithm::Sha1).is_none());
+        assert!(CacheTree::parse(b"\0x 0\n", HashAlgorithm::Sha1).is_none());
+        // 声明了子目录但数据不足
+        assert!(CacheTree::parse(b"\0-1 1\n", HashAlgorithm::Sha1).is_none());
+    }
+}
@@ -1,34 +1,384 @@
 use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::utils::error::GitError;
 use chrono::Local;
+use std::fmt;
+
+/// 作者 / 提交者身份：`Name <email> <unix 时间戳> <时区>`
+///
+/// 名字和邮箱按原始字节保存（不一定是 UTF-8）。解析得到的身份同时保留原始写法，
+/// 序列化时原样写回，不会因为空格等不规范之处改变提交的哈希。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    name: Vec<u8>,
+    email: Vec<u8>,
+    /// Unix 时间戳（秒）
+    time: i64,
+    /// 时区偏移，保留原始写法，例如 `+0800`、`-0130`
+    tz: String,
+    /// 整行的原始字节
+    raw: Vec<u8>,
+}
+
+impl Signature {
+    /// 使用当前时间和本地时区创建身份
+    pub fn now(name: &str, email: &str) -> Self {
+        let now = Local::now();
+        let offset = now.offset().local_minus_utc() / 60;
+        let sign = if offset < 0 { '-' } else { '+' };
+        let tz = format!("{}{:02}{:02}", sign, offset.abs() / 60, offset.abs() % 60);
+        Signature {
+            name: name.as_bytes().to_vec(),
+            email: email.as_bytes().to_vec(),
+            time: now.timestamp(),
+            raw: format!("{} <{}> {} {}", name, email, now.timestamp(), tz).into_bytes(),
+            tz,
+        }
+    }
+
+    /// 从环境变量读取身份（`GIT_AUTHOR_NAME` / `GIT_COMMITTER_EMAIL` 等），
+    /// 未设置时使用默认身份
+    pub fn from_env(role: &str) -> Self {
+        let name = std::env::var(format!("GIT_{}_NAME", role)).unwrap_or_else(|_| "You".to_string());
+        let email = std::env::var(format!("GIT_{}_EMAIL", role)).unwrap_or_else(|_| "you@example.com".to_string());
+        Self::now(&name, &email)
+    }
+
+    /// 解析 `Name <email> 1700000000 +0800`
+    ///
+    /// 与 Git 一样容忍名字为空、`<` 前缺少空格等写法。
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        let open = raw.iter().position(|&b| b == b'<')?;
+        let close = open + raw[open..].iter().position(|&b| b == b'>')?;
+        let name = raw[..open].strip_suffix(b" ").unwrap_or(&raw[..open]);
+        let rest = std::str::from_utf8(&raw[close + 1..]).ok()?;
+        let (time, tz) = rest.trim_start_matches(' ').split_once(' ')?;
+        Some(Signature {
+            name: name.to_vec(),
+            email: raw[open + 1..close].to_vec(),
+            time: time.parse().ok()?,
+            tz: tz.to_string(),
+            raw: raw.to_vec(),
+        })
+    }
+
+    /// 名字（原始字节）
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// 邮箱（原始字节）
+    pub fn email(&self) -> &[u8] {
+        &self.email
+    }
+
+    /// Unix 时间戳（秒）
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+
+    /// 时区偏移的原始写法
+    pub fn tz(&self) -> &str {
+        &self.tz
+    }
+
+    /// 序列化后的字节（即解析时的原始写法）
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// 时区偏移（分钟）
+    pub fn tz_offset_minutes(&self) -> i32 {
+        let sign = if self.tz.starts_with('-') { -1 } else { 1 };
+        let digits = self.tz.trim_start_matches(['+', '-']);
+        let value: i32 = digits.parse().unwrap_or(0);
+        sign * (value / 100 * 60 + value % 100)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.raw))
+    }
+}
+
+/// 提交对象中 tree / parent / author / committer 以外的头部（如 encoding / mergetag / gpgsig），
+/// 标签对象中 tagger 之后的头部也用它保存
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraHeader {
+    pub key: String,
+    /// 原始值，多行值内部用 `\n` 连接（续行开头的空格已去掉）
+    pub value: Vec<u8>,
+    /// 该头部之前有几行标准头部，用于按原来的位置写回
+    pub after: usize,
+}
+
+/// 解析后的提交对象
+///
+/// 标准头部的顺序固定为 tree、parent*、author、committer，其他头部可以出现在任意位置，
+/// 空行之后是提交信息。多行头部的续行以空格开头。提交信息和其他头部的值都按原始字节保存
+/// （编码由 `encoding` 头部决定），`parse` 之后再 `serialize` 得到的内容与原对象逐字节相同。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    pub extra_headers: Vec<ExtraHeader>,
+    pub message: Vec<u8>,
+    /// 头部与提交信息之间是否有空行（个别工具生成的无信息提交没有）
+    has_separator: bool,
+}
+
+impl Commit {
+    /// 创建新提交（没有其他头部）
+    pub fn new(tree: String, parents: Vec<String>, author: Signature, committer: Signature, message: Vec<u8>) -> Self {
+        Commit { tree, parents, author, committer, extra_headers: Vec::new(), message, has_separator: true }
+    }
+
+    /// 解析提交对象内容（不含 `commit <len>\0` header）
+    pub fn parse(data: &[u8]) -> Option<Commit> {
+        let (headers, message, has_separator) = match data.windows(2).position(|w| w == b"\n\n") {
+            Some(pos) => (&data[..pos], &data[pos + 2..], true),
+            // 没有空行时最后一个头部仍必须以换行结尾
+            None => (data.strip_suffix(b"\n")?, &data[data.len()..], false),
+        };
+
+        let mut tree = None;
+        let mut parents = Vec::new();
+        let mut author = None;
+        let mut committer = None;
+        let mut extra_headers: Vec<ExtraHeader> = Vec::new();
+        // 已解析的标准头部行数
+        let mut standard = 0;
+
+        for line in headers.split(|&b| b == b'\n') {
+            // 续行：追加到上一个其他头部
+            if let Some(cont) = line.strip_prefix(b" ") {
+                let last = extra_headers.last_mut().filter(|h| h.after == standard)?;
+                last.value.push(b'\n');
+                last.value.extend_from_slice(cont);
+                continue;
+            }
+            let space = line.iter().position(|&b| b == b' ')?;
+            let (key, value) = (&line[..space], &line[space + 1..]);
+            match key {
+                b"tree" if tree.is_none() => tree = Some(hex_value(value)?),
+                b"parent" if tree.is_some() && author.is_none() => parents.push(hex_value(value)?),
+                b"author" if tree.is_some() && author.is_none() => author = Some(Signature::parse(value)?),
+                b"committer" if author.is_some() && committer.is_none() => {
+                    committer = Some(Signature::parse(value)?)
+                }
+                _ => {
+                    extra_headers.push(ExtraHeader {
+                        key: String::from_utf8(key.to_vec()).ok()?,
+                        value: value.to_vec(),
+                        after: standard,
+                    });
+                    continue;
+                }
+            }
+            standard += 1;
+        }
+
+        Some(Commit {
+            tree: tree?,
+            parents,
+            author: author?,
+            committer: committer?,
+            extra_headers,
+            message: message.to_vec(),
+            has_separator,
+        })
+    }
+
+    /// 序列化为提交对象内容
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut standard: Vec<Vec<u8>> = vec![format!("tree {}", self.tree).into_bytes()];
+        standard.extend(self.parents.iter().map(|p| format!("parent {}", p).into_bytes()));
+        standard.push([b"author ", self.author.as_bytes()].concat());
+        standard.push([b"committer ", self.committer.as_bytes()].concat());
+
+        let mut out = Vec::new();
+        let mut extras = self.extra_headers.iter().peekable();
+        for (i, line) in standard.iter().enumerate() {
+            while let Some(header) = extras.next_if(|h| h.after <= i) {
+                write_extra_header(&mut out, header);
+            }
+            out.extend_from_slice(line);
+            out.push(b'\n');
+        }
+        for header in extras {
+            write_extra_header(&mut out, header);
+        }
+        if self.has_separator {
+            out.push(b'\n'); // 空行分隔头部和提交信息
+        }
+        out.extend_from_slice(&self.message);
+        out
+    }
+
+    /// 从对象库读取并解析提交
+    pub fn load(db: &dyn ObjectDatabase, sha: &str) -> Option<Commit> {
+        match db.read(sha)? {
+            Object::Commit(data) => Commit::parse(&data),
+            _ => None,
+        }
+    }
+
+    /// 保存到对象库，返回提交哈希
+    pub fn save(&self, db: &dyn ObjectDatabase) -> Result<String, GitError> {
+        db.write(&Object::Commit(self.serialize()))
+    }
+
+    /// 提交信息的第一行（非 UTF-8 字节按替换字符显示）
+    pub fn summary(&self) -> String {
+        let line = self.message.split(|&b| b == b'\n').next().unwrap_or(b"");
+        String::from_utf8_lossy(line).into_owned()
+    }
+}
+
+/// tree / parent 的值必须是小写十六进制哈希
+fn hex_value(value: &[u8]) -> Option<String> {
+    let hex = std::str::from_utf8(value).ok()?;
+    hex.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)).then(|| hex.to_string())
+}
+
+/// 写出一个其他头部，多行值的续行以空格开头
+pub(crate) fn write_extra_header(out: &mut Vec<u8>, header: &ExtraHeader) {
+    out.extend_from_slice(header.key.as_bytes());
+    out.push(b' ');
+    for (i, line) in header.value.split(|&b| b == b'\n').enumerate() {
+        if i > 0 {
+            out.extend_from_slice(b"\n ");
+        }
+        out.extend_from_slice(line);
+    }
+    out.push(b'\n');
+}
 
 pub struct CommitBuilder;
 
 impl CommitBuilder {
-    /// 创建新提交对象
+    /// 创建新提交对象，返回提交哈希（SHA-1 碰撞时返回错误）
     pub fn create_commit(
-        repo_path: &str,
+        db: &dyn ObjectDatabase,
         tree_hash: String,               // 关联的树对象哈希
         parent_commit: Option<String>,   // 父提交哈希
         author_info: String,             // 作者信息，如 "Tom <tom@example.com>"
         commit_message: String           // 提交信息
-    ) -> String {
-        // 获取当前时间戳（RFC 2822 格式）
-        let timestamp = Local::now().to_rfc2822();
-
-        // 构造提交内容
-        let mut commit_content = format!("tree {}\n", tree_hash);
-        if let Some(parent_hash) = parent_commit {
-            commit_content.push_str(&format!("parent {}\n", parent_hash));
-        }
-        commit_content.push_str(&format!("author {} {}\n", author_info, timestamp));
-        commit_content.push_str(&format!("committer {} {}\n", author_info, timestamp));
-        commit_content.push_str("\n"); // 空行分隔头部和提交信息
-        commit_content.push_str(&commit_message);
+    ) -> Result<String, GitError> {
+        // 解析 "Name <email>"，附上当前时间和时区
+        let (name, email) = match author_info.split_once('<') {
+            Some((name, rest)) => (name.trim(), rest.trim_end_matches('>').trim()),
+            None => (author_info.trim(), ""),
+        };
+        let signature = Signature::now(name, email);
+
+        let commit = Commit::new(
+            tree_hash,
+            parent_commit.into_iter().collect(),
+            signature.clone(),
+            signature,
+            commit_message.into_bytes(),
+        );
+        commit.save(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::HashAlgorithm;
+
+    fn hash(data: &[u8]) -> String {
+        Object::Commit(data.to_vec()).try_hash(HashAlgorithm::Sha1).unwrap()
+    }
+
+    /// 由 `git -c i18n.commitEncoding=ISO-8859-1 commit` 生成：作者名和提交信息都是 Latin-1
+    const LATIN1: &[u8] = b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751\n\
+        parent 190af364b95b96c0d2189c597a555b153d08f448\n\
+        author Jos\xe9 <t@e> 1700000000 +0800\n\
+        committer T <t@e> 1700000000 +0800\n\
+        encoding ISO-8859-1\n\
+        \n\
+        caf\xe9\n";
 
-        // 转为二进制 Vec<u8> 保存
-        let commit_obj = Object::Commit(commit_content.into_bytes());
-        let commit_hash = commit_obj.save(repo_path);
+    #[test]
+    fn round_trips_non_utf8_commit_with_encoding() {
+        assert_eq!(hash(LATIN1), "d1f0e8d6f484fd6e0f4f89882edbfb6d73a41de7");
+        let commit = Commit::parse(LATIN1).expect("commit");
+        assert_eq!(commit.author.name(), b"Jos\xe9");
+        assert_eq!(commit.author.time(), 1_700_000_000);
+        assert_eq!(commit.author.tz_offset_minutes(), 480);
+        assert_eq!(commit.extra_headers.len(), 1);
+        assert_eq!(commit.extra_headers[0].key, "encoding");
+        assert_eq!(commit.extra_headers[0].value, b"ISO-8859-1");
+        assert_eq!(commit.message, b"caf\xe9\n");
+        assert_eq!(commit.serialize(), LATIN1);
+    }
+
+    #[test]
+    fn round_trips_multiline_header_and_empty_name() {
+        let data = b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751\n\
+            author <t@e> 1700000000 +0800\n\
+            committer T <t@e> 1700000000 +0800\n\
+            gpgsig -----BEGIN PGP SIGNATURE-----\n \n iQEz\n -----END PGP SIGNATURE-----\n\
+            \n\
+            signed\n";
+        assert_eq!(hash(data), "8c5b76bc91a7453d495af4342399a646efb63e7b");
+        let commit = Commit::parse(data).expect("commit");
+        assert_eq!(commit.author.name(), b"");
+        assert_eq!(commit.author.email(), b"t@e");
+        assert_eq!(commit.extra_headers[0].value, b"-----BEGIN PGP SIGNATURE-----\n\niQEz\n-----END PGP SIGNATURE-----");
+        assert_eq!(commit.serialize(), data);
+    }
+
+    #[test]
+    fn round_trips_header_before_author_without_separator() {
+        let data = b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751\n\
+            x-origin imported\n\
+            author T <t@e> 1700000000 +0800\n\
+            committer T <t@e> 1700000000 +0800\n";
+        assert_eq!(hash(data), "d397a3fbe45dc82cb8a81bd0e69e80bbd72ded7b");
+        let commit = Commit::parse(data).expect("commit");
+        assert_eq!(commit.extra_headers[0].after, 1);
+        assert!(commit.message.is_empty());
+        assert_eq!(commit.serialize(), data);
+    }
+
+    #[test]
+    fn new_commit_matches_git_format() {
+        let sig = Signature::parse(b"T <t@e> 1700000000 +0800").unwrap();
+        let commit = Commit::new(
+            "bd3edf1ee10a88d7e88801d2ca828cd56852e751".to_string(),
+            vec![
+                "d1f0e8d6f484fd6e0f4f89882edbfb6d73a41de7".to_string(),
+                "aad66c0d02ae60283ff0199e3b5bae5d229b9753".to_string(),
+            ],
+            sig.clone(),
+            sig,
+            b"Merge tag 'v1'\n\nv1 tag\n".to_vec(),
+        );
+        // `git merge --no-ff v1` 生成的合并提交
+        assert_eq!(hash(&commit.serialize()), "65d4dc8994c1cfb384caf8ec7dc03b1047604706");
+        assert_eq!(commit.summary(), "Merge tag 'v1'");
+    }
+
+    #[test]
+    fn rejects_malformed_commits() {
+        // 缺少 committer
+        assert!(Commit::parse(b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751\nauthor T <t@e> 1 +0000\n\nm").is_none());
+        // 续行前没有可以追加的头部
+        assert!(Commit::parse(b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751\n continued\n\nm").is_none());
+        // 最后一个头部没有换行
+        assert!(Commit::parse(b"tree bd3edf1ee10a88d7e88801d2ca828cd56852e751").is_none());
+    }
 
-        commit_hash
+    #[test]
+    fn signature_now_serializes_its_fields() {
+        let sig = Signature::now("A U Thor", "author@example.com");
+        let parsed = Signature::parse(sig.as_bytes()).unwrap();
+        assert_eq!(parsed, sig);
     }
 }
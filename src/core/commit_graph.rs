@@ -0,0 +1,486 @@
+use crate::core::alternates::objects_dir;
+use crate::core::bloom::{self, BloomFilter};
+use crate::core::commit::Commit;
+use crate::core::config::Config;
+use crate::core::odb::ObjectDatabase;
+use crate::utils::fs::write_file_atomic;
+use crate::utils::hash::HashAlgorithm;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const SIGNATURE: &[u8; 4] = b"CGPH";
+const CHUNK_OID_FANOUT: u32 = 0x4f49_4446; // "OIDF"
+const CHUNK_OID_LOOKUP: u32 = 0x4f49_444c; // "OIDL"
+const CHUNK_COMMIT_DATA: u32 = 0x4344_4154; // "CDAT"
+const CHUNK_EXTRA_EDGES: u32 = 0x4544_4745; // "EDGE"
+const CHUNK_BLOOM_INDEX: u32 = 0x4249_4458; // "BIDX"
+const CHUNK_BLOOM_DATA: u32 = 0x4244_4154; // "BDAT"
+
+/// 写入的 Bloom 过滤器哈希版本（与 Git 默认值相同）
+const BLOOM_VERSION: u32 = 1;
+
+/// CDAT 中表示“没有这个父提交”的值
+const PARENT_NONE: u32 = 0x7000_0000;
+/// 第二个父提交字段的最高位：其余位是 EDGE 列表中的下标（章鱼合并）
+const PARENT_EXTRA_EDGES: u32 = 0x8000_0000;
+/// EDGE 列表中最后一个父提交的标记位
+const PARENT_LAST: u32 = 0x8000_0000;
+/// 拓扑层级（generation number v1）的最大值，只有 30 位
+const GENERATION_MAX: u32 = 0x3fff_ffff;
+/// 提交时间只有 34 位
+const COMMIT_TIME_MAX: u64 = (1 << 34) - 1;
+
+/// commit-graph 中记录的单个提交
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphCommit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    /// 提交者时间（Unix 时间戳）
+    pub commit_time: u64,
+    /// 拓扑层级：根提交为 1，其余为父提交最大层级 + 1
+    pub generation: u32,
+}
+
+/// 已加载的 commit-graph 文件（`.git/objects/info/commit-graph`）
+///
+/// 文件格式与 Git 相同：header、chunk 表、OIDF / OIDL / CDAT / EDGE 四个 chunk，
+/// 可选的 BIDX / BDAT（changed-path Bloom 过滤器），最后是整个文件的校验和。
+/// 查找提交时不需要解压和解析提交对象。
+pub struct CommitGraph {
+    algo: HashAlgorithm,
+    data: Vec<u8>,
+    fanout: usize,
+    oid_lookup: usize,
+    commit_data: usize,
+    extra_edges: Option<usize>,
+    /// (BIDX 偏移, BDAT 数据起始, BDAT 结束, 哈希版本, 哈希函数个数)
+    bloom: Option<(usize, usize, usize, u32, u32)>,
+    count: usize,
+}
+
+thread_local! {
+    /// 仓库路径 -> 已加载的 commit-graph（None 表示文件不存在或无效）
+    static GRAPHS: RefCell<HashMap<String, Option<Rc<CommitGraph>>>> = RefCell::new(HashMap::new());
+}
+
+/// commit-graph 文件路径
+pub fn graph_path(repo_path: &str) -> PathBuf {
+    objects_dir(repo_path).join("info").join("commit-graph")
+}
+
+impl CommitGraph {
+    /// 获取仓库的 commit-graph（加载结果会被缓存）
+    pub fn for_repo(repo_path: &str) -> Option<Rc<CommitGraph>> {
+        if let Some(cached) = GRAPHS.with(|g| g.borrow().get(repo_path).cloned()) {
+            return cached;
+        }
+        let algo = HashAlgorithm::for_repo(repo_path);
+        let graph = std::fs::read(graph_path(repo_path))
+            .ok()
+            .and_then(|data| CommitGraph::parse(data, algo))
+            .map(Rc::new);
+        GRAPHS.with(|g| g.borrow_mut().insert(repo_path.to_string(), graph.clone()));
+        graph
+    }
+
+    /// 丢弃缓存（重新写入 commit-graph 后调用）
+    pub fn invalidate(repo_path: &str) {
+        GRAPHS.with(|g| g.borrow_mut().remove(repo_path));
+    }
+
+    /// 解析 commit-graph 文件内容，格式不符时返回 None
+    pub fn parse(data: Vec<u8>, algo: HashAlgorithm) -> Option<CommitGraph> {
+        let hash_len = algo.raw_len();
+        if data.len() < 8 + hash_len || &data[0..4] != SIGNATURE || data[4] != 1 || data[5] != hash_version(algo) {
+            return None;
+        }
+        let chunk_count = data[6] as usize;
+        // 不支持增量 commit-graph 链
+        if data[7] != 0 {
+            return None;
+        }
+
+        // chunk 表：(id, offset) × (chunk_count + 1)，最后一项 id 为 0，标记结束位置
+        // 每个 chunk 的结束位置即下一项的偏移
+        let mut chunks = HashMap::new();
+        let table_end = 8 + (chunk_count + 1) * 12;
+        if data.len() < table_end + hash_len {
+            return None;
+        }
+        for i in 0..chunk_count {
+            let pos = 8 + i * 12;
+            let id = read_u32(&data, pos);
+            let offset = read_u64(&data, pos + 4) as usize;
+            let end = read_u64(&data, pos + 16) as usize;
+            if offset > end || end > data.len() - hash_len {
+                return None;
+            }
+            chunks.insert(id, (offset, end));
+        }
+
+        let fanout = chunks.get(&CHUNK_OID_FANOUT)?.0;
+        let oid_lookup = chunks.get(&CHUNK_OID_LOOKUP)?.0;
+        let commit_data = chunks.get(&CHUNK_COMMIT_DATA)?.0;
+        if fanout + 256 * 4 > data.len() {
+            return None;
+        }
+        let count = read_u32(&data, fanout + 255 * 4) as usize;
+        if oid_lookup + count * hash_len > data.len() || commit_data + count * (hash_len + 16) > data.len() {
+            return None;
+        }
+
+        // Bloom 过滤器：BDAT 以 (哈希版本, 哈希函数个数, 每路径位数) 开头
+        let bloom = match (chunks.get(&CHUNK_BLOOM_INDEX), chunks.get(&CHUNK_BLOOM_DATA)) {
+            (Some(&(index, index_end)), Some(&(start, end)))
+                if index_end - index >= count * 4 && end - start >= 12 =>
+            {
+                let version = read_u32(&data, start);
+                let num_hashes = read_u32(&data, start + 4);
+                matches!(version, 1 | 2).then_some((index, start + 12, end, version, num_hashes))
+            }
+            _ => None,
+        };
+
+        Some(CommitGraph {
+            algo,
+            extra_edges: chunks.get(&CHUNK_EXTRA_EDGES).map(|c| c.0),
+            bloom,
+            data,
+            fanout,
+            oid_lookup,
+            commit_data,
+            count,
+        })
+    }
+
+    /// 图中的提交数量
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 第 `pos` 个提交的哈希（按哈希排序）
+    pub fn sha_at(&self, pos: usize) -> String {
+        let hash_len = self.algo.raw_len();
+        let start = self.oid_lookup + pos * hash_len;
+        hex::encode(&self.data[start..start + hash_len])
+    }
+
+    /// 提交在图中的位置（通过 fanout 缩小范围后二分查找）
+    pub fn position(&self, sha: &str) -> Option<usize> {
+        let sha = hex::decode(sha).ok()?;
+        let hash_len = self.algo.raw_len();
+        if sha.len() != hash_len {
+            return None;
+        }
+        let first = sha[0] as usize;
+        let lo = if first == 0 { 0 } else { read_u32(&self.data, self.fanout + (first - 1) * 4) as usize };
+        let hi = read_u32(&self.data, self.fanout + first * 4) as usize;
+        let oids = &self.data[self.oid_lookup..self.oid_lookup + self.count * hash_len];
+        let slice: Vec<&[u8]> = (lo..hi.min(self.count)).map(|i| &oids[i * hash_len..(i + 1) * hash_len]).collect();
+        slice.binary_search(&sha.as_slice()).ok().map(|i| lo + i)
+    }
+
+    /// 查找提交
+    pub fn lookup(&self, sha: &str) -> Option<GraphCommit> {
+        self.commit_at(self.position(sha)?)
+    }
+
+    /// 读取第 `pos` 个提交的数据
+    pub fn commit_at(&self, pos: usize) -> Option<GraphCommit> {
+        let hash_len = self.algo.raw_len();
+        let entry = self.commit_data + pos * (hash_len + 16);
+        let tree = hex::encode(&self.data[entry..entry + hash_len]);
+        let parent1 = read_u32(&self.data, entry + hash_len);
+        let parent2 = read_u32(&self.data, entry + hash_len + 4);
+        let high = read_u32(&self.data, entry + hash_len + 8);
+        let low = read_u32(&self.data, entry + hash_len + 12);
+
+        let mut parents = Vec::new();
+        for parent in [parent1, parent2] {
+            if parent == PARENT_NONE {
+                break;
+            }
+            if parent & PARENT_EXTRA_EDGES != 0 {
+                // 章鱼合并：从 EDGE 列表读取第 2 个及之后的父提交
+                let mut edge = self.extra_edges? + (parent & !PARENT_EXTRA_EDGES) as usize * 4;
+                loop {
+                    let value = u32::from_be_bytes(self.data.get(edge..edge + 4)?.try_into().ok()?);
+                    parents.push(self.sha_at(self.check_pos(value & !PARENT_LAST)?));
+                    if value & PARENT_LAST != 0 {
+                        break;
+                    }
+                    edge += 4;
+                }
+                break;
+            }
+            parents.push(self.sha_at(self.check_pos(parent)?));
+        }
+
+        Some(GraphCommit {
+            tree,
+            parents,
+            commit_time: (((high & 0x3) as u64) << 32) | low as u64,
+            generation: high >> 2,
+        })
+    }
+
+    /// 是否包含 changed-path Bloom 过滤器
+    pub fn has_bloom_filters(&self) -> bool {
+        self.bloom.is_some()
+    }
+
+    /// 第 `pos` 个提交的 Bloom 过滤器
+    pub fn bloom_filter(&self, pos: usize) -> Option<BloomFilter<'_>> {
+        let (index, start, end, version, num_hashes) = self.bloom?;
+        let from = if pos == 0 { 0 } else { read_u32(&self.data, index + (pos - 1) * 4) as usize };
+        let to = read_u32(&self.data, index + pos * 4) as usize;
+        if from > to || start + to > end {
+            return None;
+        }
+        Some(BloomFilter::new(&self.data[start + from..start + to], version, num_hashes))
+    }
+
+    fn check_pos(&self, pos: u32) -> Option<usize> {
+        ((pos as usize) < self.count).then_some(pos as usize)
+    }
+}
+
+/// 为给定提交写出 commit-graph 文件，返回写入的提交数量
+///
+/// `commits` 必须包含所有父提交（即对祖先闭合），否则返回错误。
+/// `changed_paths` 为 true 时同时写入每个提交的 changed-path Bloom 过滤器。
+pub fn write_commit_graph(
+    repo_path: &str,
+    db: &dyn ObjectDatabase,
+    commits: &[String],
+    changed_paths: bool,
+) -> io::Result<usize> {
+    let algo = db.hash_algorithm();
+    let hash_len = algo.raw_len();
+
+    // 1️⃣ 读取所有提交并按哈希排序
+    let mut shas: Vec<String> = commits.to_vec();
+    shas.sort();
+    shas.dedup();
+    let positions: HashMap<&str, u32> = shas.iter().enumerate().map(|(i, s)| (s.as_str(), i as u32)).collect();
+    let mut parsed = Vec::with_capacity(shas.len());
+    for sha in &shas {
+        let commit = Commit::load(db, sha)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("cannot read commit {}", sha)))?;
+        for parent in &commit.parents {
+            if !positions.contains_key(parent.as_str()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("parent {} of {} is not in the commit list", parent, sha),
+                ));
+            }
+        }
+        parsed.push(commit);
+    }
+
+    // 2️⃣ 计算拓扑层级（父提交先于子提交，使用显式栈避免深历史递归溢出）
+    let mut generations = vec![0u32; shas.len()];
+    for start in 0..shas.len() {
+        let mut stack = vec![(start, false)];
+        while let Some((i, expanded)) = stack.pop() {
+            if generations[i] != 0 {
+                continue;
+            }
+            let parents = parsed[i].parents.iter().map(|p| positions[p.as_str()] as usize);
+            if expanded {
+                let max = parents.map(|p| generations[p]).max().unwrap_or(0);
+                generations[i] = (max + 1).min(GENERATION_MAX);
+            } else {
+                stack.push((i, true));
+                stack.extend(parents.filter(|&p| generations[p] == 0).map(|p| (p, false)));
+            }
+        }
+    }
+
+    // 3️⃣ 构造各个 chunk
+    let mut fanout = vec![0u8; 256 * 4];
+    let mut counts = [0u32; 256];
+    for sha in &shas {
+        counts[u8::from_str_radix(&sha[0..2], 16).unwrap() as usize] += 1;
+    }
+    let mut total = 0;
+    for (i, count) in counts.iter().enumerate() {
+        total += count;
+        fanout[i * 4..i * 4 + 4].copy_from_slice(&total.to_be_bytes());
+    }
+
+    let mut lookup = Vec::with_capacity(shas.len() * hash_len);
+    for sha in &shas {
+        lookup.extend(hex::decode(sha).unwrap());
+    }
+
+    let mut commit_data = Vec::with_capacity(shas.len() * (hash_len + 16));
+    let mut edges: Vec<u32> = Vec::new();
+    for (i, commit) in parsed.iter().enumerate() {
+        commit_data.extend(hex::decode(&commit.tree).unwrap());
+        let parents: Vec<u32> = commit.parents.iter().map(|p| positions[p.as_str()]).collect();
+        let parent1 = parents.first().copied().unwrap_or(PARENT_NONE);
+        let parent2 = match parents.len() {
+            0 | 1 => PARENT_NONE,
+            2 => parents[1],
+            _ => {
+                let start = edges.len() as u32;
+                edges.extend(&parents[1..]);
+                *edges.last_mut().unwrap() |= PARENT_LAST;
+                PARENT_EXTRA_EDGES | start
+            }
+        };
+        let time = (commit.committer.time().max(0) as u64).min(COMMIT_TIME_MAX);
+        commit_data.extend(parent1.to_be_bytes());
+        commit_data.extend(parent2.to_be_bytes());
+        commit_data.extend(((generations[i] << 2) | (time >> 32) as u32).to_be_bytes());
+        commit_data.extend((time as u32).to_be_bytes());
+    }
+
+    let mut chunks: Vec<(u32, Vec<u8>)> = vec![
+        (CHUNK_OID_FANOUT, fanout),
+        (CHUNK_OID_LOOKUP, lookup),
+        (CHUNK_COMMIT_DATA, commit_data),
+    ];
+    if !edges.is_empty() {
+        chunks.push((CHUNK_EXTRA_EDGES, edges.iter().flat_map(|e| e.to_be_bytes()).collect()));
+    }
+    if changed_paths {
+        let mut index = Vec::with_capacity(parsed.len() * 4);
+        let mut filters = Vec::new();
+        filters.extend(BLOOM_VERSION.to_be_bytes());
+        filters.extend(bloom::NUM_HASHES.to_be_bytes());
+        filters.extend(bloom::BITS_PER_ENTRY.to_be_bytes());
+        for commit in &parsed {
+            let parent_tree = commit.parents.first().map(|p| parsed[positions[p.as_str()] as usize].tree.as_str());
+            filters.extend(bloom::compute_filter(db, &commit.tree, parent_tree, BLOOM_VERSION));
+            index.extend(((filters.len() - 12) as u32).to_be_bytes());
+        }
+        chunks.push((CHUNK_BLOOM_INDEX, index));
+        chunks.push((CHUNK_BLOOM_DATA, filters));
+    }
+
+    // 4️⃣ header + chunk 表 + chunk 内容 + 校验和
+    let mut out = Vec::new();
+    out.extend(SIGNATURE);
+    out.extend([1, hash_version(algo), chunks.len() as u8, 0]);
+    let mut offset = (8 + (chunks.len() + 1) * 12) as u64;
+    for (id, chunk) in &chunks {
+        out.extend(id.to_be_bytes());
+        out.extend(offset.to_be_bytes());
+        offset += chunk.len() as u64;
+    }
+    out.extend(0u32.to_be_bytes());
+    out.extend(offset.to_be_bytes());
+    for (_, chunk) in &chunks {
+        out.extend(chunk);
+    }
+    let checksum = algo.digest(&out);
+    out.extend(checksum);
+
+    let path = graph_path(repo_path);
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    write_file_atomic(&path, &out, Config::load(repo_path).fsync("commit-graph"))?;
+    CommitGraph::invalidate(repo_path);
+    Ok(shas.len())
+}
+
+/// header 中的哈希版本：1 = SHA-1，2 = SHA-256
+fn hash_version(algo: HashAlgorithm) -> u8 {
+    match algo {
+        HashAlgorithm::Sha1 => 1,
+        HashAlgorithm::Sha256 => 2,
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], pos: usize) -> u64 {
+    u64::from_be_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::commit::Signature;
+    use crate::core::odb::LooseObjectDatabase;
+    use crate::core::tree::TreeProcessor;
+    use crate::utils::fs::TempRepo;
+    use std::path::Path;
+
+    const ROOT: &str = "448b62860bd2004019eb792b72badc62874cfc32";
+    const OCTOPUS: &str = "001fe3d8bcc6ef97774e30b243a0fb3d1489464c";
+    const BRANCHES: [&str; 3] = [
+        "937cf6963dc9d056b4a9dfa491a41358c84320dc",
+        "e85ccae854644aa4c8f9158f50fdea76d5ae36ff",
+        "d17a028ce9dd9c241d50b968304545d64ee05851",
+    ];
+
+    /// `git -c commitGraph.generationVersion=1 commit-graph write --reachable` 的输出：
+    /// 根提交、三个分支提交和一个三父提交的章鱼合并
+    fn fixture() -> Vec<u8> {
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/commit-graph/octopus")).unwrap()
+    }
+
+    #[test]
+    fn reads_git_commit_graph_with_extra_edges() {
+        let graph = CommitGraph::parse(fixture(), HashAlgorithm::Sha1).expect("commit-graph");
+        assert_eq!(graph.len(), 5);
+        assert!(!graph.has_bloom_filters());
+
+        let root = graph.lookup(ROOT).unwrap();
+        assert!(root.parents.is_empty());
+        assert_eq!(root.generation, 1);
+        assert_eq!(root.commit_time, 1_700_000_000);
+        assert_eq!(root.tree, "4b825dc642cb6eb9a060e54bf8d69288fbee4904");
+
+        assert_eq!(graph.lookup(BRANCHES[1]).unwrap().parents, vec![ROOT]);
+        let octopus = graph.lookup(OCTOPUS).unwrap();
+        assert_eq!(octopus.parents, BRANCHES);
+        assert_eq!(octopus.generation, 3);
+
+        assert!(graph.lookup("0000000000000000000000000000000000000000").is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_commit_graph() {
+        let mut data = fixture();
+        data.truncate(data.len() - 30);
+        assert!(CommitGraph::parse(data, HashAlgorithm::Sha1).is_none());
+    }
+
+    #[test]
+    fn writes_same_bytes_as_git() {
+        let repo = TempRepo::new("commit-graph");
+        let db = LooseObjectDatabase::new(repo.path());
+        let tree = TreeProcessor::create_empty_tree(&db).unwrap();
+        let sig = Signature::parse(b"T <t@e> 1700000000 +0800").unwrap();
+        let commit = |parents: Vec<String>, message: &str| {
+            Commit::new(tree.clone(), parents, sig.clone(), sig.clone(), format!("{}\n", message).into_bytes())
+                .save(&db)
+                .unwrap()
+        };
+
+        let root = commit(Vec::new(), "root");
+        let branches: Vec<String> = ["b1", "b2", "b3"].iter().map(|m| commit(vec![root.clone()], m)).collect();
+        let octopus = commit(branches.clone(), "octo");
+        assert_eq!(octopus, OCTOPUS);
+
+        let mut all = branches;
+        all.extend([root, octopus]);
+        assert_eq!(write_commit_graph(repo.path(), &db, &all, false).unwrap(), 5);
+        assert_eq!(std::fs::read(graph_path(repo.path())).unwrap(), fixture());
+
+        // 父提交不在列表中时拒绝写入
+        assert!(write_commit_graph(repo.path(), &db, &[OCTOPUS.to_string()], false).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::utils::fs::read_file;
+use std::path::Path;
+
+/// 仓库配置（`.git/config`，只读）
+///
+/// 支持 Git 配置文件的常见写法：
+/// ```text
+/// [core]
+///     repositoryformatversion = 1
+/// [extensions]
+///     objectFormat = sha256
+/// [remote "origin"]
+///     url = ...
+/// ```
+/// 键名统一为 `section.key` 或 `section.subsection.key`，section 与 key 不区分大小写。
+#[derive(Debug, Default)]
+pub struct Config {
+    entries: Vec<(String, String)>,
+}
+
+impl Config {
+    /// 读取仓库配置，文件不存在时返回空配置
+    pub fn load(repo_path: &str) -> Self {
+        let path = Path::new(repo_path).join(".git").join("config");
+        let content = read_file(path.to_str().unwrap()).unwrap_or_default();
+        Self::parse(&content)
+    }
+
+    /// 解析配置文本
+    pub fn parse(content: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut section = String::new();
+
+        for raw in content.lines() {
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // [section] 或 [section "subsection"]
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match header.split_once(' ') {
+                    Some((name, sub)) => format!(
+                        "{}.{}",
+                        name.trim().to_ascii_lowercase(),
+                        sub.trim().trim_matches('"')
+                    ),
+                    None => header.trim().to_ascii_lowercase(),
+                };
+                continue;
+            }
+
+            // key = value；只有 key 时表示布尔真
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => (k.trim(), v.trim().trim_matches('"')),
+                None => (line, "true"),
+            };
+            entries.push((format!("{}.{}", section, key.to_ascii_lowercase()), value.to_string()));
+        }
+
+        Config { entries }
+    }
+
+    /// 读取配置项（同名多次出现时以最后一次为准）
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 读取布尔配置项
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)?.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" | "" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// 读取大小配置项，支持 `k`/`m`/`g` 后缀（按 1024 进位）
+    pub fn get_size(&self, key: &str) -> Option<u64> {
+        let value = self.get(key)?.trim().to_ascii_lowercase();
+        let (digits, unit) = match value.chars().last()? {
+            'k' => (&value[..value.len() - 1], 1 << 10),
+            'm' => (&value[..value.len() - 1], 1 << 20),
+            'g' => (&value[..value.len() - 1], 1 << 30),
+            _ => (value.as_str(), 1),
+        };
+        digits.parse::<u64>().ok()?.checked_mul(unit)
+    }
+
+    /// 超过该大小的对象不做 delta，直接流式写入（`core.bigFileThreshold`，默认 512 MiB）
+    pub fn big_file_threshold(&self) -> u64 {
+        self.get_size("core.bigFileThreshold").unwrap_or(512 << 20)
+    }
+
+    /// 写入某类文件时是否需要 fsync（`core.fsync`，默认不 fsync）
+    ///
+    /// `component` 取 `loose-object`、`pack`、`pack-metadata`、`reference`、`index`、`commit-graph`
+    /// 之一；配置值为
+    /// 逗号分隔的组件列表，也可以是聚合名 `objects`、`derived-metadata`、`committed`、`added`、
+    /// `all` 或 `none`。
+    /// 兼容旧配置 `core.fsyncObjectFiles = true`（只对松散对象生效）。
+    pub fn fsync(&self, component: &str) -> bool {
+        if component == "loose-object" && self.get_bool("core.fsyncObjectFiles") == Some(true) {
+            return true;
+        }
+        let Some(value) = self.get("core.fsync") else { return false };
+        value.split(',').map(str::trim).any(|c| match c {
+            "all" => true,
+            "objects" => matches!(component, "loose-object" | "pack"),
+            "derived-metadata" => matches!(component, "pack-metadata" | "commit-graph"),
+            "committed" => matches!(component, "loose-object" | "pack" | "reference"),
+            "added" => matches!(component, "loose-object" | "pack" | "reference" | "index"),
+            other => other == component,
+        })
+    }
+}
+
+/// `section.subsection.key` 中 section 与 key 不区分大小写，subsection 区分
+fn normalize_key(key: &str) -> String {
+    let first = key.find('.').unwrap_or(0);
+    let last = key.rfind('.').unwrap_or(key.len());
+    if first == last {
+        return key.to_ascii_lowercase();
+    }
+    format!(
+        "{}{}{}",
+        key[..first].to_ascii_lowercase(),
+        &key[first..last],
+        key[last..].to_ascii_lowercase()
+    )
+}
+
+/// 去掉 `#` 或 `;` 开头的注释（引号内的除外）
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' | ';' if !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
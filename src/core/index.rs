@@ -1,78 +1,267 @@
-use crate::utils::fs::{read_file_bytes, write_file_bytes};
-// use crate::utils::hash::sha1;
+use crate::core::blob::BlobProcessor;
+use crate::core::cache_tree::CacheTree;
+use crate::core::config::Config;
+use crate::core::odb::ObjectDatabase;
+use crate::core::tree::TreeProcessor;
+use crate::utils::error::GitError;
+use crate::utils::fs::{path_from_bytes, path_to_bytes, read_file_bytes, LockFile};
+use crate::utils::hash::HashAlgorithm;
 use std::collections::HashMap;
+use std::fs::{metadata, symlink_metadata, Metadata};
 use std::path::{Path, PathBuf};
-use std::fs::metadata;
+
+const SIGNATURE: &[u8; 4] = b"DIRC";
+const EXT_CACHE_TREE: &[u8; 4] = b"TREE";
+
+/// 条目 flags：assume-valid（不检查工作区文件）
+pub const FLAG_ASSUME_VALID: u16 = 0x8000;
+/// 条目 flags：后面带有 v3 扩展标志
+const FLAG_EXTENDED: u16 = 0x4000;
+/// 条目 flags 第 12–13 位：合并阶段（stage）
+const STAGE_MASK: u16 = 0x3000;
+const STAGE_SHIFT: u16 = 12;
+/// 条目 flags 低 12 位：路径长度（超过 0xfff 时存 0xfff，实际长度以 NUL 结尾为准）
+const NAME_MASK: u16 = 0x0fff;
+
+/// v3 扩展标志：skip-worktree（稀疏检出）
+pub const EXT_FLAG_SKIP_WORKTREE: u16 = 0x4000;
+/// v3 扩展标志：intent-to-add（`git add -N`）
+pub const EXT_FLAG_INTENT_TO_ADD: u16 = 0x2000;
 
 /// Index 条目
+///
+/// 除路径、哈希和模式外，还记录暂存时文件的 stat 信息，
+/// 用于不读取文件内容就判断工作区文件是否被修改。
 #[derive(Clone, Debug)]
 pub struct IndexEntry {
     pub path: PathBuf,
     pub sha: String,
     pub mode: u32,
+    /// 修改时间（秒 + 纳秒）
     pub mtime: u64,
+    pub mtime_nsec: u32,
+    /// inode 变更时间（秒 + 纳秒）
     pub ctime: u64,
+    pub ctime_nsec: u32,
+    pub dev: u32,
+    pub ino: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// 文件大小（磁盘上只保存低 32 位）
     pub size: u64,
+    /// 条目标志（目前只保留 `FLAG_ASSUME_VALID`，路径长度写入时计算）
+    pub flags: u16,
+    /// v3 扩展标志（`EXT_FLAG_*`），非 0 时 index 以版本 3 写出
+    pub extended_flags: u16,
+    /// 合并阶段：0 = 正常条目，1 = 共同祖先（base），2 = ours，3 = theirs
+    pub stage: u8,
+}
+
+impl IndexEntry {
+    /// 根据文件的 stat 信息构造条目（`meta` 应来自 `symlink_metadata`，符号链接记为 120000）
+    pub fn from_metadata(path: PathBuf, sha: &str, meta: &Metadata) -> Self {
+        #[cfg(unix)]
+        let entry = {
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            IndexEntry {
+                path,
+                sha: sha.to_string(),
+                mode: if meta.file_type().is_symlink() {
+                    0o120000
+                } else if meta.permissions().mode() & 0o111 != 0 {
+                    0o100755
+                } else {
+                    0o100644
+                },
+                mtime: meta.mtime() as u64,
+                mtime_nsec: meta.mtime_nsec() as u32,
+                ctime: meta.ctime() as u64,
+                ctime_nsec: meta.ctime_nsec() as u32,
+                dev: meta.dev() as u32,
+                ino: meta.ino() as u32,
+                uid: meta.uid(),
+                gid: meta.gid(),
+                size: meta.len(),
+                flags: 0,
+                extended_flags: 0,
+                stage: 0,
+            }
+        };
+        #[cfg(not(unix))]
+        let entry = {
+            let modified = meta.modified().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok());
+            let (secs, nsec) = modified.map_or((0, 0), |d| (d.as_secs(), d.subsec_nanos()));
+            IndexEntry {
+                path,
+                sha: sha.to_string(),
+                mode: if meta.file_type().is_symlink() { 0o120000 } else { 0o100644 },
+                mtime: secs,
+                mtime_nsec: nsec,
+                ctime: secs,
+                ctime_nsec: nsec,
+                dev: 0,
+                ino: 0,
+                uid: 0,
+                gid: 0,
+                size: meta.len(),
+                flags: 0,
+                extended_flags: 0,
+                stage: 0,
+            }
+        };
+        entry
+    }
+
+    /// 不带 stat 信息的条目（冲突条目、直接取自对象库的版本）
+    fn without_stat(path: &Path, sha: &str, mode: u32, stage: u8) -> Self {
+        IndexEntry {
+            path: path.to_path_buf(),
+            sha: sha.to_string(),
+            mode,
+            mtime: 0,
+            mtime_nsec: 0,
+            ctime: 0,
+            ctime_nsec: 0,
+            dev: 0,
+            ino: 0,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            flags: 0,
+            extended_flags: 0,
+            stage,
+        }
+    }
+
+    /// 工作区文件的 stat 信息（`current`，按当前文件构造的条目）是否与暂存时记录的一致
+    ///
+    /// 大小只比较磁盘上保存的低 32 位。一致并不保证内容未变（见 `Index::is_racily_clean`）。
+    pub fn stat_matches(&self, current: &IndexEntry) -> bool {
+        self.mode == current.mode
+            && self.size as u32 == current.size as u32
+            && (self.mtime, self.mtime_nsec) == (current.mtime, current.mtime_nsec)
+            && (self.ctime, self.ctime_nsec) == (current.ctime, current.ctime_nsec)
+            && (self.dev, self.ino, self.uid, self.gid) == (current.dev, current.ino, current.uid, current.gid)
+    }
 }
 
-/// Git Index 暂存区
+/// Git Index 暂存区（`.git/index`）
+///
+/// 文件格式与 Git 相同（版本 2 / 3 / 4）：`DIRC` header、按路径排序的条目
+/// （版本 2 / 3 每个条目按 8 字节对齐，版本 4 对路径做前缀压缩）、可选扩展，最后是整个文件的校验和。
+/// 目前支持 TREE 扩展（`cache_tree`），其他可选扩展读取时忽略。
+///
+/// 修改暂存区要用 `lock` 加载：从读取到 `save` 整个过程持有 `.git/index.lock`，
+/// 多个进程同时修改时后来者直接报错，而不是互相覆盖。
 #[derive(Debug)]
 pub struct Index {
     pub repo_path: PathBuf,
+    /// stage 0 的条目
     pub entries: HashMap<PathBuf, IndexEntry>,
+    /// 未合并的路径 -> 冲突条目（stage 1–3，按 stage 排列，缺少的 stage 表示该侧没有这个文件）
+    ///
+    /// 同一路径不会同时出现在 `entries` 中。
+    pub unmerged: HashMap<PathBuf, Vec<IndexEntry>>,
+    /// 各目录对应的 tree 缓存；直接修改 `entries` 时需自行调用 `invalidate_path`
+    pub cache_tree: CacheTree,
+    /// 持有的 `index.lock`（只读加载时为 None）
+    lock: Option<LockFile>,
+    /// 加载时 index 文件的修改时间（秒, 纳秒），用于判断 racily clean
+    timestamp: Option<(u64, u32)>,
+    /// 加载时的 index 版本；版本 4（`git update-index --index-version 4`）保存时保持不变
+    version: u32,
+    /// `core.fileMode`：为 false 时不相信文件系统的可执行位
+    trust_executable_bit: bool,
 }
 
 impl Index {
-    /// 加载仓库的 index 文件（二进制）
+    /// 只读加载仓库的 index 文件，文件损坏时终止（需要自行处理错误时使用 `try_load`）
     pub fn load(repo_path: &Path) -> Self {
+        Self::try_load(repo_path).unwrap_or_else(|e| panic!("❌ {}", e))
+    }
+
+    /// 获取 `index.lock` 后加载 index，准备修改
+    ///
+    /// 锁在 `save` 时随新内容一起提交；未保存就丢弃 `Index`（出错、panic）时自动释放，
+    /// index 保持原样。
+    pub fn lock(repo_path: &Path) -> Result<Self, GitError> {
+        let lock = acquire_lock(repo_path)?;
+        let mut index = Self::try_load(repo_path)?;
+        index.lock = Some(lock);
+        Ok(index)
+    }
+
+    /// 加载仓库的 index 文件，校验 header 与校验和
+    pub fn try_load(repo_path: &Path) -> Result<Self, GitError> {
         let index_file = repo_path.join(".git").join("index");
+        let algo = HashAlgorithm::for_repo(repo_path.to_str().unwrap());
         let mut entries = HashMap::new();
+        let mut unmerged: HashMap<PathBuf, Vec<IndexEntry>> = HashMap::new();
+        let mut cache_tree = CacheTree::default();
+        let mut version = 2;
+        let timestamp = metadata(&index_file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| (d.as_secs(), d.subsec_nanos()));
 
-        if index_file.exists() {
-            let content = read_file_bytes(index_file.to_str().unwrap()).unwrap_or_default();
-            let mut i = 0;
-            while i + 20 + 4 + 8*3 <= content.len() {
-                let sha = hex::encode(&content[i..i+20]); i+=20;
-                let mode = u32::from_be_bytes(content[i..i+4].try_into().unwrap()); i+=4;
-                let mtime = u64::from_be_bytes(content[i..i+8].try_into().unwrap()); i+=8;
-                let ctime = u64::from_be_bytes(content[i..i+8].try_into().unwrap()); i+=8;
-                let size = u64::from_be_bytes(content[i..i+8].try_into().unwrap()); i+=8;
-                let path_len = content[i] as usize; i+=1;
-                let path = PathBuf::from(String::from_utf8(content[i..i+path_len].to_vec()).unwrap());
-                i += path_len;
-
-                entries.insert(path.clone(), IndexEntry { path, sha, mode, mtime, ctime, size });
+        // 文件不存在或为空（从未暂存过）时视为空暂存区
+        let content = read_file_bytes(index_file.to_str().unwrap()).unwrap_or_default();
+        if !content.is_empty() {
+            let (parsed, cached) = parse_index(&content, algo)?;
+            version = read_u32(&content, 4);
+            for entry in parsed {
+                if entry.stage == 0 {
+                    entries.insert(entry.path.clone(), entry);
+                } else {
+                    unmerged.entry(entry.path.clone()).or_default().push(entry);
+                }
             }
+            cache_tree = cached.unwrap_or_default();
         }
 
-        Index { repo_path: repo_path.to_path_buf(), entries }
+        let trust_executable_bit = Config::load(repo_path.to_str().unwrap()).get_bool("core.fileMode").unwrap_or(true);
+        Ok(Index {
+            repo_path: repo_path.to_path_buf(),
+            entries,
+            unmerged,
+            cache_tree,
+            lock: None,
+            timestamp,
+            version,
+            trust_executable_bit,
+        })
     }
 
-    /// 保存 Index（二进制）
-    pub fn save(&self) {
-        let index_file = self.repo_path.join(".git").join("index");
-        let mut buf = Vec::new();
-        for entry in self.entries.values() {
-            buf.extend(hex::decode(&entry.sha).unwrap());
-            buf.extend(&entry.mode.to_be_bytes());
-            buf.extend(&entry.mtime.to_be_bytes());
-            buf.extend(&entry.ctime.to_be_bytes());
-            buf.extend(&entry.size.to_be_bytes());
-            let path_bytes = entry.path.to_str().unwrap().as_bytes();
-            buf.push(path_bytes.len() as u8);
-            buf.extend(path_bytes);
-        }
-        write_file_bytes(index_file.to_str().unwrap(), &buf).unwrap();
-    }
-
-    /// 将文件加入暂存区（存相对路径）
-    pub fn stage_file(&mut self, file_path: &Path, obj_sha: &String) {
-        let metadata = metadata(file_path).unwrap();
-        let sha = obj_sha.clone();
-        let mode = if metadata.permissions().readonly() { 0o100644 } else { 0o100755 };
-        let mtime = metadata.modified().unwrap().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
-        let ctime = mtime;
-        let size = metadata.len();
+    /// 保存 Index（Git 兼容的二进制格式）并释放锁
+    ///
+    /// 新内容先写入 `index.lock`，再 rename 覆盖 index，崩溃时不会留下截断的 index。
+    /// 没有通过 `lock` 加载时在这里获取锁。
+    pub fn save(&mut self) -> Result<(), GitError> {
+        let lock = match self.lock.take() {
+            Some(lock) => lock,
+            None => acquire_lock(&self.repo_path)?,
+        };
+        self.smudge_racily_clean_entries();
+        let algo = HashAlgorithm::for_repo(self.repo_path.to_str().unwrap());
+        let mut entries: Vec<&IndexEntry> = self.all_entries().collect();
+        entries.sort_by(|a, b| path_to_bytes(&a.path).cmp(path_to_bytes(&b.path)).then(a.stage.cmp(&b.stage)));
+        let buf = write_index(&entries, &self.cache_tree, algo, self.version == 4);
+
+        let fsync = Config::load(self.repo_path.to_str().unwrap()).fsync("index");
+        lock.commit(&buf, fsync).map_err(|e| GitError::IndexWrite(e.to_string()))
+    }
+
+    /// 所有条目（包括未合并的冲突条目）
+    pub fn all_entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values().chain(self.unmerged.values().flatten())
+    }
+
+    /// 将文件加入暂存区（存相对路径，`save` 后写入磁盘）
+    ///
+    /// 与 `git add` 相同，暂存冲突中的文件即标记该路径的冲突已解决。
+    pub fn stage_file(&mut self, file_path: &Path, obj_sha: &str) {
+        let metadata = symlink_metadata(file_path).unwrap();
 
         // ✅ 使用相对仓库根路径
         let tmp = file_path.strip_prefix(&self.repo_path)
@@ -80,30 +269,828 @@ impl Index {
         let relative_path = tmp.strip_prefix(Path::new("./")).
             unwrap_or(tmp).
             to_path_buf();
-            
-        
-        let entry = IndexEntry {
-            path: relative_path.clone(),
-            sha,
-            mode,
-            mtime,
-            ctime,
-            size,
-        };
 
+        let entry = self.entry_from_metadata(relative_path.clone(), obj_sha, &metadata, self.entries.get(&relative_path));
+        // 内容和模式都没变时（例如 `add .` 重新暂存未修改的文件）保留 tree 缓存
+        let unchanged = self
+            .entries
+            .get(&relative_path)
+            .is_some_and(|old| old.sha == entry.sha && old.mode == entry.mode);
+        if !unchanged {
+            self.invalidate_path(&relative_path);
+        }
+        self.unmerged.remove(&relative_path);
         self.entries.insert(relative_path, entry);
-        self.save();
     }
 
-    /// 从暂存区移除文件
+    /// 从暂存区移除文件（包括该路径的冲突条目）
     pub fn unstage_file(&mut self, file_path: &Path) {
-        self.entries.remove(file_path);
-        self.save();
+        let removed = self.entries.remove(file_path).is_some();
+        if self.unmerged.remove(file_path).is_some() || removed {
+            self.invalidate_path(file_path);
+        }
     }
 
     /// 清空暂存区
     pub fn clear(&mut self) {
         self.entries.clear();
-        self.save();
+        self.unmerged.clear();
+        self.cache_tree = CacheTree::default();
+    }
+
+    /// 记录路径的合并冲突：`stages` 依次为 base、ours、theirs 的 (哈希, 模式)，
+    /// None 表示该侧没有这个文件；原有的 stage 0 条目被移除
+    pub fn record_conflict(&mut self, path: &Path, stages: [Option<(&str, u32)>; 3]) {
+        let conflict: Vec<IndexEntry> = stages
+            .iter()
+            .zip(1u8..)
+            .filter_map(|(side, stage)| side.map(|(sha, mode)| IndexEntry::without_stat(path, sha, mode, stage)))
+            .collect();
+        if conflict.is_empty() {
+            return;
+        }
+        self.entries.remove(path);
+        self.invalidate_path(path);
+        self.unmerged.insert(path.to_path_buf(), conflict);
+    }
+
+    /// 是否存在未合并的路径
+    pub fn has_conflicts(&self) -> bool {
+        !self.unmerged.is_empty()
+    }
+
+    /// 未合并的路径（按路径排序）
+    pub fn unmerged_paths(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.unmerged.keys().map(PathBuf::as_path).collect();
+        paths.sort_by(|a, b| path_to_bytes(a).cmp(path_to_bytes(b)));
+        paths
+    }
+
+    /// 用某一侧的版本（stage 1–3）解决冲突，恢复为 stage 0 条目
+    ///
+    /// 该侧没有这个文件时（例如一侧删除了文件）解决为删除。路径没有冲突或 stage 无效时返回 false。
+    /// 只更新暂存区，工作区文件由调用者写出。
+    pub fn resolve_conflict(&mut self, path: &Path, stage: u8) -> bool {
+        if !(1..=3).contains(&stage) {
+            return false;
+        }
+        let Some(conflict) = self.unmerged.remove(path) else { return false };
+        if let Some(side) = conflict.into_iter().find(|e| e.stage == stage) {
+            let entry = IndexEntry::without_stat(path, &side.sha, side.mode, 0);
+            self.entries.insert(path.to_path_buf(), entry);
+        }
+        self.invalidate_path(path);
+        true
+    }
+
+    /// 按工作区文件的 stat 信息构造条目
+    ///
+    /// `core.fileMode = false` 时普通文件沿用 `old` 中记录的模式（新文件记为 100644），
+    /// 可执行位的变化不算修改，与 Git 相同。
+    fn entry_from_metadata(&self, path: PathBuf, sha: &str, meta: &Metadata, old: Option<&IndexEntry>) -> IndexEntry {
+        let mut entry = IndexEntry::from_metadata(path, sha, meta);
+        if !self.trust_executable_bit && entry.mode != 0o120000 {
+            entry.mode = match old {
+                Some(old) if old.mode == 0o100755 => 0o100755,
+                _ => 0o100644,
+            };
+        }
+        entry
+    }
+
+    /// 工作区文件相对暂存区是否被修改（内容、模式改变或文件被删除）
+    ///
+    /// 先比较 stat 信息：一致且不是 racily clean 时直接认为未修改；大小或模式不同时
+    /// 一定已修改；只有时间、inode 等变化（例如 `touch`）或 racily clean 时才重新计算哈希。
+    /// 符号链接不跟随，比较的是链接本身和链接目标路径。
+    pub fn is_modified(&self, entry: &IndexEntry) -> bool {
+        // 子模块不在这里检查；assume-valid 与 skip-worktree 的条目按约定不检查工作区
+        if entry.mode == 0o160000
+            || entry.flags & FLAG_ASSUME_VALID != 0
+            || entry.extended_flags & EXT_FLAG_SKIP_WORKTREE != 0
+        {
+            return false;
+        }
+        let path = self.repo_path.join(&entry.path);
+        let Ok(meta) = symlink_metadata(&path) else { return true };
+        if !meta.is_file() && !meta.file_type().is_symlink() {
+            return true;
+        }
+
+        let current = self.entry_from_metadata(entry.path.clone(), &entry.sha, &meta, Some(entry));
+        if entry.stat_matches(&current) {
+            if !self.is_racily_clean(entry) {
+                return false;
+            }
+        } else {
+            // size 为 0 可能是被“抹掉”的 racily clean 条目，需要比较内容
+            if current.mode != entry.mode || (entry.size != 0 && current.size as u32 != entry.size as u32) {
+                return true;
+            }
+        }
+        self.content_differs(entry, &path)
+    }
+
+    /// 条目是否 racily clean：文件在写入 index 的同一时刻（或之后）被修改，
+    /// 此时 stat 信息一致也不能说明内容未变
+    pub fn is_racily_clean(&self, entry: &IndexEntry) -> bool {
+        self.timestamp.is_some_and(|ts| (entry.mtime, entry.mtime_nsec) >= ts)
+    }
+
+    /// 重新计算工作区文件的哈希并与条目比较（只计算，不写入对象库）；符号链接计算链接目标路径的哈希
+    fn content_differs(&self, entry: &IndexEntry, path: &Path) -> bool {
+        let algo = HashAlgorithm::for_repo(self.repo_path.to_str().unwrap());
+        let hashed = if entry.mode == 0o120000 {
+            std::fs::read_link(path).and_then(|target| {
+                let target = path_to_bytes(&target);
+                BlobProcessor::hash_reader(algo, &mut &target[..], target.len() as u64)
+            })
+        } else {
+            std::fs::File::open(path).and_then(|mut file| {
+                let size = file.metadata()?.len();
+                BlobProcessor::hash_reader(algo, &mut file, size)
+            })
+        };
+        hashed.map_or(true, |sha| sha != entry.sha)
+    }
+
+    /// 写入前处理 racily clean 的条目：stat 一致但内容已变的，把 size 置 0（与 Git 相同），
+    /// 否则新 index 的时间戳更晚，之后就再也发现不了这次修改
+    fn smudge_racily_clean_entries(&mut self) {
+        let smudged: Vec<PathBuf> = self
+            .entries
+            .values()
+            .filter(|e| self.is_racily_clean(e))
+            .filter(|e| {
+                let path = self.repo_path.join(&e.path);
+                let matches = symlink_metadata(&path)
+                    .is_ok_and(|meta| e.stat_matches(&self.entry_from_metadata(e.path.clone(), &e.sha, &meta, Some(e))));
+                matches && self.content_differs(e, &path)
+            })
+            .map(|e| e.path.clone())
+            .collect();
+        for path in smudged {
+            if let Some(entry) = self.entries.get_mut(&path) {
+                entry.size = 0;
+            }
+        }
+    }
+
+    /// 路径被改动：使 tree 缓存中该路径所在的各级目录失效
+    pub fn invalidate_path(&mut self, path: &Path) {
+        self.cache_tree.invalidate_path(path_to_bytes(path));
+    }
+
+    /// 根据暂存区生成 tree 对象，返回根 tree 的哈希
+    ///
+    /// 未改动的目录复用缓存的 tree，生成的结果写回 TREE 扩展（`save` 后供下次提交使用）。
+    /// 只包含 stage 0 的条目，存在冲突时调用者应先拒绝（见 `has_conflicts`）。
+    pub fn write_tree(&mut self, db: &dyn ObjectDatabase) -> Result<String, GitError> {
+        TreeProcessor::create_tree_from_index(db, &self.entries, &mut self.cache_tree)
+    }
+}
+
+/// 获取 `.git/index.lock`，已被其他进程持有时返回 `GitError::IndexLocked`
+fn acquire_lock(repo_path: &Path) -> Result<LockFile, GitError> {
+    let index_file = repo_path.join(".git").join("index");
+    LockFile::acquire(&index_file).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            let mut lock = index_file.into_os_string();
+            lock.push(".lock");
+            GitError::IndexLocked(PathBuf::from(lock).display().to_string())
+        }
+        _ => GitError::IndexWrite(e.to_string()),
+    })
+}
+
+fn corrupt(reason: impl Into<String>) -> GitError {
+    GitError::IndexCorrupt(reason.into())
+}
+
+/// 解析 index 文件内容
+fn parse_index(content: &[u8], algo: HashAlgorithm) -> Result<(Vec<IndexEntry>, Option<CacheTree>), GitError> {
+    let hash_len = algo.raw_len();
+
+    // 1️⃣ header + 校验和
+    if content.len() < 12 + hash_len {
+        return Err(corrupt("index file smaller than expected"));
+    }
+    if &content[0..4] != SIGNATURE {
+        return Err(corrupt("bad signature"));
+    }
+    let version = read_u32(content, 4);
+    if !(2..=4).contains(&version) {
+        return Err(corrupt(format!("unsupported index version {}", version)));
+    }
+    let body_end = content.len() - hash_len;
+    let checksum = &content[body_end..];
+    // index.skipHash 写出的 index 校验和全为 0，不做校验
+    if checksum.iter().any(|&b| b != 0) && algo.digest(&content[..body_end]) != checksum {
+        return Err(corrupt("bad index file sha1 signature"));
+    }
+    let count = read_u32(content, 8) as usize;
+
+    // 2️⃣ 条目
+    let fixed_len = 40 + hash_len + 2;
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 12;
+    // 版本 4 中每个路径相对前一个条目的路径压缩
+    let mut previous: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        if pos + fixed_len > body_end {
+            return Err(corrupt("truncated entry"));
+        }
+        let field = |i: usize| read_u32(content, pos + i * 4);
+        let sha = hex::encode(&content[pos + 40..pos + 40 + hash_len]);
+        let flags = u16::from_be_bytes([content[pos + 40 + hash_len], content[pos + 41 + hash_len]]);
+        let mut name_start = pos + fixed_len;
+        let extended_flags = if flags & FLAG_EXTENDED != 0 {
+            if version < 3 || name_start + 2 > body_end {
+                return Err(corrupt("unexpected extended flags"));
+            }
+            name_start += 2;
+            u16::from_be_bytes([content[name_start - 2], content[name_start - 1]])
+        } else {
+            0
+        };
+
+        // 版本 4：先是要从前一个路径末尾去掉的字节数，再是以 NUL 结尾的后缀
+        let (strip, suffix_start) = if version == 4 {
+            let (strip, used) = read_varint(&content[name_start..body_end]).ok_or_else(|| corrupt("truncated entry"))?;
+            if strip > previous.len() {
+                return Err(corrupt("malformed name field in the index"));
+            }
+            (strip, name_start + used)
+        } else {
+            (previous.len(), name_start)
+        };
+        let suffix_len = content[suffix_start..body_end]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| corrupt("unterminated path"))?;
+        previous.truncate(previous.len() - strip);
+        previous.extend_from_slice(&content[suffix_start..suffix_start + suffix_len]);
+
+        // 长度不超过 0xfff 时 flags 中的长度必须一致
+        if (flags & NAME_MASK) != NAME_MASK && (flags & NAME_MASK) as usize != previous.len() {
+            return Err(corrupt("path length does not match flags"));
+        }
+
+        entries.push(IndexEntry {
+            path: path_from_bytes(&previous),
+            sha,
+            mode: field(6),
+            ctime: field(0) as u64,
+            ctime_nsec: field(1),
+            mtime: field(2) as u64,
+            mtime_nsec: field(3),
+            dev: field(4),
+            ino: field(5),
+            uid: field(7),
+            gid: field(8),
+            size: field(9) as u64,
+            flags: flags & FLAG_ASSUME_VALID,
+            extended_flags,
+            stage: ((flags & STAGE_MASK) >> STAGE_SHIFT) as u8,
+        });
+        pos = if version == 4 {
+            suffix_start + suffix_len + 1
+        } else {
+            // 条目总长补齐到 8 的倍数（至少补 1 个 NUL）
+            pos + ((name_start - pos + suffix_len + 8) & !7)
+        };
+    }
+    if pos > body_end {
+        return Err(corrupt("truncated entry"));
+    }
+
+    // 3️⃣ 扩展：签名首字母大写的扩展是可选的，不认识时跳过
+    let mut cache_tree = None;
+    while pos < body_end {
+        if pos + 8 > body_end {
+            return Err(corrupt("truncated extension header"));
+        }
+        let signature = &content[pos..pos + 4];
+        let size = read_u32(content, pos + 4) as usize;
+        if pos + 8 + size > body_end {
+            return Err(corrupt("truncated extension"));
+        }
+        if signature == EXT_CACHE_TREE {
+            cache_tree = CacheTree::parse(&content[pos + 8..pos + 8 + size], algo);
+        } else if !signature[0].is_ascii_uppercase() {
+            return Err(corrupt(format!(
+                "index uses {} extension, which we do not understand",
+                String::from_utf8_lossy(signature)
+            )));
+        }
+        pos += 8 + size;
+    }
+
+    Ok((entries, cache_tree))
+}
+
+/// 生成 index 文件内容（`entries` 已按路径排序），`compress_paths` 为 true 时写出版本 4
+fn write_index(entries: &[&IndexEntry], cache_tree: &CacheTree, algo: HashAlgorithm, compress_paths: bool) -> Vec<u8> {
+    let version: u32 = match (compress_paths, entries.iter().any(|e| e.extended_flags != 0)) {
+        (true, _) => 4,
+        (false, true) => 3,
+        (false, false) => 2,
+    };
+    let mut buf = Vec::new();
+    buf.extend(SIGNATURE);
+    buf.extend(version.to_be_bytes());
+    buf.extend((entries.len() as u32).to_be_bytes());
+
+    let mut previous: &[u8] = &[];
+    for entry in entries {
+        let start = buf.len();
+        // 磁盘上的时间、大小等字段都只有 32 位，超出部分截断（与 Git 相同）
+        for field in [
+            entry.ctime as u32,
+            entry.ctime_nsec,
+            entry.mtime as u32,
+            entry.mtime_nsec,
+            entry.dev,
+            entry.ino,
+            entry.mode,
+            entry.uid,
+            entry.gid,
+            entry.size as u32,
+        ] {
+            buf.extend(field.to_be_bytes());
+        }
+        buf.extend(hex::decode(&entry.sha).unwrap());
+
+        let path = path_to_bytes(&entry.path);
+        let mut flags = (entry.flags & FLAG_ASSUME_VALID)
+            | (((entry.stage as u16) << STAGE_SHIFT) & STAGE_MASK)
+            | path.len().min(NAME_MASK as usize) as u16;
+        if entry.extended_flags != 0 {
+            flags |= FLAG_EXTENDED;
+        }
+        buf.extend(flags.to_be_bytes());
+        if entry.extended_flags != 0 {
+            buf.extend(entry.extended_flags.to_be_bytes());
+        }
+        if version == 4 {
+            let common = previous.iter().zip(path).take_while(|(a, b)| a == b).count();
+            write_varint(&mut buf, previous.len() - common);
+            buf.extend(&path[common..]);
+            buf.push(0);
+            previous = path;
+        } else {
+            buf.extend(path);
+            let len = (buf.len() - start + 8) & !7;
+            buf.resize(start + len, 0);
+        }
+    }
+
+    // 没有任何缓存内容时不写 TREE 扩展
+    if cache_tree.is_valid() || !cache_tree.children.is_empty() {
+        let mut ext = Vec::new();
+        cache_tree.write(&mut ext);
+        buf.extend(EXT_CACHE_TREE);
+        buf.extend((ext.len() as u32).to_be_bytes());
+        buf.extend(ext);
+    }
+
+    let checksum = algo.digest(&buf);
+    buf.extend(checksum);
+    buf
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+/// 读取版本 4 路径前缀压缩使用的变长整数（与 pack 中 OFS_DELTA 偏移的编码相同），
+/// 返回 (值, 占用的字节数)
+fn read_varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut byte = *data.first()?;
+    let mut value = (byte & 0x7f) as usize;
+    let mut used = 1;
+    while byte & 0x80 != 0 {
+        byte = *data.get(used)?;
+        used += 1;
+        value = value.checked_add(1)?.checked_mul(128)? | (byte & 0x7f) as usize;
+    }
+    Some((value, used))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        bytes.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/tests/fixtures/index/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    /// 解析后按文件中的顺序重新写出
+    fn rewrite(content: &[u8]) -> Vec<u8> {
+        let (entries, cache_tree) = parse_index(content, HashAlgorithm::Sha1).unwrap();
+        let entries: Vec<&IndexEntry> = entries.iter().collect();
+        write_index(&entries, &cache_tree.unwrap_or_default(), HashAlgorithm::Sha1, read_u32(content, 4) == 4)
+    }
+
+    #[test]
+    fn git_v2_index_round_trips() {
+        // git add + git write-tree 生成，带完整的 TREE 扩展
+        for name in ["v2-tree", "v2-partial"] {
+            let content = fixture(name);
+            assert_eq!(read_u32(&content, 4), 2);
+            assert_eq!(rewrite(&content), content, "{}", name);
+        }
+
+        let (entries, _) = parse_index(&fixture("v2-tree"), HashAlgorithm::Sha1).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["a.txt", "d/e/f", "d/g", "h"]);
+        assert_eq!(entries[0].sha, "78981922613b2afb6025042ff6bd878ac1994e85");
+        assert!(entries.iter().all(|e| e.mode == 0o100644 && e.extended_flags == 0));
+        assert_eq!(entries[0].size, 2);
+    }
+
+    #[test]
+    fn git_v3_index_round_trips() {
+        // git add -N new.txt && git update-index --skip-worktree a.txt
+        let content = fixture("v3");
+        assert_eq!(read_u32(&content, 4), 3);
+        let (entries, _) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        let flags: Vec<(&str, u16)> = entries
+            .iter()
+            .map(|e| (e.path.to_str().unwrap(), e.extended_flags))
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                ("a.txt", EXT_FLAG_SKIP_WORKTREE),
+                ("d/e/f", 0),
+                ("d/g", 0),
+                ("h", 0),
+                ("new.txt", EXT_FLAG_INTENT_TO_ADD),
+            ]
+        );
+        assert_eq!(rewrite(&content), content);
+    }
+
+    #[test]
+    fn git_v4_index_round_trips() {
+        // 与 v2-tree 相同的文件，之后 git update-index --index-version 4
+        let content = fixture("v4");
+        assert_eq!(read_u32(&content, 4), 4);
+        let (entries, cache_tree) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["a.txt", "d/e/f", "d/g", "h"]);
+        assert!(cache_tree.unwrap().is_valid());
+        assert_eq!(rewrite(&content), content);
+
+        // 去掉的字节数使用与 OFS_DELTA 偏移相同的变长编码
+        assert_eq!(read_varint(&[0x81, 0x00]), Some((256, 2)));
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 256);
+        assert_eq!(buf, [0x81, 0x00]);
+    }
+
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        // 文件名为 Latin-1 编码的 "café"
+        let content = fixture("v2-non-utf8");
+        let (entries, _) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(path_to_bytes(&entries[0].path), b"caf\xe9");
+        assert_eq!(path_to_bytes(&entries[1].path), b"plain");
+        assert_eq!(rewrite(&content), content);
+    }
+
+    #[test]
+    fn unsupported_version_is_reported() {
+        let mut content = fixture("v2-tree");
+        content[4..8].copy_from_slice(&5u32.to_be_bytes());
+        let err = parse_index(&content, HashAlgorithm::Sha1).unwrap_err();
+        assert_eq!(err.to_string(), GitError::IndexCorrupt("unsupported index version 5".into()).to_string());
+    }
+
+    #[test]
+    fn v2_is_written_when_no_extended_flags() {
+        let content = fixture("v3");
+        let (mut entries, cache_tree) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        for entry in &mut entries {
+            entry.extended_flags = 0;
+        }
+        let entries: Vec<&IndexEntry> = entries.iter().collect();
+        let written = write_index(&entries, &cache_tree.unwrap(), HashAlgorithm::Sha1, false);
+        assert_eq!(read_u32(&written, 4), 2);
+        // 没有扩展标志时条目恢复为 v2 的布局
+        let (reread, _) = parse_index(&written, HashAlgorithm::Sha1).unwrap();
+        assert!(reread.iter().all(|e| e.extended_flags == 0));
+        assert_eq!(reread.len(), 5);
+    }
+
+    #[test]
+    fn long_paths_store_0xfff_in_flags() {
+        let (mut entries, _) = parse_index(&fixture("v2-tree"), HashAlgorithm::Sha1).unwrap();
+        let long = "x".repeat(5000);
+        entries.truncate(1);
+        entries[0].path = PathBuf::from(&long);
+        let written = write_index(&[&entries[0]], &CacheTree::default(), HashAlgorithm::Sha1, false);
+        assert_eq!(u16::from_be_bytes([written[12 + 60], written[12 + 61]]) & NAME_MASK, NAME_MASK);
+        let (reread, cache_tree) = parse_index(&written, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(reread[0].path, PathBuf::from(long));
+        assert!(cache_tree.is_none());
+    }
+
+    #[test]
+    fn corrupt_index_is_rejected() {
+        let content = fixture("v2-tree");
+
+        let mut bad = content.clone();
+        bad[20] ^= 1;
+        assert!(matches!(parse_index(&bad, HashAlgorithm::Sha1), Err(GitError::IndexCorrupt(_))));
+
+        let mut bad = content.clone();
+        bad[7] = 5;
+        assert!(parse_index(&bad, HashAlgorithm::Sha1).is_err());
+
+        assert!(parse_index(&content[..30], HashAlgorithm::Sha1).is_err());
+
+        // 条目数比实际多
+        let mut bad = content.clone();
+        bad[11] = 40;
+        let body_end = bad.len() - 20;
+        let checksum = HashAlgorithm::Sha1.digest(&bad[..body_end]);
+        bad[body_end..].copy_from_slice(&checksum);
+        assert!(parse_index(&bad, HashAlgorithm::Sha1).is_err());
+
+        // 不认识的必需扩展（签名首字母小写）
+        let mut bad = content[..content.len() - 20].to_vec();
+        bad.extend(b"link");
+        bad.extend(0u32.to_be_bytes());
+        let checksum = HashAlgorithm::Sha1.digest(&bad);
+        bad.extend(checksum);
+        assert!(parse_index(&bad, HashAlgorithm::Sha1).is_err());
+    }
+
+    /// 合并冲突的 index：c.txt 有 stage 1–3，both 只有 stage 2–3（两侧都新增），k 无冲突
+    fn conflict_repo(name: &str) -> crate::utils::fs::TempRepo {
+        let repo = crate::utils::fs::TempRepo::new(name);
+        std::fs::write(Path::new(repo.path()).join(".git").join("index"), fixture("conflict")).unwrap();
+        repo
+    }
+
+    fn stages<'a>(index: &'a Index, path: &str) -> Vec<(u8, &'a str)> {
+        index.unmerged[Path::new(path)].iter().map(|e| (e.stage, e.sha.as_str())).collect()
+    }
+
+    #[test]
+    fn git_conflict_index_is_parsed() {
+        let content = fixture("conflict");
+        let (entries, _) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        let paths: Vec<(&str, u8)> = entries.iter().map(|e| (e.path.to_str().unwrap(), e.stage)).collect();
+        assert_eq!(paths, [("both", 2), ("both", 3), ("c.txt", 1), ("c.txt", 2), ("c.txt", 3), ("k", 0)]);
+
+        // git 在合并后留下了一个失效的空 TREE 扩展，我们不写；其余部分逐字节相同
+        let written = rewrite(&content);
+        let tree = content.windows(4).position(|w| w == b"TREE").unwrap();
+        assert_eq!(written[..written.len() - 20], content[..tree]);
+    }
+
+    #[test]
+    fn conflicts_are_loaded_and_saved() {
+        let repo = conflict_repo("index-conflict-load");
+        let mut index = Index::try_load(Path::new(repo.path())).unwrap();
+        assert!(index.has_conflicts());
+        assert_eq!(index.unmerged_paths(), [Path::new("both"), Path::new("c.txt")]);
+        assert_eq!(
+            stages(&index, "c.txt"),
+            [
+                (1, "df967b96a579e45a18b8251732d16804b2e56a55"),
+                (2, "b19a1e93bec1317dc6097229e12afaffbfa74dc2"),
+                (3, "950b81b7eee953d050aa05a641f8e056c85dd1bd"),
+            ]
+        );
+        assert_eq!(
+            stages(&index, "both"),
+            [(2, "13e7564ea0c889e81bcba6f8e496b2a74cdb32fa"), (3, "718f4d2ff533cf8ead8d3556cf43912bd245fbc4")]
+        );
+        assert_eq!(index.entries.keys().collect::<Vec<_>>(), [Path::new("k")]);
+
+        index.save().unwrap();
+        let reloaded = Index::try_load(Path::new(repo.path())).unwrap();
+        assert_eq!(stages(&reloaded, "c.txt"), stages(&index, "c.txt"));
+        assert_eq!(stages(&reloaded, "both"), stages(&index, "both"));
+        assert_eq!(reloaded.entries.len(), 1);
+    }
+
+    #[test]
+    fn conflicts_are_resolved() {
+        let repo = conflict_repo("index-conflict-resolve");
+        let mut index = Index::try_load(Path::new(repo.path())).unwrap();
+
+        assert!(!index.resolve_conflict(Path::new("c.txt"), 0));
+        assert!(!index.resolve_conflict(Path::new("k"), 2));
+
+        // 取 theirs
+        assert!(index.resolve_conflict(Path::new("c.txt"), 3));
+        let entry = &index.entries[Path::new("c.txt")];
+        assert_eq!((entry.stage, entry.sha.as_str()), (0, "950b81b7eee953d050aa05a641f8e056c85dd1bd"));
+
+        // base 中没有 both：解决为删除
+        assert!(index.resolve_conflict(Path::new("both"), 1));
+        assert!(!index.entries.contains_key(Path::new("both")));
+        assert!(!index.has_conflicts());
+
+        index.save().unwrap();
+        let content = read_file_bytes(&format!("{}/.git/index", repo.path())).unwrap();
+        let (entries, _) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        let paths: Vec<(&str, u8)> = entries.iter().map(|e| (e.path.to_str().unwrap(), e.stage)).collect();
+        assert_eq!(paths, [("c.txt", 0), ("k", 0)]);
+    }
+
+    #[test]
+    fn recorded_conflict_replaces_entry() {
+        let repo = conflict_repo("index-conflict-record");
+        let mut index = Index::try_load(Path::new(repo.path())).unwrap();
+        let blob = "2fa992c0b8b5c6acd2bdd4fa31de29d29799bdd5";
+        index.record_conflict(Path::new("k"), [Some((blob, 0o100644)), None, Some((blob, 0o100755))]);
+        assert!(index.entries.is_empty());
+        assert_eq!(stages(&index, "k"), [(1, blob), (3, blob)]);
+        assert_eq!(index.unmerged[Path::new("k")][1].mode, 0o100755);
+
+        index.record_conflict(Path::new("x"), [None, None, None]);
+        assert!(!index.unmerged.contains_key(Path::new("x")));
+
+        // 暂存冲突中的文件即视为解决
+        let file = Path::new(repo.path()).join("k");
+        std::fs::write(&file, "k\n").unwrap();
+        index.stage_file(&file, blob);
+        assert!(!index.unmerged.contains_key(Path::new("k")));
+        assert_eq!(index.entries[Path::new("k")].stage, 0);
+    }
+
+    #[test]
+    fn zero_checksum_is_accepted() {
+        // index.skipHash
+        let mut content = fixture("v2-tree");
+        let body_end = content.len() - 20;
+        content[body_end..].fill(0);
+        let (entries, cache_tree) = parse_index(&content, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert!(cache_tree.unwrap().is_valid());
+    }
+
+    #[test]
+    fn second_lock_attempt_reports_the_lock_path() {
+        let repo = crate::utils::fs::TempRepo::new("index-lock-contention");
+        let repo_path = Path::new(repo.path());
+        let held = Index::lock(repo_path).unwrap();
+
+        let lock_path = repo_path.join(".git").join("index.lock");
+        match Index::lock(repo_path) {
+            Err(GitError::IndexLocked(path)) => assert_eq!(path, lock_path.display().to_string()),
+            other => panic!("expected IndexLocked, got {:?}", other.map(|_| ())),
+        }
+        // 第二次尝试失败不能删除别人持有的锁
+        assert!(lock_path.exists());
+
+        drop(held);
+        assert!(!lock_path.exists());
+        assert!(Index::lock(repo_path).is_ok());
+    }
+
+    /// 在临时仓库中写入工作区文件，返回只读加载的空暂存区和按当前 stat 构造的条目
+    fn stat_fixture(name: &str, content: &[u8]) -> (crate::utils::fs::TempRepo, Index, IndexEntry) {
+        let repo = crate::utils::fs::TempRepo::new(name);
+        let path = Path::new(repo.path()).join("file.txt");
+        std::fs::write(&path, content).unwrap();
+        let sha = BlobProcessor::hash_reader(HashAlgorithm::Sha1, &mut &content[..], content.len() as u64).unwrap();
+        let entry = IndexEntry::from_metadata(PathBuf::from("file.txt"), &sha, &metadata(&path).unwrap());
+        let index = Index::try_load(Path::new(repo.path())).unwrap();
+        (repo, index, entry)
+    }
+
+    /// index 文件写入时间晚于条目的修改时间，条目不是 racily clean
+    fn written_after(index: &mut Index, entry: &IndexEntry) {
+        index.timestamp = Some((entry.mtime + 10, 0));
+    }
+
+    #[test]
+    fn matching_stat_skips_rehash() {
+        let (_repo, mut index, mut entry) = stat_fixture("index-stat-match", b"hello\n");
+        written_after(&mut index, &entry);
+        assert!(!index.is_modified(&entry));
+
+        // 哈希与内容不符，但 stat 一致时根本不会重新计算哈希
+        entry.sha = "0".repeat(40);
+        assert!(!index.is_modified(&entry));
+    }
+
+    #[test]
+    fn changed_size_or_mtime_is_detected() {
+        let (repo, mut index, entry) = stat_fixture("index-stat-changed", b"hello\n");
+        written_after(&mut index, &entry);
+        let path = Path::new(repo.path()).join("file.txt");
+
+        // 大小变化：不需要计算哈希即可判定
+        std::fs::write(&path, b"hello world\n").unwrap();
+        assert!(index.is_modified(&entry));
+
+        // 大小相同、只有修改时间不同：重新计算哈希后判定
+        std::fs::write(&path, b"HELLO\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        let later = std::time::UNIX_EPOCH + std::time::Duration::from_secs(entry.mtime + 5);
+        file.set_modified(later).unwrap();
+        assert!(index.is_modified(&entry));
+
+        // 只是 touch 过、内容未变
+        std::fs::write(&path, b"hello\n").unwrap();
+        file.set_modified(later).unwrap();
+        assert!(!index.is_modified(&entry));
+    }
+
+    #[test]
+    fn same_second_mtime_entry_is_rehashed_and_smudged() {
+        let (repo, mut index, entry) = stat_fixture("index-racy", b"hello\n");
+        let path = Path::new(repo.path()).join("file.txt");
+        // index 与文件在同一时刻写入
+        index.timestamp = Some((entry.mtime, entry.mtime_nsec));
+        assert!(index.is_racily_clean(&entry));
+        assert!(!index.is_modified(&entry));
+
+        // 同一时刻内容被改写为等长内容，stat 完全一致，只能靠重新计算哈希发现
+        std::fs::write(&path, b"HELLO\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::new(entry.mtime, entry.mtime_nsec);
+        file.set_modified(mtime).unwrap();
+        let entry = IndexEntry::from_metadata(entry.path.clone(), &entry.sha, &metadata(&path).unwrap());
+        assert!(index.is_modified(&entry));
+
+        // 写入前把这样的条目大小置 0，之后 index 时间戳变晚也仍会被发现
+        index.entries.insert(entry.path.clone(), entry.clone());
+        index.smudge_racily_clean_entries();
+        let smudged = &index.entries[&entry.path];
+        assert_eq!(smudged.size, 0);
+        index.timestamp = Some((entry.mtime + 10, 0));
+        assert!(index.is_modified(smudged));
+    }
+
+    #[test]
+    fn assume_valid_and_skip_worktree_entries_are_not_checked() {
+        let (repo, mut index, mut entry) = stat_fixture("index-assume-valid", b"hello\n");
+        written_after(&mut index, &entry);
+        std::fs::write(Path::new(repo.path()).join("file.txt"), b"hello world\n").unwrap();
+        assert!(index.is_modified(&entry));
+
+        entry.flags = FLAG_ASSUME_VALID;
+        assert!(!index.is_modified(&entry));
+        entry.flags = 0;
+        entry.extended_flags = EXT_FLAG_SKIP_WORKTREE;
+        assert!(!index.is_modified(&entry));
+    }
+
+    #[test]
+    fn executable_bit_is_ignored_when_core_file_mode_is_false() {
+        let (repo, mut index, mut entry) = stat_fixture("index-filemode", b"hello\n");
+        written_after(&mut index, &entry);
+        entry.mode = 0o100755;
+        assert!(index.is_modified(&entry));
+
+        std::fs::write(Path::new(repo.path()).join(".git/config"), "[core]\n\tfileMode = false\n").unwrap();
+        let mut index = Index::try_load(Path::new(repo.path())).unwrap();
+        written_after(&mut index, &entry);
+        assert!(!index.is_modified(&entry));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_compared_by_link_target() {
+        let repo = crate::utils::fs::TempRepo::new("index-symlink");
+        let link = Path::new(repo.path()).join("link");
+        std::os::unix::fs::symlink("missing-target", &link).unwrap();
+        let hash = |target: &[u8]| BlobProcessor::hash_reader(HashAlgorithm::Sha1, &mut &target[..], target.len() as u64).unwrap();
+
+        // 悬空的符号链接也能暂存，模式为 120000，大小为链接目标路径的长度
+        let mut index = Index::try_load(Path::new(repo.path())).unwrap();
+        index.stage_file(&link, &hash(b"missing-target"));
+        let entry = index.entries[Path::new("link")].clone();
+        assert_eq!((entry.mode, entry.size), (0o120000, 14));
+        written_after(&mut index, &entry);
+        assert!(!index.is_modified(&entry));
+
+        // 重新计算哈希时使用链接目标路径，而不是跟随链接读取文件
+        index.timestamp = Some((entry.mtime, entry.mtime_nsec));
+        assert!(!index.is_modified(&entry));
+
+        std::fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink("missing-tarGET", &link).unwrap();
+        assert!(index.is_modified(&entry));
     }
 }
@@ -0,0 +1,559 @@
+use crate::core::config::Config;
+use crate::core::pack::{self, PackFile};
+use crate::utils::fs::write_file_atomic;
+use crate::utils::hash::HashAlgorithm;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const SIGNATURE: &[u8; 4] = b"MIDX";
+const CHUNK_PACK_NAMES: u32 = 0x504e_414d; // "PNAM"
+const CHUNK_OID_FANOUT: u32 = 0x4f49_4446; // "OIDF"
+const CHUNK_OID_LOOKUP: u32 = 0x4f49_444c; // "OIDL"
+const CHUNK_OBJECT_OFFSETS: u32 = 0x4f4f_4646; // "OOFF"
+const CHUNK_LARGE_OFFSETS: u32 = 0x4c4f_4646; // "LOFF"
+
+/// OOFF 中偏移的最高位：其余位是 LOFF 中的下标
+const LARGE_OFFSET_NEEDED: u32 = 0x8000_0000;
+
+/// 已加载的 multi-pack-index（`.git/objects/pack/multi-pack-index`）
+///
+/// 把多个 pack 的 .idx 合并成一张有序表：每个对象记录所在 pack 的编号与偏移，
+/// 查找对象只需一次二分查找，而不必逐个探测每个 .idx。文件格式与 Git 相同：
+/// header、chunk 表、PNAM / OIDF / OIDL / OOFF / LOFF，最后是文件校验和。
+pub struct MultiPackIndex {
+    hash_len: usize,
+    data: Vec<u8>,
+    /// pack 名称（`pack-<checksum>.idx`），按字典序排列，下标即 pack 编号
+    pack_names: Vec<String>,
+    fanout: usize,
+    oid_lookup: usize,
+    object_offsets: usize,
+    large_offsets: Option<(usize, usize)>,
+    count: usize,
+    /// 最近一次使用的 pack 列表与 pack 编号的对应关系
+    pack_map: RefCell<Option<Rc<PackMap>>>,
+}
+
+/// multi-pack-index 的 pack 编号与一份已打开的 pack 列表之间的对应关系
+///
+/// 按 pack 名称匹配只在 pack 列表变化（重新扫描）后做一次，之后的查找都是下标访问。
+struct PackMap {
+    /// 持有列表本身，保证以指针比较判断是否为同一份列表时不会误判
+    packs: Rc<Vec<PackFile>>,
+    /// pack 编号 -> 在 `packs` 中的下标（pack 已被删除时为 None）
+    positions: Vec<Option<usize>>,
+    /// `packs` 中的每个 pack 是否被覆盖
+    covered: Vec<bool>,
+}
+
+thread_local! {
+    /// 仓库路径 -> 已加载的 multi-pack-index（None 表示文件不存在或无效）
+    static MIDX: RefCell<HashMap<String, Option<Rc<MultiPackIndex>>>> = RefCell::new(HashMap::new());
+}
+
+/// multi-pack-index 文件路径
+pub fn midx_path(repo_path: &str) -> PathBuf {
+    pack::pack_dir(repo_path).join("multi-pack-index")
+}
+
+impl MultiPackIndex {
+    /// 获取仓库的 multi-pack-index（加载结果会被缓存）
+    pub fn for_repo(repo_path: &str) -> Option<Rc<MultiPackIndex>> {
+        if let Some(cached) = MIDX.with(|m| m.borrow().get(repo_path).cloned()) {
+            return cached;
+        }
+        let algo = HashAlgorithm::for_repo(repo_path);
+        let midx = std::fs::read(midx_path(repo_path))
+            .ok()
+            .and_then(|data| MultiPackIndex::parse(data, algo))
+            .map(Rc::new);
+        MIDX.with(|m| m.borrow_mut().insert(repo_path.to_string(), midx.clone()));
+        midx
+    }
+
+    /// 丢弃缓存（写入或删除 multi-pack-index、重新扫描 pack 后调用）
+    pub fn invalidate(repo_path: &str) {
+        MIDX.with(|m| m.borrow_mut().remove(repo_path));
+    }
+
+    /// 解析 multi-pack-index 文件内容，格式不符时返回 None
+    pub fn parse(data: Vec<u8>, algo: HashAlgorithm) -> Option<MultiPackIndex> {
+        let hash_len = algo.raw_len();
+        if data.len() < 12 + hash_len || &data[0..4] != SIGNATURE || data[4] != 1 || data[5] != hash_version(algo) {
+            return None;
+        }
+        let chunk_count = data[6] as usize;
+        // 不支持增量 multi-pack-index 链
+        if data[7] != 0 {
+            return None;
+        }
+        let pack_count = read_u32(&data, 8) as usize;
+
+        // chunk 表：(id, offset) × (chunk_count + 1)，每个 chunk 的结束位置即下一项的偏移
+        let table_end = 12 + (chunk_count + 1) * 12;
+        if data.len() < table_end + hash_len {
+            return None;
+        }
+        let mut chunks = HashMap::new();
+        for i in 0..chunk_count {
+            let pos = 12 + i * 12;
+            let offset = read_u64(&data, pos + 4) as usize;
+            let end = read_u64(&data, pos + 16) as usize;
+            if offset > end || end > data.len() - hash_len {
+                return None;
+            }
+            chunks.insert(read_u32(&data, pos), (offset, end));
+        }
+
+        let (names_start, names_end) = *chunks.get(&CHUNK_PACK_NAMES)?;
+        let pack_names: Vec<String> = data[names_start..names_end]
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect();
+        if pack_names.len() != pack_count {
+            return None;
+        }
+
+        let fanout = chunks.get(&CHUNK_OID_FANOUT)?.0;
+        let oid_lookup = chunks.get(&CHUNK_OID_LOOKUP)?.0;
+        let object_offsets = chunks.get(&CHUNK_OBJECT_OFFSETS)?.0;
+        if fanout + 256 * 4 > data.len() {
+            return None;
+        }
+        let count = read_u32(&data, fanout + 255 * 4) as usize;
+        if oid_lookup + count * hash_len > data.len() || object_offsets + count * 8 > data.len() {
+            return None;
+        }
+
+        Some(MultiPackIndex {
+            hash_len,
+            large_offsets: chunks.get(&CHUNK_LARGE_OFFSETS).copied(),
+            data,
+            pack_names,
+            fanout,
+            oid_lookup,
+            object_offsets,
+            count,
+            pack_map: RefCell::new(None),
+        })
+    }
+
+    /// 索引中的对象数量
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 覆盖的 pack（`pack-<checksum>.idx`），下标即 pack 编号
+    pub fn pack_names(&self) -> &[String] {
+        &self.pack_names
+    }
+
+    /// 是否覆盖了 `packs` 中的第 i 个 pack
+    pub fn covers(&self, packs: &Rc<Vec<PackFile>>, i: usize) -> bool {
+        self.pack_map(packs).covered.get(i).copied().unwrap_or(false)
+    }
+
+    /// 取得与 `packs` 对应的 pack 编号映射，列表与上次相同时直接复用
+    fn pack_map(&self, packs: &Rc<Vec<PackFile>>) -> Rc<PackMap> {
+        if let Some(map) = self.pack_map.borrow().as_ref().filter(|m| Rc::ptr_eq(&m.packs, packs)) {
+            return map.clone();
+        }
+        let by_name: HashMap<String, usize> = packs.iter().enumerate().map(|(i, p)| (idx_name(p), i)).collect();
+        let positions: Vec<Option<usize>> = self.pack_names.iter().map(|name| by_name.get(name).copied()).collect();
+        let mut covered = vec![false; packs.len()];
+        for &i in positions.iter().flatten() {
+            covered[i] = true;
+        }
+        let map = Rc::new(PackMap { packs: packs.clone(), positions, covered });
+        *self.pack_map.borrow_mut() = Some(map.clone());
+        map
+    }
+
+    /// 第 i 个对象的二进制 SHA
+    pub fn sha_at(&self, i: usize) -> &[u8] {
+        let start = self.oid_lookup + i * self.hash_len;
+        &self.data[start..start + self.hash_len]
+    }
+
+    /// 第 i 个对象所在的 (pack 编号, 偏移)
+    pub fn entry_at(&self, i: usize) -> Option<(usize, u64)> {
+        let pos = self.object_offsets + i * 8;
+        let pack_id = read_u32(&self.data, pos) as usize;
+        let offset = read_u32(&self.data, pos + 4);
+        let offset = match self.large_offsets {
+            Some((start, end)) if offset & LARGE_OFFSET_NEEDED != 0 => {
+                let at = start + (offset & !LARGE_OFFSET_NEEDED) as usize * 8;
+                if at + 8 > end {
+                    return None;
+                }
+                read_u64(&self.data, at)
+            }
+            _ => offset as u64,
+        };
+        Some((pack_id, offset))
+    }
+
+    fn fanout_at(&self, byte: usize) -> usize {
+        read_u32(&self.data, self.fanout + byte * 4) as usize
+    }
+
+    /// 二分查找对象，返回其在索引中的位置
+    pub fn position(&self, sha: &[u8]) -> Option<usize> {
+        let first = *sha.first()? as usize;
+        let mut lo = if first == 0 { 0 } else { self.fanout_at(first - 1) };
+        let mut hi = self.fanout_at(first).min(self.count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.sha_at(mid).cmp(sha) {
+                std::cmp::Ordering::Equal => return Some(mid),
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        None
+    }
+
+    /// 查找以给定十六进制前缀开头的所有对象，返回其在索引中的位置
+    pub fn find_prefix(&self, prefix: &str) -> Vec<usize> {
+        let Some(first) = prefix.get(0..2).and_then(|b| u8::from_str_radix(b, 16).ok()) else {
+            return Vec::new();
+        };
+        let first = first as usize;
+        let lo = if first == 0 { 0 } else { self.fanout_at(first - 1) };
+        let hi = self.fanout_at(first).min(self.count);
+        (lo..hi)
+            .filter(|&i| hex::encode(self.sha_at(i)).starts_with(prefix))
+            .collect()
+    }
+
+    /// 查找对象所在的 pack（在 `packs` 中的下标）与偏移
+    ///
+    /// pack 已被删除（multi-pack-index 过期）时返回 None，调用方应回退到逐个查找。
+    pub fn locate(&self, packs: &Rc<Vec<PackFile>>, sha: &[u8]) -> Option<(usize, u64)> {
+        let (pack_id, offset) = self.entry_at(self.position(sha)?)?;
+        let pack = (*self.pack_map(packs).positions.get(pack_id)?)?;
+        Some((pack, offset))
+    }
+}
+
+/// pack 在 multi-pack-index 中的名称：`pack-<checksum>.idx`
+fn idx_name(pack: &PackFile) -> String {
+    pack.pack_path
+        .with_extension("idx")
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 为 pack 目录中的所有 pack 写出 multi-pack-index，返回 (pack 数量, 对象数量)
+///
+/// 同一对象出现在多个 pack 中时，选择修改时间最新的 pack（与 Git 相同）。
+pub fn write_midx(repo_path: &str) -> io::Result<(usize, usize)> {
+    let algo = HashAlgorithm::for_repo(repo_path);
+    let hash_len = algo.raw_len();
+
+    // 1️⃣ pack 按名称排序，下标即 pack 编号
+    let packs = pack::rescan_packs(repo_path);
+    let mut order: Vec<usize> = (0..packs.len()).collect();
+    order.sort_by_key(|&i| idx_name(&packs[i]));
+    let names: Vec<String> = order.iter().map(|&i| idx_name(&packs[i])).collect();
+    let mtime = |p: &PackFile| std::fs::metadata(&p.pack_path).and_then(|m| m.modified()).ok();
+
+    // 2️⃣ 合并所有对象：(sha, pack 编号, 偏移)，重复对象保留最新 pack 中的那一份
+    let mut objects: Vec<(&[u8], usize, u64)> = Vec::new();
+    for (pack_id, &i) in order.iter().enumerate() {
+        let index = &packs[i].index;
+        for pos in 0..index.len() {
+            let offset = index.offset_at(pos).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("corrupt pack index {}", names[pack_id]))
+            })?;
+            objects.push((index.sha_at(pos), pack_id, offset));
+        }
+    }
+    objects.sort_by(|a, b| {
+        a.0.cmp(b.0)
+            .then_with(|| mtime(&packs[order[b.1]]).cmp(&mtime(&packs[order[a.1]])))
+            .then_with(|| a.1.cmp(&b.1))
+    });
+    objects.dedup_by(|later, first| later.0 == first.0);
+
+    // 3️⃣ 构造各个 chunk
+    let mut pack_names = Vec::new();
+    for name in &names {
+        pack_names.extend(name.as_bytes());
+        pack_names.push(0);
+    }
+    while pack_names.len() % 4 != 0 {
+        pack_names.push(0);
+    }
+
+    let mut fanout = vec![0u8; 256 * 4];
+    let mut counts = [0u32; 256];
+    for (sha, _, _) in &objects {
+        counts[sha[0] as usize] += 1;
+    }
+    let mut total = 0;
+    for (i, count) in counts.iter().enumerate() {
+        total += count;
+        fanout[i * 4..i * 4 + 4].copy_from_slice(&total.to_be_bytes());
+    }
+
+    let mut lookup = Vec::with_capacity(objects.len() * hash_len);
+    for (sha, _, _) in &objects {
+        lookup.extend(*sha);
+    }
+
+    // 只有存在超过 32 位的偏移时才写 LOFF，此时超过 31 位的偏移都放进 LOFF
+    let large_needed = objects.iter().any(|o| o.2 > u32::MAX as u64);
+    let mut offsets = Vec::with_capacity(objects.len() * 8);
+    let mut large = Vec::new();
+    for &(_, pack_id, offset) in &objects {
+        offsets.extend((pack_id as u32).to_be_bytes());
+        if large_needed && offset >= LARGE_OFFSET_NEEDED as u64 {
+            offsets.extend((LARGE_OFFSET_NEEDED | (large.len() / 8) as u32).to_be_bytes());
+            large.extend(offset.to_be_bytes());
+        } else {
+            offsets.extend((offset as u32).to_be_bytes());
+        }
+    }
+
+    let mut chunks: Vec<(u32, Vec<u8>)> = vec![
+        (CHUNK_PACK_NAMES, pack_names),
+        (CHUNK_OID_FANOUT, fanout),
+        (CHUNK_OID_LOOKUP, lookup),
+        (CHUNK_OBJECT_OFFSETS, offsets),
+    ];
+    if !large.is_empty() {
+        chunks.push((CHUNK_LARGE_OFFSETS, large));
+    }
+
+    // 4️⃣ header + chunk 表 + chunk 内容 + 校验和
+    let mut out = SIGNATURE.to_vec();
+    out.extend([1, hash_version(algo), chunks.len() as u8, 0]);
+    out.extend((names.len() as u32).to_be_bytes());
+    let mut offset = (12 + (chunks.len() + 1) * 12) as u64;
+    for (id, chunk) in &chunks {
+        out.extend(id.to_be_bytes());
+        out.extend(offset.to_be_bytes());
+        offset += chunk.len() as u64;
+    }
+    out.extend(0u32.to_be_bytes());
+    out.extend(offset.to_be_bytes());
+    for (_, chunk) in &chunks {
+        out.extend(chunk);
+    }
+    let checksum = algo.digest(&out);
+    out.extend(checksum);
+
+    let count = objects.len();
+    write_file_atomic(&midx_path(repo_path), &out, Config::load(repo_path).fsync("pack-metadata"))?;
+    MultiPackIndex::invalidate(repo_path);
+    Ok((names.len(), count))
+}
+
+/// 校验 multi-pack-index，返回发现的问题（为空表示通过）
+///
+/// 检查文件校验和、fan-out 与对象顺序、引用的 pack 是否存在、
+/// 每个对象记录的偏移是否与对应 .idx 一致，以及每个 pack 的对象是否都被收录。
+pub fn verify_midx(repo_path: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let algo = HashAlgorithm::for_repo(repo_path);
+    let hash_len = algo.raw_len();
+
+    // 1️⃣ 文件校验和
+    let Ok(data) = std::fs::read(midx_path(repo_path)) else {
+        return vec!["multi-pack-index file not found".to_string()];
+    };
+    if data.len() < hash_len || algo.digest(&data[..data.len() - hash_len]) != data[data.len() - hash_len..] {
+        errors.push("incorrect checksum".to_string());
+    }
+    let Some(midx) = MultiPackIndex::parse(data, algo) else {
+        errors.push("invalid multi-pack-index file".to_string());
+        return errors;
+    };
+
+    // 2️⃣ 引用的 pack
+    let packs = pack::rescan_packs(repo_path);
+    let mut by_id = Vec::new();
+    for name in midx.pack_names() {
+        let pack = packs.iter().find(|p| idx_name(p) == *name);
+        if pack.is_none() {
+            errors.push(format!("failed to load pack '{}'", name));
+        }
+        by_id.push(pack);
+    }
+    if midx.pack_names().windows(2).any(|w| w[0] >= w[1]) {
+        errors.push("pack names out of order".to_string());
+    }
+
+    // 3️⃣ fan-out 与对象顺序
+    for byte in 1..256 {
+        if midx.fanout_at(byte) < midx.fanout_at(byte - 1) {
+            errors.push(format!("oid fanout out of order: fanout[{}] > fanout[{}]", byte - 1, byte));
+        }
+    }
+    for i in 1..midx.len() {
+        if midx.sha_at(i - 1) >= midx.sha_at(i) {
+            errors.push(format!("oid lookup out of order: oid[{}] >= oid[{}]", i - 1, i));
+        }
+    }
+
+    // 4️⃣ 每个对象的偏移与 .idx 一致
+    for i in 0..midx.len() {
+        let sha = midx.sha_at(i);
+        let Some((pack_id, offset)) = midx.entry_at(i) else {
+            errors.push(format!("bad large offset for {}", hex::encode(sha)));
+            continue;
+        };
+        match by_id.get(pack_id) {
+            Some(Some(pack)) => match pack.index.find_offset(sha) {
+                Some(expected) if expected == offset => {}
+                Some(expected) => errors.push(format!(
+                    "incorrect object offset for oid[{}] = {}: {} != {}",
+                    i,
+                    hex::encode(sha),
+                    offset,
+                    expected
+                )),
+                None => errors.push(format!("object {} not found in {}", hex::encode(sha), midx.pack_names[pack_id])),
+            },
+            Some(None) => {}
+            None => errors.push(format!("bad pack-int-id {} for {}", pack_id, hex::encode(sha))),
+        }
+    }
+
+    // 5️⃣ pack 中的每个对象都已收录
+    for pack in by_id.iter().flatten() {
+        for pos in 0..pack.index.len() {
+            if midx.position(pack.index.sha_at(pos)).is_none() {
+                errors.push(format!("object {} in {} missing from multi-pack-index", hex::encode(pack.index.sha_at(pos)), idx_name(pack)));
+            }
+        }
+    }
+
+    errors
+}
+
+/// header 中的哈希版本：1 = SHA-1，2 = SHA-256
+fn hash_version(algo: HashAlgorithm) -> u8 {
+    match algo {
+        HashAlgorithm::Sha1 => 1,
+        HashAlgorithm::Sha256 => 2,
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], pos: usize) -> u64 {
+    u64::from_be_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+    use std::path::Path;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// 两个有重叠对象的 pack：`tests/fixtures/pack` 中的较旧，`tests/fixtures/bitmap` 中的较新（修改时间）
+    const PACKS: [(&str, u64); 2] = [
+        ("pack/pack-ec8a003d24bea4e9c52612ad45a951deddae618a", 1_700_000_000),
+        ("bitmap/pack-7a21f599e451f3dfe33edd44f5cdc730b046f4a0", 1_700_000_100),
+    ];
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+    }
+
+    /// 在临时仓库中放入两个 pack，并设置与生成夹具时相同的修改时间
+    fn repo_with_packs(name: &str) -> TempRepo {
+        let repo = TempRepo::new(name);
+        let dir = pack::pack_dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        for (pack, mtime) in PACKS {
+            let src = fixtures().join(pack);
+            let base = dir.join(src.file_name().unwrap());
+            std::fs::copy(src.with_extension("idx"), base.with_extension("idx")).unwrap();
+            std::fs::copy(src.with_extension("pack"), base.with_extension("pack")).unwrap();
+            let file = std::fs::File::options().write(true).open(base.with_extension("pack")).unwrap();
+            file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime)).unwrap();
+        }
+        repo
+    }
+
+    #[test]
+    fn writes_same_bytes_as_git() {
+        let repo = repo_with_packs("midx-write");
+        assert_eq!(write_midx(repo.path()).unwrap(), (2, 19));
+        // `git multi-pack-index write` 对同样两个 pack 的输出
+        let expected = std::fs::read(fixtures().join("midx/multi-pack-index")).unwrap();
+        assert_eq!(std::fs::read(midx_path(repo.path())).unwrap(), expected);
+        assert!(verify_midx(repo.path()).is_empty());
+    }
+
+    #[test]
+    fn reads_git_multi_pack_index() {
+        let repo = repo_with_packs("midx-read");
+        let data = std::fs::read(fixtures().join("midx/multi-pack-index")).unwrap();
+        let midx = MultiPackIndex::parse(data, HashAlgorithm::Sha1).expect("midx");
+        assert_eq!(midx.len(), 19);
+        assert_eq!(midx.pack_names()[0], "pack-7a21f599e451f3dfe33edd44f5cdc730b046f4a0.idx");
+
+        // 两个 pack 都有的对象取自较新的 pack（较旧 pack 的对象是它的子集）
+        let packs = pack::rescan_packs(repo.path());
+        for (sha, pack_name) in [
+            ("d1b5027b62afcfdbf227cde925f01c46528631ec", "pack-7a21f599e451f3dfe33edd44f5cdc730b046f4a0.idx"),
+            ("aad66c0d02ae60283ff0199e3b5bae5d229b9753", "pack-7a21f599e451f3dfe33edd44f5cdc730b046f4a0.idx"),
+        ] {
+            let sha = hex::decode(sha).unwrap();
+            let (pack, offset) = midx.locate(&packs, &sha).unwrap();
+            assert_eq!(idx_name(&packs[pack]), pack_name);
+            assert_eq!(packs[pack].index.find_offset(&sha), Some(offset));
+        }
+        assert_eq!(midx.find_prefix("d1b5").len(), 1);
+        assert!(midx.position(&[0u8; 20]).is_none());
+    }
+
+    #[test]
+    fn falls_back_to_other_packs_when_located_entry_is_unreadable() {
+        let repo = repo_with_packs("midx-fallback");
+        write_midx(repo.path()).unwrap();
+        let sha = "d1b5027b62afcfdbf227cde925f01c46528631ec";
+        let sha_bytes = hex::decode(sha).unwrap();
+        let packs = pack::packs(repo.path());
+        let midx = MultiPackIndex::for_repo(repo.path()).unwrap();
+        let (i, offset) = midx.locate(&packs, &sha_bytes).unwrap();
+
+        // 破坏 multi-pack-index 选中的条目（跳过类型与大小头部），另一个 pack 中还有同一对象
+        let path = packs[i].pack_path.clone();
+        let mut data = std::fs::read(&path).unwrap();
+        let start = offset as usize + 2;
+        data[start..start + 16].fill(0xff);
+        std::fs::write(&path, data).unwrap();
+
+        let object = pack::read_packed_object(repo.path(), sha).expect("object from the other pack");
+        assert_eq!(object.data().len(), 176);
+    }
+
+    #[test]
+    fn verify_reports_corruption() {
+        let repo = repo_with_packs("midx-verify");
+        write_midx(repo.path()).unwrap();
+        let path = midx_path(repo.path());
+        let mut data = std::fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        std::fs::write(&path, data).unwrap();
+        assert!(!verify_midx(repo.path()).is_empty());
+    }
+}
@@ -1,8 +1,20 @@
+pub mod bitmap;
 pub mod blob;
+pub mod cache_tree;
+pub mod bloom;
 pub mod commit;
+pub mod commit_graph;
+pub mod config;
 pub mod repository;
 pub mod tree;
 pub mod index;
 pub mod reference;
 pub mod object;
+pub mod odb;
+pub mod alternates;
+pub mod tag;
+pub mod pack;
+pub mod pack_writer;
+pub mod midx;
+pub mod walk;
 
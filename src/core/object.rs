@@ -1,8 +1,13 @@
-use crate::utils::fs::{create_dir, read_file_bytes, write_file_bytes};
-use crate::utils::hash::sha1;
-use std::path::Path;
+use crate::core::alternates::{alternate_dirs, objects_dir};
+use crate::core::odb::{LooseObjectDatabase, ObjectDatabase};
+use crate::core::pack;
+use crate::utils::fs::create_dir;
+use crate::utils::error::GitError;
+use crate::utils::hash::HashAlgorithm;
+use std::path::{Path, PathBuf};
 
 /// Git 对象类型，全部使用二进制 Vec<u8>
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Object {
     Commit(Vec<u8>),
     Tree(Vec<u8>),
@@ -11,91 +16,303 @@ pub enum Object {
 }
 
 impl Object {
+    /// 根据类型名构造对象（"commit" / "tree" / "blob" / "tag"）
+    pub fn from_type(kind: &str, data: Vec<u8>) -> Option<Object> {
+        match kind {
+            "commit" => Some(Object::Commit(data)),
+            "tree" => Some(Object::Tree(data)),
+            "blob" => Some(Object::Blob(data)),
+            "tag" => Some(Object::Tag(data)),
+            _ => None,
+        }
+    }
+
+    /// 对象类型名
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Commit(_) => "commit",
+            Object::Tree(_) => "tree",
+            Object::Blob(_) => "blob",
+            Object::Tag(_) => "tag",
+        }
+    }
+
+    /// 对象数据（不含 header）
+    pub fn data(&self) -> &[u8] {
+        match self {
+            Object::Commit(data) | Object::Tree(data) | Object::Blob(data) | Object::Tag(data) => data,
+        }
+    }
+
+    /// 取出对象数据（不含 header）
+    pub fn into_data(self) -> Vec<u8> {
+        match self {
+            Object::Commit(data) | Object::Tree(data) | Object::Blob(data) | Object::Tag(data) => data,
+        }
+    }
+
+    /// 构造 header + 数据，例如 `blob 12\0<file content>`
+    pub fn encode(&self) -> Vec<u8> {
+        let data = self.data();
+        [format!("{} {}\0", self.type_name(), data.len()).as_bytes(), data].concat()
+    }
+
+    /// 解析 header + 数据，校验类型与长度
+    pub fn decode(raw: &[u8]) -> Option<Object> {
+        // header 示例: "blob 123\0" -> 返回 \0 的位置
+        let pos = raw.iter().position(|&b| b == 0)?;
+        let header = std::str::from_utf8(&raw[..pos]).ok()?;
+        let (kind, len) = header.split_once(' ')?;
+        let len: usize = len.parse().ok()?;
+        let data = &raw[pos + 1..];
+        if data.len() != len {
+            return None;
+        }
+        Object::from_type(kind, data.to_vec())
+    }
+
+    /// 松散对象的存储路径：`.git/objects/xx/yyyy...`
+    pub fn loose_path(repo_path: &str, sha: &str) -> PathBuf {
+        Path::new(repo_path)
+            .join(".git")
+            .join("objects")
+            .join(&sha[0..2])
+            .join(&sha[2..])
+    }
+
+    /// 列出所有松散对象的 SHA（扫描 `.git/objects/xx/` 目录）
+    pub fn list_loose(repo_path: &str) -> Vec<String> {
+        let objects_dir = Path::new(repo_path).join(".git").join("objects");
+        let rest_len = HashAlgorithm::for_repo(repo_path).hex_len() - 2;
+        let mut shas = Vec::new();
+        let Ok(dirs) = std::fs::read_dir(&objects_dir) else { return shas };
+        for dir in dirs.filter_map(Result::ok) {
+            let prefix = dir.file_name().to_string_lossy().to_string();
+            if prefix.len() != 2 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue; // 跳过 info / pack
+            }
+            let Ok(files) = std::fs::read_dir(dir.path()) else { continue };
+            for file in files.filter_map(Result::ok) {
+                let rest = file.file_name().to_string_lossy().to_string();
+                if rest.len() == rest_len && rest.chars().all(|c| c.is_ascii_hexdigit()) {
+                    shas.push(format!("{}{}", prefix, rest));
+                }
+            }
+        }
+        shas.sort();
+        shas
+    }
+
+    /// 把短哈希（至少 4 位十六进制）解析为唯一的完整对象哈希
+    ///
+    /// 同时扫描松散对象的 fan-out 目录 `.git/objects/xx/` 和所有 pack 索引（包括 alternates 中的对象目录）；
+    /// 匹配到多个对象时返回 `AmbiguousObject`，并列出每个候选对象及其类型。
+    pub fn resolve_prefix(repo_path: &str, prefix: &str) -> Result<String, GitError> {
+        let algo = HashAlgorithm::for_repo(repo_path);
+        let prefix = prefix.to_ascii_lowercase();
+        if prefix.len() < 4 || prefix.len() > algo.hex_len() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(GitError::UnknownRevision(prefix));
+        }
+        if prefix.len() == algo.hex_len() {
+            return if LooseObjectDatabase::new(repo_path).contains(&prefix) {
+                Ok(prefix)
+            } else {
+                Err(GitError::ObjectNotFound(prefix))
+            };
+        }
+
+        // 本仓库和 alternates 中的所有对象目录
+        let alternates = alternate_dirs(repo_path);
+        let object_dirs = std::iter::once(objects_dir(repo_path)).chain(alternates.iter().cloned());
+
+        let mut candidates = Vec::new();
+        for objects in object_dirs {
+            // 1️⃣ 松散对象：只需扫描前两位对应的目录
+            if let Ok(files) = std::fs::read_dir(objects.join(&prefix[0..2])) {
+                for file in files.filter_map(Result::ok) {
+                    let rest = file.file_name().to_string_lossy().to_string();
+                    if rest.len() == algo.hex_len() - 2 && rest.starts_with(&prefix[2..]) {
+                        candidates.push(format!("{}{}", &prefix[0..2], rest));
+                    }
+                }
+            }
+
+            // 2️⃣ pack 中的对象
+            for p in pack::packs_in(&objects.join("pack"), algo).iter() {
+                candidates.extend(p.index.find_prefix(&prefix).into_iter().map(|i| hex::encode(p.index.sha_at(i))));
+            }
+        }
+        candidates.sort();
+        candidates.dedup();
+
+        match candidates.len() {
+            0 => Err(GitError::ObjectNotFound(prefix)),
+            1 => Ok(candidates.remove(0)),
+            _ => {
+                // 类型只需读取松散对象的 header 或 pack 条目头部，不必解压整个对象
+                let db = LooseObjectDatabase::new(repo_path);
+                let candidates = candidates
+                    .into_iter()
+                    .map(|sha| {
+                        let kind = db.read_header(&sha).map_or("unknown", |(kind, _)| kind);
+                        (sha, kind.to_string())
+                    })
+                    .collect();
+                Err(GitError::AmbiguousObject { prefix, candidates })
+            }
+        }
+    }
+
     /// 从仓库加载 Git 对象内容（Blob / Tree / Commit / Tag）
     ///
     /// # 参数
     /// - `repo_path`: 仓库根路径（包含 .git 文件夹）
-    /// - `sha`: 对象 SHA1 哈希值（40 位十六进制字符串）
+    /// - `sha`: 对象的完整哈希值（SHA-1 为 40 位、SHA-256 为 64 位十六进制字符串）；
+    ///   短哈希需先通过 `Reference::try_resolve_revision` 解析
     ///
     /// # 返回
     /// - `Some(Vec<u8>)`：对象的原始二进制内容（不含 header）  
     /// - `None`：对象不存在或读取失败
     ///
     /// # 功能说明
-    /// - Git 对象存储在 `.git/objects/xx/yyyy...`，xx 是 SHA 前两位，yyyy... 是剩余 38 位。
-    /// - 文件内容包含 header + 数据，例如：
+    /// - Git 对象存储在 `.git/objects/xx/yyyy...`，xx 是哈希前两位，yyyy... 是剩余部分。
+    /// - 文件内容是 zlib 压缩后的 header + 数据，例如：
     ///     - Blob:  `blob 12\0<file content>`  
     ///     - Tree:  `tree 45\0<tree content>`  
     ///     - Commit: `commit 123\0<commit content>`
-    /// - 本方法会去掉 header，返回纯数据部分。
+    /// - 本方法会解压并去掉 header，返回纯数据部分。
+    /// - 松散对象不存在时，会继续在 `.git/objects/pack` 下的 pack 文件中查找，
+    ///   最后查找 `.git/objects/info/alternates` 中列出的备用对象目录。
     pub fn load(repo_path: &str, sha: &str) -> Option<Vec<u8>> {
-        // ✅ 校验 SHA 长度
-        if sha.len() < 40 {
-            return None;
-        }
+        Self::load_object(repo_path, sha).map(Object::into_data)
+    }
 
-        // 1️⃣ 构造对象路径
-        // SHA: 0123456789abcdef...
-        // 文件路径: .git/objects/01/23456789abcdef...
-        let dir = &sha[0..2];
-        let file = &sha[2..];
-        let obj_path = Path::new(repo_path)
-            .join(".git")
-            .join("objects")
-            .join(dir)
-            .join(file);
+    /// 从仓库加载 Git 对象（保留对象类型）
+    pub fn load_object(repo_path: &str, sha: &str) -> Option<Object> {
+        LooseObjectDatabase::new(repo_path).read(sha)
+    }
 
-        // 2️⃣ 文件不存在直接返回 None
-        if !obj_path.exists() {
-            return None;
-        }
+    /// 计算对象哈希，检测到 SHA-1 碰撞攻击时返回错误
+    pub fn try_hash(&self, algo: HashAlgorithm) -> Result<String, GitError> {
+        algo.try_hash(&self.encode())
+    }
+
+    /// 保存对象到仓库的松散对象库，返回对象哈希（SHA-1 或 SHA-256，取决于仓库配置）
+    pub fn save(&self, repo_path: &str) -> Result<String, GitError> {
+        LooseObjectDatabase::new(repo_path).write(self)
+    }
+
+    /// 创建标签引用（refs/tags/<tag_name>）
+    pub fn create_tag_ref(repo_path: &str, tag_name: &str, obj_hash: &str) {
+        let ref_path = Path::new(repo_path).join(".git").join("refs").join("tags");
+        let _ = create_dir(ref_path.to_str().unwrap());
+        let tag_file = ref_path.join(tag_name);
+        let _ = crate::utils::fs::write_file(tag_file.to_str().unwrap(), obj_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+    use crate::utils::zlib;
 
-        // 3️⃣ 读取对象文件
-        let data = match read_file_bytes(obj_path.to_str().unwrap()) {
-            Ok(d) => d,
-            Err(_) => return None,
-        };
+    /// 一个 blob 和一个 commit，二者的哈希都以 `3c56` 开头
+    fn write_colliding_pair(repo: &TempRepo) -> (String, String) {
+        let blob = Object::Blob(b"blob 1085\n".to_vec()).save(repo.path()).unwrap();
+        let commit = "tree 4b825dc642cb6eb9a060e54bf8d69288fbe4904b\n\
+            author A <a@example.com> 0 +0000\n\
+            committer A <a@example.com> 0 +0000\n\nmessage 5\n";
+        let commit = Object::Commit(commit.as_bytes().to_vec()).save(repo.path()).unwrap();
+        (blob, commit)
+    }
 
-        // 4️⃣ 查找 header 结束位置（\0 分隔符）
-        //    header 示例: "blob 123\0" -> 返回 \0 的位置
-        let pos = match data.iter().position(|&b| b == 0) {
-            Some(p) => p,
-            None => 0, // 若没有找到 header，直接返回整个文件
-        };
+    #[test]
+    fn loose_objects_round_trip_in_git_format() {
+        let repo = TempRepo::new("loose-round-trip");
+        let blob = Object::Blob(b"round trip\n".to_vec());
+        let sha = blob.save(repo.path()).unwrap();
+        assert_eq!(sha, "e9305ebfb4dbc03a923f55bf8a8514486388b5bc");
 
-        // 5️⃣ 返回 header 之后的数据部分
-        Some(data[pos + 1..].to_vec())
+        // 磁盘上是 zlib 压缩的 `blob <len>\0<data>`
+        let compressed = std::fs::read(Object::loose_path(repo.path(), &sha)).unwrap();
+        assert_eq!(zlib::decompress(&compressed).unwrap(), b"blob 11\0round trip\n");
+        assert_eq!(Object::load_object(repo.path(), &sha), Some(blob));
     }
 
-    /// 保存对象到仓库，返回 SHA1 哈希
-    pub fn save(&self, repo_path: &str) -> String {
-        // 构造 header + 数据
-        let raw_data: Vec<u8> = match self {
-            Object::Commit(data) => [format!("commit {}\0", data.len()).as_bytes(), data].concat(),
-            Object::Tree(data) => [format!("tree {}\0", data.len()).as_bytes(), data].concat(),
-            Object::Blob(data) => [format!("blob {}\0", data.len()).as_bytes(), data].concat(),
-            Object::Tag(data) => [format!("tag {}\0", data.len()).as_bytes(), data].concat(),
-        };
+    #[test]
+    fn reads_loose_object_written_by_git() {
+        // `git hash-object -w` 写出的松散对象
+        let repo = TempRepo::new("loose-git");
+        let sha = "7f04e8dee0f5f0370b4be4182274eb6d85e13bb6";
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/loose").join(&sha[..2]).join(&sha[2..]);
+        let path = Object::loose_path(repo.path(), sha);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::copy(fixture, &path).unwrap();
 
-        // 计算 SHA1
-        let hash = sha1(&raw_data);
+        let obj = Object::load_object(repo.path(), sha).unwrap();
+        assert_eq!(obj, Object::Blob(b"written by git\n".to_vec()));
+        assert_eq!(obj.try_hash(HashAlgorithm::Sha1).unwrap(), sha);
+    }
 
-        // 创建对象目录
-        let dir_path = Path::new(repo_path).join(".git").join("objects").join(&hash[0..2]);
-        create_dir(dir_path.to_str().unwrap());
+    #[test]
+    fn resolves_unique_four_character_prefix() {
+        let repo = TempRepo::new("prefix-unique");
+        let (blob, _) = write_colliding_pair(&repo);
+        let other = Object::Blob(b"other\n".to_vec()).save(repo.path()).unwrap();
 
-        // 保存对象文件
-        let file_path = dir_path.join(&hash[2..]);
-        write_file_bytes(file_path.to_str().unwrap(), &raw_data).unwrap();
+        assert_eq!(Object::resolve_prefix(repo.path(), &other[..4]).unwrap(), other);
+        assert_eq!(Object::resolve_prefix(repo.path(), &blob[..5].to_uppercase()).unwrap(), blob);
+        assert!(matches!(Object::resolve_prefix(repo.path(), "ffff"), Err(GitError::ObjectNotFound(_))));
+    }
 
-        hash
+    #[test]
+    fn short_hash_is_only_expanded_by_revision_parsing() {
+        let repo = TempRepo::new("prefix-load");
+        let sha = Object::Blob(b"short\n".to_vec()).save(repo.path()).unwrap();
+
+        // 按哈希读取对象时不做前缀匹配，短哈希由版本解析负责
+        assert_eq!(Object::load_object(repo.path(), &sha[..7]), None);
+        assert_eq!(crate::core::reference::Reference::try_resolve_revision(repo.path(), &sha[..7]).unwrap(), sha);
     }
 
-    /// 创建标签引用（refs/tags/<tag_name>）
-    pub fn create_tag_ref(repo_path: &str, tag_name: &str, obj_hash: &str) {
-        let ref_path = Path::new(repo_path).join(".git").join("refs").join("tags");
-        create_dir(ref_path.to_str().unwrap());
-        let tag_file = ref_path.join(tag_name);
-        let _ = crate::utils::fs::write_file(tag_file.to_str().unwrap(), obj_hash);
+    #[test]
+    fn ambiguous_prefix_lists_each_candidate_with_its_type() {
+        let repo = TempRepo::new("prefix-ambiguous");
+        let (blob, commit) = write_colliding_pair(&repo);
+
+        let err = Object::resolve_prefix(repo.path(), "3c56").unwrap_err();
+        let GitError::AmbiguousObject { prefix, candidates } = &err else { panic!("unexpected {:?}", err) };
+        assert_eq!(prefix, "3c56");
+        assert_eq!(
+            candidates,
+            &vec![(commit.clone(), "commit".to_string()), (blob.clone(), "blob".to_string())]
+        );
+        let message = err.to_string();
+        assert!(message.contains(&format!("{} commit", commit)));
+        assert!(message.contains(&format!("{} blob", blob)));
+    }
+
+    #[test]
+    fn rejects_prefix_shorter_than_four_characters() {
+        let repo = TempRepo::new("prefix-short");
+        write_colliding_pair(&repo);
+        assert!(matches!(Object::resolve_prefix(repo.path(), "3c5"), Err(GitError::UnknownRevision(_))));
+    }
+
+    #[test]
+    fn resolves_prefix_of_object_only_in_a_pack() {
+        let repo = TempRepo::new("prefix-packed");
+        let pack_dir = Path::new(repo.path()).join(".git").join("objects").join("pack");
+        std::fs::create_dir_all(&pack_dir).unwrap();
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pack");
+        for file in std::fs::read_dir(fixture).unwrap().filter_map(Result::ok) {
+            std::fs::copy(file.path(), pack_dir.join(file.file_name())).unwrap();
+        }
+
+        // 深度为 2 的 delta 链末端，只存在于 pack 中
+        let sha = "fcd87345e00673ff10adeb5c83e620d50bb0d62a";
+        assert_eq!(Object::resolve_prefix(repo.path(), "fcd8").unwrap(), sha);
+        assert_eq!(Object::resolve_prefix(repo.path(), sha).unwrap(), sha);
     }
 }
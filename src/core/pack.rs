@@ -0,0 +1,913 @@
+use crate::core::midx::MultiPackIndex;
+use crate::core::object::Object;
+use crate::utils::fs::read_file_bytes;
+use crate::utils::hash::HashAlgorithm;
+use flate2::bufread::ZlibDecoder;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::rc::Rc;
+
+/// pack 中的对象类型编号
+pub const OBJ_COMMIT: u8 = 1;
+pub const OBJ_TREE: u8 = 2;
+pub const OBJ_BLOB: u8 = 3;
+pub const OBJ_TAG: u8 = 4;
+pub const OBJ_OFS_DELTA: u8 = 6;
+pub const OBJ_REF_DELTA: u8 = 7;
+
+/// delta 基础对象缓存上限（字节），与 Git 的 core.deltaBaseCacheLimit 默认值一致
+const DELTA_BASE_CACHE_LIMIT: usize = 96 * 1024 * 1024;
+/// delta 链的最大长度，超过时视为损坏的 pack（防止环形或恶意构造的链耗尽内存）
+const MAX_DELTA_CHAIN: usize = 10_000;
+
+/// 对象类型编号 -> 类型名
+pub fn type_name(type_id: u8) -> Option<&'static str> {
+    match type_id {
+        OBJ_COMMIT => Some("commit"),
+        OBJ_TREE => Some("tree"),
+        OBJ_BLOB => Some("blob"),
+        OBJ_TAG => Some("tag"),
+        _ => None,
+    }
+}
+
+/// 类型名 -> 对象类型编号
+pub fn type_id(type_name: &str) -> Option<u8> {
+    match type_name {
+        "commit" => Some(OBJ_COMMIT),
+        "tree" => Some(OBJ_TREE),
+        "blob" => Some(OBJ_BLOB),
+        "tag" => Some(OBJ_TAG),
+        _ => None,
+    }
+}
+
+// =====================================
+// ---------- .idx v2 索引文件 ----------
+// =====================================
+
+/// pack 索引（.idx v2）
+///
+/// 布局：
+/// - `\377tOc` + 版本号 2
+/// - 256 项 fan-out 表（以每个首字节结尾的累计对象数）
+/// - N 个有序哈希（SHA-1 为 20 字节，SHA-256 仓库为 32 字节）、N 个 CRC32、N 个 4 字节偏移
+/// - 大于 2^31 的偏移存放在 8 字节偏移表中
+/// - pack 校验和 + idx 校验和
+pub struct PackIndex {
+    hash_len: usize,
+    fanout: Vec<u32>,
+    shas: Vec<u8>,
+    crcs: Vec<u32>,
+    offsets: Vec<u32>,
+    large_offsets: Vec<u64>,
+    pub pack_checksum: Vec<u8>,
+}
+
+impl PackIndex {
+    /// 解析 .idx 文件内容，格式不合法时返回 None
+    pub fn parse(data: &[u8], algo: HashAlgorithm) -> Option<PackIndex> {
+        let hash_len = algo.raw_len();
+        if data.len() < 8 + 256 * 4 + 2 * hash_len || data[0..4] != [0xff, b't', b'O', b'c'] {
+            return None;
+        }
+        if read_u32(data, 4) != 2 {
+            return None;
+        }
+
+        let fanout: Vec<u32> = (0..256).map(|i| read_u32(data, 8 + i * 4)).collect();
+        // fanout 必须单调不减，否则二分查找会越界
+        if fanout.windows(2).any(|w| w[0] > w[1]) {
+            return None;
+        }
+        let count = fanout[255] as usize;
+
+        let sha_start = 8 + 256 * 4;
+        let crc_start = sha_start + count * hash_len;
+        let off_start = crc_start + count * 4;
+        let large_start = off_start + count * 4;
+        if data.len() < large_start + 2 * hash_len {
+            return None;
+        }
+
+        let offsets: Vec<u32> = (0..count).map(|i| read_u32(data, off_start + i * 4)).collect();
+        let large_count = offsets.iter().filter(|&&o| o & 0x8000_0000 != 0).count();
+        let trailer = large_start + large_count * 8;
+        if data.len() != trailer + 2 * hash_len {
+            return None;
+        }
+
+        Some(PackIndex {
+            hash_len,
+            fanout,
+            shas: data[sha_start..crc_start].to_vec(),
+            crcs: (0..count).map(|i| read_u32(data, crc_start + i * 4)).collect(),
+            offsets,
+            large_offsets: (0..large_count).map(|i| read_u64(data, large_start + i * 8)).collect(),
+            pack_checksum: data[trailer..trailer + hash_len].to_vec(),
+        })
+    }
+
+    /// 索引中的对象数量
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// 第 i 个对象的二进制 SHA
+    pub fn sha_at(&self, i: usize) -> &[u8] {
+        &self.shas[i * self.hash_len..(i + 1) * self.hash_len]
+    }
+
+    /// 第 i 个对象的 CRC32
+    pub fn crc_at(&self, i: usize) -> u32 {
+        self.crcs[i]
+    }
+
+    /// 第 i 个对象在 pack 中的偏移（.idx 损坏、大偏移表下标越界时返回 None）
+    pub fn offset_at(&self, i: usize) -> Option<u64> {
+        let off = *self.offsets.get(i)?;
+        if off & 0x8000_0000 != 0 {
+            self.large_offsets.get((off & 0x7fff_ffff) as usize).copied()
+        } else {
+            Some(off as u64)
+        }
+    }
+
+    /// 二分查找对象，返回其在索引中的位置
+    pub fn position(&self, sha: &[u8]) -> Option<usize> {
+        let first = sha[0] as usize;
+        let mut lo = if first == 0 { 0 } else { self.fanout[first - 1] as usize };
+        let mut hi = self.fanout[first] as usize;
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.sha_at(mid).cmp(sha) {
+                std::cmp::Ordering::Equal => return Some(mid),
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        None
+    }
+
+    /// 查找以给定十六进制前缀开头的所有对象，返回其在索引中的位置
+    pub fn find_prefix(&self, prefix: &str) -> Vec<usize> {
+        let Some(first) = prefix.get(0..2).and_then(|b| u8::from_str_radix(b, 16).ok()) else {
+            return Vec::new();
+        };
+        let first = first as usize;
+        let lo = if first == 0 { 0 } else { self.fanout[first - 1] as usize };
+        let hi = self.fanout[first] as usize;
+        (lo..hi)
+            .filter(|&i| hex::encode(self.sha_at(i)).starts_with(prefix))
+            .collect()
+    }
+
+    /// 查找对象在 pack 中的偏移
+    pub fn find_offset(&self, sha: &[u8]) -> Option<u64> {
+        self.position(sha).and_then(|i| self.offset_at(i))
+    }
+}
+
+// =====================================
+// ---------- .pack 数据文件 ----------
+// =====================================
+
+/// pack 内一个对象条目的头部信息
+pub struct PackEntryHeader {
+    pub type_id: u8,
+    pub size: usize,
+    /// OFS_DELTA 的基础对象偏移
+    pub base_offset: Option<u64>,
+    /// REF_DELTA 的基础对象 SHA
+    pub base_sha: Option<Vec<u8>>,
+}
+
+/// pack 条目内容的解压读取器（从条目头部之后开始）
+pub type EntryReader = ZlibDecoder<BufReader<File>>;
+
+/// 已打开的 pack 文件（.pack + .idx）
+pub struct PackFile {
+    pub pack_path: PathBuf,
+    pub algo: HashAlgorithm,
+    pub index: PackIndex,
+    /// delta 基础对象缓存：offset -> (类型, 内容)
+    cache: RefCell<DeltaBaseCache>,
+}
+
+impl PackFile {
+    /// 打开 pack 文件（根据 .idx 路径找到对应的 .pack）
+    pub fn open(idx_path: &Path, algo: HashAlgorithm) -> Option<PackFile> {
+        let pack_path = idx_path.with_extension("pack");
+        if !pack_path.exists() {
+            return None;
+        }
+        let index = PackIndex::parse(&read_file_bytes(idx_path.to_str()?).ok()?, algo)?;
+        Some(PackFile {
+            pack_path,
+            algo,
+            index,
+            cache: RefCell::new(DeltaBaseCache::default()),
+        })
+    }
+
+    /// 校验 pack 文件末尾的校验和：需与文件内容的哈希以及 .idx 中记录的值一致
+    ///
+    /// 分块读取计算哈希，不把整个 pack 读入内存。与 Git 相同，内容带有 SHA-1 碰撞攻击特征时也视为校验失败。
+    pub fn verify_checksum(&self) -> bool {
+        let hash_len = self.algo.raw_len() as u64;
+        let Ok(file) = File::open(&self.pack_path) else { return false };
+        let Ok(len) = file.metadata().map(|m| m.len()) else { return false };
+        if len < 12 + hash_len {
+            return false;
+        }
+
+        let mut reader = BufReader::new(file);
+        let mut magic = [0u8; 4];
+        if reader.read_exact(&mut magic).is_err() || &magic != b"PACK" {
+            return false;
+        }
+        let mut hasher = self.algo.hasher();
+        hasher.update(&magic);
+        let mut body = (&mut reader).take(len - hash_len - 4);
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            match body.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => hasher.update(&buf[..n]),
+                Err(_) => return false,
+            }
+        }
+        let mut trailer = vec![0u8; hash_len as usize];
+        if reader.read_exact(&mut trailer).is_err() {
+            return false;
+        }
+        hasher.finalize_checked().is_ok_and(|actual| trailer == actual) && trailer == self.index.pack_checksum
+    }
+
+    /// pack 中是否包含该对象
+    pub fn contains(&self, sha: &[u8]) -> bool {
+        self.index.position(sha).is_some()
+    }
+
+    /// 按 SHA 读取对象（自动解析 delta 链）
+    pub fn read(&self, repo_path: &str, sha: &[u8]) -> Option<Object> {
+        let offset = self.index.find_offset(sha)?;
+        let (type_id, data) = self.read_at(repo_path, offset).ok()?;
+        Object::from_type(type_name(type_id)?, data.as_ref().clone())
+    }
+
+    /// 只读取对象的类型和大小，不还原 delta（只解压 delta 开头的大小字段）
+    pub fn read_header(&self, repo_path: &str, sha: &[u8]) -> Option<(&'static str, u64)> {
+        let offset = self.index.find_offset(sha)?;
+        let (type_id, size) = self.read_header_at(repo_path, offset).ok()?;
+        Some((type_name(type_id)?, size))
+    }
+
+    /// 以流的方式打开指定偏移处的对象：跳过条目头部后返回（类型编号, 大小, 解压读取器）
+    ///
+    /// 只适用于非 delta 条目；delta 需要基础对象才能还原，返回 `Ok(None)`，由调用方整体读取。
+    pub fn stream_at(&self, offset: u64) -> io::Result<Option<(u8, u64, EntryReader)>> {
+        let mut file = BufReader::new(File::open(&self.pack_path)?);
+        let header = read_entry_header(&mut file, offset, self.algo)?;
+        match header.type_id {
+            OBJ_OFS_DELTA | OBJ_REF_DELTA => Ok(None),
+            t if type_name(t).is_some() => Ok(Some((t, header.size as u64, ZlibDecoder::new(file)))),
+            t => Err(invalid(format!("unknown pack object type {}", t))),
+        }
+    }
+
+    /// 读取指定偏移处对象的（类型编号, 大小）
+    ///
+    /// 大小取自第一个 delta 的目标大小字段，类型沿 delta 链找到基础对象后确定。
+    pub fn read_header_at(&self, repo_path: &str, offset: u64) -> io::Result<(u8, u64)> {
+        let mut file = BufReader::new(File::open(&self.pack_path)?);
+        let mut size = None;
+        let mut current = offset;
+        for _ in 0..=MAX_DELTA_CHAIN {
+            if let Some((kind, data)) = self.cache.borrow_mut().get(current) {
+                return Ok((kind, size.unwrap_or(data.len() as u64)));
+            }
+            let header = read_entry_header(&mut file, current, self.algo)?;
+            match header.type_id {
+                OBJ_OFS_DELTA | OBJ_REF_DELTA => {
+                    if size.is_none() {
+                        size = Some(delta_result_size(&mut file)? as u64);
+                    }
+                    current = match (header.base_offset, header.base_sha) {
+                        (Some(base_offset), _) => base_offset,
+                        (None, Some(base_sha)) => match self.index.find_offset(&base_sha) {
+                            Some(base_offset) if base_offset == current => {
+                                return Err(invalid(format!("delta at offset {} refers to itself", current)));
+                            }
+                            Some(base_offset) => base_offset,
+                            None => {
+                                let base = Object::load_object(repo_path, &hex::encode(&base_sha))
+                                    .ok_or_else(|| invalid(format!("missing delta base {}", hex::encode(&base_sha))))?;
+                                return Ok((type_id(base.type_name()).unwrap(), size.unwrap()));
+                            }
+                        },
+                        (None, None) => unreachable!("delta entries always carry a base"),
+                    };
+                }
+                t if type_name(t).is_some() => return Ok((t, size.unwrap_or(header.size as u64))),
+                t => return Err(invalid(format!("unknown pack object type {}", t))),
+            }
+        }
+        Err(invalid(format!("delta chain too long at offset {}", offset)))
+    }
+
+    /// 读取指定偏移处的对象，返回解析完 delta 后的（类型编号, 内容）
+    ///
+    /// delta 链会先逆向收集到非 delta 的基础对象，再从基础对象依次应用，
+    /// 中间结果写入缓存，避免长链反复解压。
+    pub fn read_at(&self, repo_path: &str, offset: u64) -> io::Result<(u8, Rc<Vec<u8>>)> {
+        if let Some(hit) = self.cache.borrow_mut().get(offset) {
+            return Ok(hit);
+        }
+
+        let mut file = BufReader::new(File::open(&self.pack_path)?);
+
+        // 1️⃣ 沿 delta 链向下查找，直到遇到缓存命中或完整对象
+        let mut chain: Vec<(u64, Vec<u8>)> = Vec::new();
+        let mut current = offset;
+        let (kind, mut data) = loop {
+            if let Some(hit) = self.cache.borrow_mut().get(current) {
+                break hit;
+            }
+            if chain.len() >= MAX_DELTA_CHAIN {
+                return Err(invalid(format!("delta chain too long at offset {}", offset)));
+            }
+            let header = read_entry_header(&mut file, current, self.algo)?;
+            let body = inflate(&mut file, header.size)?;
+            match header.type_id {
+                OBJ_OFS_DELTA => {
+                    chain.push((current, body));
+                    current = header.base_offset.unwrap();
+                }
+                OBJ_REF_DELTA => {
+                    let base_sha = header.base_sha.unwrap();
+                    chain.push((current, body));
+                    match self.index.find_offset(&base_sha) {
+                        // 以自身为基础对象的 delta 永远无法解析
+                        Some(base_offset) if base_offset == current => {
+                            return Err(invalid(format!("delta at offset {} refers to itself", current)));
+                        }
+                        Some(base_offset) => current = base_offset,
+                        None => {
+                            // 基础对象不在本 pack 中（thin pack），从对象库中查找
+                            let base = Object::load_object(repo_path, &hex::encode(&base_sha))
+                                .ok_or_else(|| invalid(format!("missing delta base {}", hex::encode(&base_sha))))?;
+                            break (type_id(base.type_name()).unwrap(), Rc::new(base.into_data()));
+                        }
+                    }
+                }
+                t if type_name(t).is_some() => {
+                    let body = Rc::new(body);
+                    self.cache.borrow_mut().insert(current, t, body.clone());
+                    break (t, body);
+                }
+                t => return Err(invalid(format!("unknown pack object type {}", t))),
+            }
+        };
+
+        // 2️⃣ 从基础对象开始依次应用 delta
+        while let Some((delta_offset, delta)) = chain.pop() {
+            data = Rc::new(apply_delta(&data, &delta)?);
+            self.cache.borrow_mut().insert(delta_offset, kind, data.clone());
+        }
+
+        Ok((kind, data))
+    }
+}
+
+/// 读取 pack 条目头部：类型 + 大小（变长编码），以及 delta 基础对象信息
+pub fn read_entry_header<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    algo: HashAlgorithm,
+) -> io::Result<PackEntryHeader> {
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut byte = read_byte(reader)?;
+    let type_id = (byte >> 4) & 0x7;
+    let mut size = (byte & 0x0f) as usize;
+    let mut shift = 4;
+    while byte & 0x80 != 0 {
+        byte = read_byte(reader)?;
+        // 大小超出 usize 范围的变长编码只可能来自损坏的 pack
+        size |= shift_varint_bits(byte, shift).ok_or_else(|| invalid(format!("bad object size at offset {}", offset)))?;
+        shift += 7;
+    }
+
+    let mut header = PackEntryHeader { type_id, size, base_offset: None, base_sha: None };
+    match type_id {
+        OBJ_OFS_DELTA => {
+            // 偏移量使用 Git 特有的“每字节 +1”的大端变长编码
+            let mut byte = read_byte(reader)?;
+            let mut rel = (byte & 0x7f) as u64;
+            while byte & 0x80 != 0 {
+                byte = read_byte(reader)?;
+                // rel 只会增大，超过 offset 后即可判定无效，同时避免移位溢出
+                if rel >= offset {
+                    return Err(invalid(format!("bad delta base offset at {}", offset)));
+                }
+                rel = ((rel + 1) << 7) | (byte & 0x7f) as u64;
+            }
+            // 相对偏移为 0 时基础对象就是自己，delta 链永远不会结束
+            if rel == 0 || rel > offset {
+                return Err(invalid(format!("bad delta base offset at {}", offset)));
+            }
+            header.base_offset = Some(offset - rel);
+        }
+        OBJ_REF_DELTA => {
+            let mut sha = vec![0u8; algo.raw_len()];
+            reader.read_exact(&mut sha)?;
+            header.base_sha = Some(sha);
+        }
+        _ => {}
+    }
+    Ok(header)
+}
+
+/// 从当前位置解压 delta 的开头，读出其中记录的目标对象大小
+fn delta_result_size<R: io::BufRead>(reader: &mut R) -> io::Result<usize> {
+    // 两个变长整数最多各占 10 字节
+    let mut prefix = Vec::new();
+    ZlibDecoder::new(reader).take(20).read_to_end(&mut prefix)?;
+    let mut pos = 0;
+    read_delta_size(&prefix, &mut pos)?;
+    read_delta_size(&prefix, &mut pos)
+}
+
+/// 从当前位置解压一个 zlib 流，并校验解压后的大小
+fn inflate<R: io::BufRead>(reader: &mut R, size: usize) -> io::Result<Vec<u8>> {
+    // 头部中的大小不可信，预分配设上限
+    let mut out = Vec::with_capacity(size.min(1 << 20));
+    ZlibDecoder::new(reader).take(size as u64 + 1).read_to_end(&mut out)?;
+    if out.len() != size {
+        return Err(invalid(format!("pack entry size mismatch: expected {}, got {}", size, out.len())));
+    }
+    Ok(out)
+}
+
+/// 应用 delta 指令，由基础对象生成目标对象
+///
+/// delta 格式：源大小（变长）+ 目标大小（变长）+ 指令序列
+/// - 最高位为 1：copy 指令，后续可选字节给出 offset（4 字节）和 size（3 字节）
+/// - 最高位为 0：insert 指令，低 7 位为紧随其后的字面数据长度
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut pos = 0;
+    let src_size = read_delta_size(delta, &mut pos)?;
+    let dst_size = read_delta_size(delta, &mut pos)?;
+    if src_size != base.len() {
+        return Err(invalid("delta base size mismatch".to_string()));
+    }
+
+    let mut out = Vec::with_capacity(dst_size.min(1 << 20));
+    while pos < delta.len() {
+        let cmd = delta[pos];
+        pos += 1;
+        if cmd & 0x80 != 0 {
+            let mut offset = 0usize;
+            let mut size = 0usize;
+            for i in 0..4 {
+                if cmd & (1 << i) != 0 {
+                    offset |= (*delta.get(pos).ok_or_else(truncated)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if cmd & (0x10 << i) != 0 {
+                    size |= (*delta.get(pos).ok_or_else(truncated)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            let chunk = base.get(offset..offset + size).ok_or_else(truncated)?;
+            out.extend_from_slice(chunk);
+        } else if cmd != 0 {
+            let len = cmd as usize;
+            let chunk = delta.get(pos..pos + len).ok_or_else(truncated)?;
+            out.extend_from_slice(chunk);
+            pos += len;
+        } else {
+            return Err(invalid("delta opcode 0 is reserved".to_string()));
+        }
+    }
+
+    if out.len() != dst_size {
+        return Err(invalid("delta result size mismatch".to_string()));
+    }
+    Ok(out)
+}
+
+/// 变长整数中一个字节的低 7 位左移 `shift` 位，有效位被移出 usize 范围时返回 None
+fn shift_varint_bits(byte: u8, shift: u32) -> Option<usize> {
+    let bits = (byte & 0x7f) as usize;
+    bits.checked_shl(shift).filter(|part| part >> shift == bits)
+}
+
+/// 读取 delta 头部中的小端变长整数
+fn read_delta_size(delta: &[u8], pos: &mut usize) -> io::Result<usize> {
+    let mut size = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *delta.get(*pos).ok_or_else(truncated)?;
+        *pos += 1;
+        size |= shift_varint_bits(byte, shift).ok_or_else(|| invalid("delta size overflows".to_string()))?;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+// =====================================
+// ---------- delta 基础对象缓存 ----------
+// =====================================
+
+/// 按插入顺序淘汰的简单缓存，总大小不超过 DELTA_BASE_CACHE_LIMIT
+#[derive(Default)]
+struct DeltaBaseCache {
+    entries: HashMap<u64, (u8, Rc<Vec<u8>>)>,
+    order: VecDeque<u64>,
+    total: usize,
+}
+
+impl DeltaBaseCache {
+    fn get(&mut self, offset: u64) -> Option<(u8, Rc<Vec<u8>>)> {
+        self.entries.get(&offset).cloned()
+    }
+
+    fn insert(&mut self, offset: u64, type_id: u8, data: Rc<Vec<u8>>) {
+        if data.len() > DELTA_BASE_CACHE_LIMIT || self.entries.contains_key(&offset) {
+            return;
+        }
+        self.total += data.len();
+        self.entries.insert(offset, (type_id, data));
+        self.order.push_back(offset);
+        while self.total > DELTA_BASE_CACHE_LIMIT {
+            let Some(old) = self.order.pop_front() else { break };
+            if let Some((_, d)) = self.entries.remove(&old) {
+                self.total -= d.len();
+            }
+        }
+    }
+}
+
+// =====================================
+// ---------- 仓库内所有 pack ----------
+// =====================================
+
+/// 扫描时 pack 目录的修改时间，以及当时打开的 pack 列表
+type ScannedPacks = (Option<SystemTime>, Rc<Vec<PackFile>>);
+
+thread_local! {
+    /// 每个 objects/pack 目录对应已打开的 pack 列表
+    static PACKS: RefCell<HashMap<PathBuf, ScannedPacks>> = RefCell::new(HashMap::new());
+}
+
+/// pack 目录路径：`.git/objects/pack`
+pub fn pack_dir(repo_path: &str) -> PathBuf {
+    Path::new(repo_path).join(".git").join("objects").join("pack")
+}
+
+/// 获取仓库的所有 pack（已打开的会被复用）
+pub fn packs(repo_path: &str) -> Rc<Vec<PackFile>> {
+    packs_in(&pack_dir(repo_path), HashAlgorithm::for_repo(repo_path))
+}
+
+/// 获取任意 pack 目录（例如 alternates 中的对象目录）下的所有 pack
+pub fn packs_in(dir: &Path, algo: HashAlgorithm) -> Rc<Vec<PackFile>> {
+    if let Some((_, cached)) = PACKS.with(|p| p.borrow().get(dir).cloned()) {
+        return cached;
+    }
+    rescan_dir(dir, algo)
+}
+
+/// 重新扫描 pack 目录（新 pack 写入后调用），同时丢弃已加载的 multi-pack-index
+pub fn rescan_packs(repo_path: &str) -> Rc<Vec<PackFile>> {
+    MultiPackIndex::invalidate(repo_path);
+    rescan_dir(&pack_dir(repo_path), HashAlgorithm::for_repo(repo_path))
+}
+
+/// pack 目录自上次扫描后有变化（有 pack 被写入或删除）时才重新扫描，否则返回 None
+///
+/// 与 Git 的 `reprepare_packed_git` 相同，以目录的修改时间判断，避免每次查找失败都重新打开所有 pack。
+fn reprepare_packs(repo_path: &str) -> Option<Rc<Vec<PackFile>>> {
+    let dir = pack_dir(repo_path);
+    let scanned = PACKS.with(|p| p.borrow().get(&dir).map(|(mtime, _)| *mtime));
+    if scanned.is_some_and(|mtime| mtime == dir_mtime(&dir)) {
+        return None;
+    }
+    Some(rescan_packs(repo_path))
+}
+
+fn dir_mtime(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+fn rescan_dir(dir: &Path, algo: HashAlgorithm) -> Rc<Vec<PackFile>> {
+    // 先记录修改时间再读取目录：扫描期间写入的 pack 会在下一次查找失败时被发现
+    let mtime = dir_mtime(dir);
+    let mut idx_paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|rd| rd.filter_map(Result::ok).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    idx_paths.retain(|p| p.extension().is_some_and(|e| e == "idx"));
+    idx_paths.sort();
+
+    let opened: Rc<Vec<PackFile>> = Rc::new(idx_paths.iter().filter_map(|p| PackFile::open(p, algo)).collect());
+    PACKS.with(|p| p.borrow_mut().insert(dir.to_path_buf(), (mtime, opened.clone())));
+    opened
+}
+
+/// 需要逐个探测的 pack：有 multi-pack-index 时只剩它没有覆盖的 pack（例如之后新写入的）
+///
+/// multi-pack-index 覆盖的 pack 被删除后，其中的对象只可能在未覆盖的新 pack 中，
+/// 因此过期的 multi-pack-index 也不会漏掉对象。
+fn uncovered<'a>(packs: &'a Rc<Vec<PackFile>>, midx: Option<&'a MultiPackIndex>) -> impl Iterator<Item = &'a PackFile> {
+    packs
+        .iter()
+        .enumerate()
+        .filter(move |&(i, _)| !midx.is_some_and(|m| m.covers(packs, i)))
+        .map(|(_, p)| p)
+}
+
+/// 在所有 pack 中定位对象并用 `read` 读取（有 multi-pack-index 时先查它）
+fn find_packed<T>(repo_path: &str, sha: &str, read: impl Fn(&PackFile, u64) -> Option<T>) -> Option<T> {
+    let sha_bytes = hex::decode(sha).ok()?;
+    let find = |packs: &Rc<Vec<PackFile>>| {
+        let midx = MultiPackIndex::for_repo(repo_path);
+        let Some((i, offset)) = midx.as_ref().and_then(|m| m.locate(packs, &sha_bytes)) else {
+            return uncovered(packs, midx.as_deref()).find_map(|p| read(p, p.index.find_offset(&sha_bytes)?));
+        };
+        // multi-pack-index 选中的 pack 读取失败（例如条目损坏）时，对象可能还在其他 pack 中
+        read(&packs[i], offset).or_else(|| {
+            packs
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .find_map(|(_, p)| read(p, p.index.find_offset(&sha_bytes)?))
+        })
+    };
+
+    find(&packs(repo_path)).or_else(|| {
+        // 未找到时检查是否有其他进程新写入的 pack
+        find(&reprepare_packs(repo_path)?)
+    })
+}
+
+/// 在所有 pack 中查找并读取对象
+pub fn read_packed_object(repo_path: &str, sha: &str) -> Option<Object> {
+    find_packed(repo_path, sha, |pack, offset| {
+        let (type_id, data) = pack.read_at(repo_path, offset).ok()?;
+        Object::from_type(type_name(type_id)?, data.as_ref().clone())
+    })
+}
+
+/// 在所有 pack 中查找非 delta 对象，返回（类型, 大小, 解压读取器），内容按需解压
+///
+/// 对象是 delta 或不在 pack 中时返回 None。
+pub fn stream_packed_object(repo_path: &str, sha: &str) -> Option<(&'static str, u64, EntryReader)> {
+    find_packed(repo_path, sha, |pack, offset| {
+        let (type_id, size, reader) = pack.stream_at(offset).ok()??;
+        Some((type_name(type_id)?, size, reader))
+    })
+}
+
+/// 在所有 pack 中查找对象的类型和大小（不还原 delta）
+pub fn read_packed_header(repo_path: &str, sha: &str) -> Option<(&'static str, u64)> {
+    find_packed(repo_path, sha, |pack, offset| {
+        let (type_id, size) = pack.read_header_at(repo_path, offset).ok()?;
+        Some((type_name(type_id)?, size))
+    })
+}
+
+/// 在所有 pack 中查找以给定前缀开头的对象哈希
+pub fn find_packed_prefix(repo_path: &str, prefix: &str) -> Vec<String> {
+    let packs = packs(repo_path);
+    let midx = MultiPackIndex::for_repo(repo_path);
+    let mut found = Vec::new();
+    if let Some(midx) = &midx {
+        for i in midx.find_prefix(prefix) {
+            if midx.locate(&packs, midx.sha_at(i)).is_some() {
+                found.push(hex::encode(midx.sha_at(i)));
+            }
+        }
+    }
+    for p in uncovered(&packs, midx.as_deref()) {
+        for i in p.index.find_prefix(prefix) {
+            found.push(hex::encode(p.index.sha_at(i)));
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// 对象是否存在于某个 pack 中
+pub fn has_packed_object(repo_path: &str, sha: &str) -> bool {
+    let Ok(sha_bytes) = hex::decode(sha) else { return false };
+    let packs = packs(repo_path);
+    let midx = MultiPackIndex::for_repo(repo_path);
+    if midx.as_ref().is_some_and(|m| m.locate(&packs, &sha_bytes).is_some()) {
+        return true;
+    }
+    uncovered(&packs, midx.as_deref()).any(|p| p.contains(&sha_bytes))
+}
+
+// =====================================
+// ---------- 工具函数 ----------
+// =====================================
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], pos: usize) -> u64 {
+    u64::from_be_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut b = [0u8; 1];
+    reader.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn truncated() -> io::Error {
+    invalid("truncated delta".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// 由 `git repack -adf` 生成的 pack：3 个提交，其中一个 blob 经过两层 OFS_DELTA
+    const FIXTURE_IDX: &str = "tests/fixtures/pack/pack-ec8a003d24bea4e9c52612ad45a951deddae618a.idx";
+
+    fn fixture() -> PackFile {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_IDX);
+        PackFile::open(&path, HashAlgorithm::Sha1).expect("fixture pack")
+    }
+
+    #[test]
+    fn reads_every_object_of_git_pack() {
+        let pack = fixture();
+        assert!(pack.verify_checksum());
+        assert_eq!(pack.index.len(), 15);
+        for i in 0..pack.index.len() {
+            let sha = pack.index.sha_at(i).to_vec();
+            let obj = pack.read("", &sha).expect("object");
+            assert_eq!(obj.try_hash(HashAlgorithm::Sha1).unwrap(), hex::encode(&sha));
+        }
+        // 深度为 2 的 delta 链末端
+        let sha = hex::decode("fcd87345e00673ff10adeb5c83e620d50bb0d62a").unwrap();
+        let (kind, _) = pack.read_at("", pack.index.find_offset(&sha).unwrap()).unwrap();
+        assert_eq!(kind, OBJ_BLOB);
+    }
+
+    #[test]
+    fn reads_headers_without_resolving_deltas() {
+        // 每次新打开 pack，保证 delta 基础对象缓存为空，类型和大小只能来自条目头部
+        for i in 0..fixture().index.len() {
+            let pack = fixture();
+            let sha = pack.index.sha_at(i).to_vec();
+            let header = pack.read_header("", &sha).expect("header");
+            let obj = fixture().read("", &sha).unwrap();
+            assert_eq!(header, (obj.type_name(), obj.data().len() as u64));
+        }
+    }
+
+    #[test]
+    fn streams_only_non_delta_entries() {
+        let pack = fixture();
+        let offset = |sha: &str| pack.index.find_offset(&hex::decode(sha).unwrap()).unwrap();
+
+        let (kind, size, reader) = pack.stream_at(offset("d1b5027b62afcfdbf227cde925f01c46528631ec")).unwrap().unwrap();
+        let mut data = Vec::new();
+        reader.take(size).read_to_end(&mut data).unwrap();
+        assert_eq!((kind, size), (OBJ_BLOB, 176));
+        assert_eq!(Object::Blob(data).try_hash(HashAlgorithm::Sha1).unwrap(), "d1b5027b62afcfdbf227cde925f01c46528631ec");
+
+        assert!(pack.stream_at(offset("fcd87345e00673ff10adeb5c83e620d50bb0d62a")).unwrap().is_none());
+    }
+
+    #[test]
+    fn rescans_pack_directory_only_after_it_changes() {
+        let repo = crate::utils::fs::TempRepo::new("pack-reprepare");
+        let dir = pack_dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        let sha = "fcd87345e00673ff10adeb5c83e620d50bb0d62a";
+
+        // 目录未变化：查找失败不会重新打开 pack
+        let before = packs(repo.path());
+        assert!(read_packed_object(repo.path(), sha).is_none());
+        assert!(Rc::ptr_eq(&before, &packs(repo.path())));
+
+        // 其他进程写入新 pack 后，下一次查找失败时会发现它
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_IDX);
+        for ext in ["pack", "idx"] {
+            let from = fixture.with_extension(ext);
+            std::fs::copy(&from, dir.join(from.file_name().unwrap())).unwrap();
+        }
+        assert!(read_packed_object(repo.path(), sha).is_some());
+        assert_eq!(packs(repo.path()).len(), 1);
+    }
+
+    #[test]
+    fn finds_objects_by_prefix() {
+        let pack = fixture();
+        let hits = pack.index.find_prefix("d1b5");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hex::encode(pack.index.sha_at(hits[0])), "d1b5027b62afcfdbf227cde925f01c46528631ec");
+        assert!(pack.index.find_prefix("0000").is_empty());
+    }
+
+    #[test]
+    fn offset_at_rejects_out_of_range_indices() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_IDX);
+        let mut data = std::fs::read(path).unwrap();
+        let count = 15;
+        let off_start = 8 + 256 * 4 + count * 24;
+        // 第一个偏移指向大偏移表的第 8 项，而表中只有 1 项
+        data[off_start..off_start + 4].copy_from_slice(&0x8000_0007u32.to_be_bytes());
+        let trailer = data.len() - 40;
+        data.splice(trailer..trailer, 0x1234u64.to_be_bytes());
+
+        let index = PackIndex::parse(&data, HashAlgorithm::Sha1).expect("idx");
+        assert_eq!(index.offset_at(0), None);
+        assert_eq!(index.offset_at(count), None);
+        assert!(index.offset_at(1).is_some());
+    }
+
+    #[test]
+    fn rejects_non_monotonic_fanout() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_IDX);
+        let mut data = std::fs::read(path).unwrap();
+        data[8..12].copy_from_slice(&100u32.to_be_bytes());
+        assert!(PackIndex::parse(&data, HashAlgorithm::Sha1).is_none());
+    }
+
+    #[test]
+    fn rejects_ofs_delta_pointing_at_itself() {
+        // 类型 6、大小 0，相对偏移 0
+        let mut pack = vec![0u8; 12];
+        pack.extend([0x60, 0x00]);
+        let err = read_entry_header(&mut Cursor::new(pack), 12, HashAlgorithm::Sha1).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_ofs_delta_before_pack_start() {
+        let mut pack = vec![0u8; 12];
+        pack.extend([0x60, 0xff, 0xff, 0x7f]);
+        assert!(read_entry_header(&mut Cursor::new(pack), 12, HashAlgorithm::Sha1).is_err());
+    }
+
+    #[test]
+    fn rejects_overlong_size_varint() {
+        let mut pack = vec![0xb0];
+        pack.extend([0xff; 12]);
+        pack.push(0x7f);
+        assert!(read_entry_header(&mut Cursor::new(pack), 0, HashAlgorithm::Sha1).is_err());
+
+        let mut delta = vec![0xff; 12];
+        delta.push(0x01);
+        assert!(apply_delta(b"", &delta).is_err());
+    }
+
+    #[test]
+    fn applies_copy_and_insert_instructions() {
+        let base = b"hello, world";
+        // 源 12 字节、目标 11 字节：copy(0, 5) + insert("!!!") + copy(7, 3)
+        let delta = [12, 11, 0x90, 5, 3, b'!', b'!', b'!', 0x91, 7, 3];
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"hello!!!wor");
+    }
+
+    #[test]
+    fn rejects_malformed_deltas() {
+        // 源大小不符
+        assert!(apply_delta(b"abc", &[4, 1, 1, b'x']).is_err());
+        // 保留的 0 指令
+        assert!(apply_delta(b"abc", &[3, 1, 0]).is_err());
+        // copy 越过基础对象末尾
+        assert!(apply_delta(b"abc", &[3, 4, 0x90, 4]).is_err());
+        // 结果大小不符
+        assert!(apply_delta(b"abc", &[3, 2, 1, b'x']).is_err());
+    }
+}
@@ -0,0 +1,584 @@
+use crate::core::config::Config;
+use crate::core::odb::ObjectDatabase;
+use crate::core::pack::{self, OBJ_OFS_DELTA};
+use crate::utils::fs::{persist_temp_file, temp_path, write_file_atomic};
+use crate::utils::zlib;
+use crate::utils::hash::Hasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// delta 中单条 copy 指令的最大长度（与 Git 的 diff-delta 保持一致）
+const MAX_COPY_SIZE: usize = 0x10000;
+/// 建立基础对象索引时的块大小
+const BLOCK_SIZE: usize = 16;
+/// 小于该大小的对象不尝试 delta
+const MIN_DELTA_TARGET: usize = 50;
+/// 流式写入大对象时每次处理的字节数
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 待打包的对象（只记录元数据，内容在写入时才从对象库读取）
+pub struct PackObject {
+    pub sha: String,
+    pub kind: &'static str,
+    /// 内容长度（不含 header）
+    pub size: u64,
+    /// 路径名哈希，用于让同名文件的不同版本在排序后相邻
+    pub name_hash: u32,
+}
+
+impl PackObject {
+    pub fn new(sha: String, kind: &'static str, size: u64, path: Option<&str>) -> Self {
+        PackObject {
+            sha,
+            kind,
+            size,
+            name_hash: path.map(name_hash).unwrap_or(0),
+        }
+    }
+}
+
+/// 对象选中的 delta：(基础对象下标, delta 数据)
+type DeltaChoice = Option<(usize, Vec<u8>)>;
+
+/// pack 写入参数
+pub struct PackOptions {
+    /// 滑动窗口大小：每个对象最多尝试多少个候选基础对象
+    pub window: usize,
+    /// delta 链的最大深度
+    pub depth: usize,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        PackOptions { window: 10, depth: 50 }
+    }
+}
+
+/// 写入结果
+pub struct PackResult {
+    /// pack 校验和（十六进制），同时也是文件名的一部分
+    pub checksum: String,
+    pub pack_path: PathBuf,
+    pub idx_path: PathBuf,
+    pub object_count: usize,
+    pub delta_count: usize,
+}
+
+/// Git 的路径名哈希（pack_name_hash）：越靠后的字符权重越大，
+/// 使得扩展名和文件名相同的路径得到相近的值
+pub fn name_hash(path: &str) -> u32 {
+    let mut hash: u32 = 0;
+    for c in path.bytes() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        hash = (hash >> 2).wrapping_add((c as u32) << 24);
+    }
+    hash
+}
+
+// =====================================
+// ---------- delta 生成 ----------
+// =====================================
+
+/// 生成把 `base` 变换为 `target` 的 delta（格式与 pack::apply_delta 对应）
+///
+/// 以 16 字节块为单位索引基础对象，扫描目标对象寻找最长匹配，
+/// 匹配部分输出 copy 指令，其余字节输出 insert 指令。
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_delta_size(&mut out, base.len());
+    write_delta_size(&mut out, target.len());
+
+    // 1️⃣ 索引基础对象的每个块
+    let mut blocks: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    let mut i = 0;
+    while i + BLOCK_SIZE <= base.len() {
+        blocks.entry(&base[i..i + BLOCK_SIZE]).or_default().push(i);
+        i += BLOCK_SIZE;
+    }
+
+    // 2️⃣ 扫描目标对象
+    let mut insert_start = 0;
+    let mut pos = 0;
+    while pos + BLOCK_SIZE <= target.len() {
+        let mut best: Option<(usize, usize)> = None; // (base 偏移, 长度)
+        if let Some(candidates) = blocks.get(&target[pos..pos + BLOCK_SIZE]) {
+            for &start in candidates.iter().rev().take(64) {
+                let len = base[start..]
+                    .iter()
+                    .zip(&target[pos..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if best.is_none_or(|(_, l)| len > l) {
+                    best = Some((start, len));
+                }
+            }
+        }
+
+        match best {
+            Some((mut src, mut len)) if len >= BLOCK_SIZE => {
+                // 向前扩展匹配，吃掉尚未输出的 insert 字节
+                while pos > insert_start && src > 0 && base[src - 1] == target[pos - 1] {
+                    src -= 1;
+                    pos -= 1;
+                    len += 1;
+                }
+                emit_insert(&mut out, &target[insert_start..pos]);
+                pos += len;
+                insert_start = pos;
+                while len > 0 {
+                    let chunk = len.min(MAX_COPY_SIZE);
+                    emit_copy(&mut out, src, chunk);
+                    src += chunk;
+                    len -= chunk;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    emit_insert(&mut out, &target[insert_start..]);
+    out
+}
+
+fn write_delta_size(out: &mut Vec<u8>, mut size: usize) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn emit_insert(out: &mut Vec<u8>, data: &[u8]) {
+    for chunk in data.chunks(0x7f) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn emit_copy(out: &mut Vec<u8>, offset: usize, size: usize) {
+    let cmd_pos = out.len();
+    let mut cmd = 0x80u8;
+    out.push(0);
+    for i in 0..4 {
+        let byte = (offset >> (8 * i)) as u8;
+        if byte != 0 {
+            cmd |= 1 << i;
+            out.push(byte);
+        }
+    }
+    // size 为 0x10000 时按约定省略全部 size 字节
+    let size = if size == MAX_COPY_SIZE { 0 } else { size };
+    for i in 0..3 {
+        let byte = (size >> (8 * i)) as u8;
+        if byte != 0 {
+            cmd |= 0x10 << i;
+            out.push(byte);
+        }
+    }
+    out[cmd_pos] = cmd;
+}
+
+// =====================================
+// ---------- delta 基础对象选择 ----------
+// =====================================
+
+/// 在滑动窗口（按写入顺序排在前面、内容已读入的对象）中为目标对象选择 delta 基础对象，
+/// 保留最小的 delta
+fn choose_delta(
+    objects: &[PackObject],
+    window: &VecDeque<(usize, Rc<Vec<u8>>)>,
+    depth: &[usize],
+    target_idx: usize,
+    target: &[u8],
+    opts: &PackOptions,
+) -> DeltaChoice {
+    if target.len() < MIN_DELTA_TARGET {
+        return None;
+    }
+    let mut best: DeltaChoice = None;
+    for (base_idx, base) in window.iter().rev() {
+        if objects[*base_idx].kind != objects[target_idx].kind || depth[*base_idx] >= opts.depth {
+            continue;
+        }
+        // 大小相差过大的对象几乎不可能得到有效 delta
+        if base.len() < target.len() / 32 {
+            continue;
+        }
+
+        let limit = match &best {
+            Some((_, d)) => d.len(),
+            None => (target.len() / 2).saturating_sub(20),
+        };
+        let delta = create_delta(base, target);
+        if delta.len() < limit {
+            best = Some((*base_idx, delta));
+        }
+    }
+    best
+}
+
+// =====================================
+// ---------- .pack / .idx 写入 ----------
+// =====================================
+
+/// 写入 pack 的同时计算整体校验和、当前条目的 CRC32 以及已写入的字节数
+struct PackSink<W: Write> {
+    inner: W,
+    hasher: Hasher,
+    crc: crc32fast::Hasher,
+    written: u64,
+}
+
+impl<W: Write> Write for PackSink<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.crc.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 把对象写入新的 pack 文件，并生成对应的 .idx v2
+///
+/// 对象按 类型 → 路径哈希 → 大小（降序）排序后依次写出，每个对象在前面
+/// `opts.window` 个对象中寻找 delta 基础对象；较大的（通常是较新的）版本因此成为基础对象。
+/// 内存中只保留窗口内对象的内容；超过 `core.bigFileThreshold` 的对象不做 delta，
+/// 直接从对象库流式压缩写入（同时校验其哈希）。
+///
+/// 文件先写入临时文件，完成后再重命名为 `pack-<checksum>.pack/.idx`。
+pub fn write_pack(
+    repo_path: &str,
+    db: &dyn ObjectDatabase,
+    objects: &[PackObject],
+    opts: &PackOptions,
+) -> io::Result<PackResult> {
+    let dir = pack::pack_dir(repo_path);
+    std::fs::create_dir_all(&dir)?;
+    let config = Config::load(repo_path);
+    let big_file_threshold = config.big_file_threshold();
+
+    let mut order: Vec<usize> = (0..objects.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (x, y) = (&objects[a], &objects[b]);
+        (pack::type_id(x.kind), x.name_hash, std::cmp::Reverse(x.size), &x.sha)
+            .cmp(&(pack::type_id(y.kind), y.name_hash, std::cmp::Reverse(y.size), &y.sha))
+    });
+
+    // 1️⃣ 写 .pack：header + 条目 + 校验和 trailer（与对象使用相同的哈希算法）
+    let tmp_pack = temp_path(&dir.join("incoming.pack"));
+    let algo = db.hash_algorithm();
+    let written = (|| {
+        let mut sink = PackSink {
+            inner: BufWriter::new(File::create(&tmp_pack)?),
+            hasher: algo.hasher(),
+            crc: crc32fast::Hasher::new(),
+            written: 0,
+        };
+        sink.write_all(b"PACK")?;
+        sink.write_all(&2u32.to_be_bytes())?;
+        sink.write_all(&(objects.len() as u32).to_be_bytes())?;
+
+        let mut offsets = vec![0u64; objects.len()];
+        let mut crcs = vec![0u32; objects.len()];
+        let mut depth = vec![0usize; objects.len()];
+        let mut window: VecDeque<(usize, Rc<Vec<u8>>)> = VecDeque::new();
+        let mut delta_count = 0;
+        for &i in &order {
+            let obj = &objects[i];
+            offsets[i] = sink.written;
+            sink.crc = crc32fast::Hasher::new();
+
+            if obj.size > big_file_threshold {
+                write_streamed(&mut sink, db, obj)?;
+            } else {
+                let data = match db.read(&obj.sha) {
+                    Some(o) if o.type_name() == obj.kind => Rc::new(o.into_data()),
+                    _ => return Err(io::Error::new(io::ErrorKind::NotFound, format!("cannot read object {}", obj.sha))),
+                };
+                let mut entry = Vec::new();
+                match choose_delta(objects, &window, &depth, i, &data, opts) {
+                    Some((base_idx, delta)) => {
+                        encode_entry_header(&mut entry, OBJ_OFS_DELTA, delta.len());
+                        encode_ofs(&mut entry, offsets[i] - offsets[base_idx]);
+                        entry.extend(zlib::compress(&delta));
+                        depth[i] = depth[base_idx] + 1;
+                        delta_count += 1;
+                    }
+                    None => {
+                        encode_entry_header(&mut entry, pack::type_id(obj.kind).unwrap(), data.len());
+                        entry.extend(zlib::compress(&data));
+                    }
+                }
+                sink.write_all(&entry)?;
+                window.push_back((i, data));
+                if window.len() > opts.window {
+                    window.pop_front();
+                }
+            }
+            crcs[i] = std::mem::take(&mut sink.crc).finalize();
+        }
+
+        let checksum = sink.hasher.finalize();
+        sink.inner.write_all(&checksum)?;
+        let file = sink.inner.into_inner().map_err(|e| e.into_error())?;
+        Ok((file, checksum, offsets, crcs, delta_count))
+    })();
+    let (pack_file, checksum, offsets, crcs, delta_count) = match written {
+        Ok(written) => written,
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_pack);
+            return Err(e);
+        }
+    };
+
+    // 2️⃣ 写 .idx v2
+    let mut sorted: Vec<usize> = (0..objects.len()).collect();
+    sorted.sort_by(|&a, &b| objects[a].sha.cmp(&objects[b].sha));
+    let shas: Vec<Vec<u8>> = sorted.iter().map(|&i| hex::decode(&objects[i].sha).unwrap()).collect();
+
+    let mut idx = vec![0xff, b't', b'O', b'c'];
+    idx.extend(2u32.to_be_bytes());
+    let mut fanout = [0u32; 256];
+    for sha in &shas {
+        fanout[sha[0] as usize] += 1;
+    }
+    let mut total = 0;
+    for count in fanout.iter_mut() {
+        total += *count;
+        *count = total;
+        idx.extend(total.to_be_bytes());
+    }
+    for sha in &shas {
+        idx.extend(sha);
+    }
+    for &i in &sorted {
+        idx.extend(crcs[i].to_be_bytes());
+    }
+    let mut large = Vec::new();
+    for &i in &sorted {
+        if offsets[i] >= 0x8000_0000 {
+            idx.extend((0x8000_0000 | large.len() as u32).to_be_bytes());
+            large.push(offsets[i]);
+        } else {
+            idx.extend((offsets[i] as u32).to_be_bytes());
+        }
+    }
+    for off in large {
+        idx.extend(off.to_be_bytes());
+    }
+    idx.extend(&checksum);
+    let idx_checksum = algo.digest(&idx);
+    idx.extend(idx_checksum);
+
+    // 3️⃣ 重命名为正式文件名（先 .pack 后 .idx，读取方以 .idx 为准）
+    let name = hex::encode(&checksum);
+    let pack_path = dir.join(format!("pack-{}.pack", name));
+    let idx_path = dir.join(format!("pack-{}.idx", name));
+    let fsync = config.fsync("pack");
+    persist_temp_file(pack_file, &tmp_pack, &pack_path, fsync)?;
+    write_file_atomic(&idx_path, &idx, fsync)?;
+
+    Ok(PackResult {
+        checksum: name,
+        pack_path,
+        idx_path,
+        object_count: objects.len(),
+        delta_count,
+    })
+}
+
+/// 大对象：边从对象库读取边压缩写入，不做 delta，读取的内容同时计算哈希并与对象名比对
+fn write_streamed<W: Write>(sink: &mut PackSink<W>, db: &dyn ObjectDatabase, obj: &PackObject) -> io::Result<()> {
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, format!("cannot read object {}", obj.sha));
+    let mut reader = db.read_stream(&obj.sha).ok_or_else(not_found)?;
+    if reader.kind != obj.kind || reader.size != obj.size {
+        return Err(not_found());
+    }
+
+    let mut header = Vec::new();
+    encode_entry_header(&mut header, pack::type_id(obj.kind).unwrap(), obj.size as usize);
+    sink.write_all(&header)?;
+
+    let mut hasher = db.hash_algorithm().hasher();
+    hasher.update(format!("{} {}\0", obj.kind, obj.size).as_bytes());
+    let mut encoder = zlib::encoder(&mut *sink);
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut remaining = obj.size;
+    while remaining > 0 {
+        let chunk = remaining.min(STREAM_CHUNK_SIZE as u64) as usize;
+        let n = reader.read(&mut buf[..chunk])?;
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("object {} is truncated", obj.sha)));
+        }
+        hasher.update(&buf[..n]);
+        encoder.write_all(&buf[..n])?;
+        remaining -= n as u64;
+    }
+    encoder.finish()?;
+
+    let actual = hasher.finalize_checked().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    if hex::encode(actual) != obj.sha {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("object {} is corrupt", obj.sha)));
+    }
+    Ok(())
+}
+
+/// 条目头部：3 位类型 + 变长大小
+fn encode_entry_header(out: &mut Vec<u8>, type_id: u8, mut size: usize) {
+    let mut byte = (type_id << 4) | (size & 0x0f) as u8;
+    size >>= 4;
+    while size != 0 {
+        out.push(byte | 0x80);
+        byte = (size & 0x7f) as u8;
+        size >>= 7;
+    }
+    out.push(byte);
+}
+
+/// OFS_DELTA 的相对偏移（大端，每个后续字节隐含 +1）
+fn encode_ofs(out: &mut Vec<u8>, mut rel: u64) {
+    let mut buf = vec![(rel & 0x7f) as u8];
+    rel >>= 7;
+    while rel != 0 {
+        rel -= 1;
+        buf.push(0x80 | (rel & 0x7f) as u8);
+        rel >>= 7;
+    }
+    buf.reverse();
+    out.extend(buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::object::Object;
+    use crate::core::odb::MemoryObjectDatabase;
+    use crate::core::pack::{apply_delta, PackFile};
+    use crate::utils::fs::TempRepo;
+    use crate::utils::hash::HashAlgorithm;
+
+    fn lines(range: std::ops::Range<u32>) -> Vec<u8> {
+        range.map(|i| format!("line {}\n", i)).collect::<String>().into_bytes()
+    }
+
+    #[test]
+    fn delta_round_trips() {
+        let base = lines(0..200);
+        let mut target = lines(0..80);
+        target.extend(b"inserted\n");
+        target.extend(lines(90..220));
+
+        let delta = create_delta(&base, &target);
+        assert!(delta.len() < target.len() / 4);
+        assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+
+        // 完全不同的内容只能输出 insert 指令，仍然可以还原
+        let other = vec![0xaau8; 300];
+        assert_eq!(apply_delta(&base, &create_delta(&base, &other)).unwrap(), other);
+        assert_eq!(apply_delta(&base, &create_delta(&base, b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn copies_longer_than_max_copy_size_are_split() {
+        let base: Vec<u8> = (0..3 * MAX_COPY_SIZE as u32).map(|i| (i * 7 % 251) as u8).collect();
+        let delta = create_delta(&base, &base);
+        assert_eq!(apply_delta(&base, &delta).unwrap(), base);
+    }
+
+    #[test]
+    fn entry_header_and_ofs_encoding_match_reader() {
+        let mut pack = vec![0u8; 1000];
+        let start = pack.len() as u64;
+        encode_entry_header(&mut pack, OBJ_OFS_DELTA, 123_456);
+        encode_ofs(&mut pack, 900);
+        let header = pack::read_entry_header(&mut io::Cursor::new(pack), start, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(header.type_id, OBJ_OFS_DELTA);
+        assert_eq!(header.size, 123_456);
+        assert_eq!(header.base_offset, Some(start - 900));
+    }
+
+    /// 把对象写入内存对象库，返回对应的 PackObject
+    fn add(db: &MemoryObjectDatabase, obj: Object, path: &str) -> PackObject {
+        let (kind, size) = (obj.type_name(), obj.data().len() as u64);
+        let sha = db.write(&obj).unwrap();
+        PackObject::new(sha, kind, size, Some(path))
+    }
+
+    #[test]
+    fn written_pack_reads_back() {
+        let repo = TempRepo::new("pack-writer");
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let mut objects = Vec::new();
+        for version in 0..5 {
+            let mut content = lines(0..100);
+            content.extend(format!("version {}\n", version).into_bytes());
+            objects.push(add(&db, Object::Blob(content), "file.txt"));
+        }
+        objects.push(add(&db, Object::Blob(b"small\n".to_vec()), "small.txt"));
+
+        let result = write_pack(repo.path(), &db, &objects, &PackOptions::default()).unwrap();
+        assert_eq!(result.object_count, 6);
+        assert_eq!(result.delta_count, 4);
+        assert!(result.pack_path.ends_with(format!("pack-{}.pack", result.checksum)));
+
+        let pack = PackFile::open(&result.idx_path, HashAlgorithm::Sha1).unwrap();
+        assert!(pack.verify_checksum());
+        assert_eq!(pack.index.len(), 6);
+        for obj in &objects {
+            let sha = hex::decode(&obj.sha).unwrap();
+            let read = pack.read(repo.path(), &sha).unwrap();
+            assert_eq!(read.type_name(), obj.kind);
+            assert_eq!(read, db.read(&obj.sha).unwrap());
+        }
+    }
+
+    #[test]
+    fn objects_above_big_file_threshold_are_streamed_without_delta() {
+        let repo = TempRepo::new("pack-writer-big");
+        std::fs::write(format!("{}/.git/config", repo.path()), "[core]\n\tbigFileThreshold = 1k\n").unwrap();
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let big = lines(0..500);
+        let mut big_changed = big.clone();
+        big_changed.extend(b"one more line\n");
+        let objects = vec![
+            add(&db, Object::Blob(big), "big.bin"),
+            add(&db, Object::Blob(big_changed), "big.bin"),
+            add(&db, Object::Blob(lines(0..20)), "small.txt"),
+            add(&db, Object::Blob(lines(0..21)), "small.txt"),
+        ];
+
+        let result = write_pack(repo.path(), &db, &objects, &PackOptions::default()).unwrap();
+        // 只有两个小版本之间做了 delta
+        assert_eq!(result.delta_count, 1);
+
+        let pack = PackFile::open(&result.idx_path, HashAlgorithm::Sha1).unwrap();
+        assert!(pack.verify_checksum());
+        for obj in &objects {
+            let read = pack.read(repo.path(), &hex::decode(&obj.sha).unwrap()).unwrap();
+            assert_eq!(read, db.read(&obj.sha).unwrap());
+        }
+    }
+
+    #[test]
+    fn missing_object_aborts_without_leaving_files() {
+        let repo = TempRepo::new("pack-writer-missing");
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let objects = vec![PackObject::new("ab".repeat(20), "blob", 10, None)];
+        assert!(write_pack(repo.path(), &db, &objects, &PackOptions::default()).is_err());
+        let dir = pack::pack_dir(repo.path());
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 0);
+    }
+}
@@ -1,38 +1,278 @@
-use crate::utils::fs::{create_dir, write_file, read_file, check_path_exists};
+use crate::core::object::Object;
+use crate::core::odb::LooseObjectDatabase;
+use crate::core::tag::peel_to_commit;
+use crate::utils::error::GitError;
+use crate::core::config::Config;
+use crate::utils::fs::{create_dir, write_file, write_file_atomic, read_file, check_path_exists};
+use crate::utils::hash::HashAlgorithm;
 use std::path::Path;
 
 /// 引用管理器（不存储状态，纯操作类）
 pub struct Reference;
 
+/// `.git/packed-refs` 中的一条引用（`git pack-refs` / `git gc` 打包后的引用）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedRef {
+    pub name: String,
+    pub sha: String,
+    /// 附注标签剥离后的对象（紧随其后的 `^<哈希>` 行），没有记录时为 None
+    pub peeled: Option<String>,
+}
+
 impl Reference {
-    /// 创建引用文件（分支或标签）
+    /// 创建或更新引用文件（分支、标签或 HEAD）
+    ///
+    /// 通过临时文件 + rename 原子替换，中途崩溃不会留下空引用。
     pub fn create(repo_path: &str, ref_name: &str, target_hash: &str) {
         // 构建完整路径：.git/refs/... 
         let ref_path = Path::new(repo_path).join(".git").join(ref_name);
         if let Some(parent) = ref_path.parent() {
-            create_dir(parent.to_str().unwrap()); // 确保目录存在
+            create_dir(parent.to_str().unwrap()).expect("Failed to create reference directory"); // 确保目录存在
         }
-        write_file(ref_path.to_str().unwrap(), target_hash)
+        let fsync = Config::load(repo_path).fsync("reference");
+        write_file_atomic(&ref_path, target_hash.as_bytes(), fsync)
             .expect("Failed to write reference file");
     }
 
-    /// 删除引用文件
+    /// 删除引用：松散的引用文件以及 `packed-refs` 中的同名引用
     pub fn delete(repo_path: &str, ref_name: &str) {
         let ref_path = Path::new(repo_path).join(".git").join(ref_name);
         if check_path_exists(ref_path.to_str().unwrap()) {
             std::fs::remove_file(ref_path).expect("Failed to delete reference file");
         }
+
+        // packed-refs 中去掉该引用所在的行及紧随其后的 `^` 行，其余内容原样保留
+        let packed_path = Path::new(repo_path).join(".git").join("packed-refs");
+        let Ok(content) = read_file(packed_path.to_str().unwrap()) else { return };
+        let mut kept = String::new();
+        let mut removed = false;
+        let mut skipping = false;
+        for line in content.lines() {
+            if line.starts_with('^') && skipping {
+                continue;
+            }
+            skipping = !line.starts_with('#') && line.split_once(' ').is_some_and(|(_, name)| name == ref_name);
+            if skipping {
+                removed = true;
+                continue;
+            }
+            kept.push_str(line);
+            kept.push('\n');
+        }
+        if removed {
+            write_file(packed_path.to_str().unwrap(), &kept).expect("Failed to rewrite packed-refs");
+        }
+    }
+
+    /// 读取 `.git/packed-refs`，文件不存在时返回空列表
+    ///
+    /// 每行 `<哈希> <引用名>`；`^<哈希>` 行记录上一条附注标签剥离后的对象，`#` 开头的是文件头。
+    pub fn packed_refs(repo_path: &str) -> Vec<PackedRef> {
+        let path = Path::new(repo_path).join(".git").join("packed-refs");
+        let Ok(content) = read_file(path.to_str().unwrap()) else { return Vec::new() };
+        let mut refs: Vec<PackedRef> = Vec::new();
+        for line in content.lines().map(str::trim_end) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(peeled) = line.strip_prefix('^') {
+                if let Some(last) = refs.last_mut() {
+                    last.peeled = Some(peeled.to_string());
+                }
+                continue;
+            }
+            if let Some((sha, name)) = line.split_once(' ') {
+                refs.push(PackedRef { name: name.to_string(), sha: sha.to_string(), peeled: None });
+            }
+        }
+        refs
+    }
+
+    /// 列出所有引用（`.git/refs` 下的松散引用和 `packed-refs`），返回 (引用名, 哈希)，按引用名排序
+    ///
+    /// 同名引用同时存在时以松散引用为准（与 Git 相同）。
+    /// 以 `.` 开头或以 `.lock` 结尾的文件和目录不是合法的引用名，跳过：
+    /// 它们是原子写入留下的临时文件（`.<name>.tmp-*`）或其他进程持有的锁文件。
+    pub fn list_all(repo_path: &str) -> Vec<(String, String)> {
+        let git_dir = Path::new(repo_path).join(".git");
+        let mut refs = Vec::new();
+        for entry in walkdir::WalkDir::new(git_dir.join("refs"))
+            .into_iter()
+            .filter_entry(|e| {
+                let name = e.file_name().to_string_lossy();
+                e.depth() == 0 || !(name.starts_with('.') || name.ends_with(".lock"))
+            })
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+        {
+            let name = entry
+                .path()
+                .strip_prefix(&git_dir)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/");
+            if let Some(hash) = Self::resolve(repo_path, &name).filter(|h| !h.is_empty()) {
+                refs.push((name, hash));
+            }
+        }
+        for packed in Self::packed_refs(repo_path) {
+            if !refs.iter().any(|(name, _)| *name == packed.name) {
+                refs.push((packed.name, packed.sha));
+            }
+        }
+        refs.sort();
+        refs
     }
 
-    /// 解析引用内容，返回对应的哈希
+    /// 解析 HEAD，返回其指向的提交哈希（分支尚无提交时返回 None）
+    pub fn resolve_head(repo_path: &str) -> Option<String> {
+        let head = Self::resolve(repo_path, "HEAD")?;
+        match head.strip_prefix("ref: ") {
+            Some(target) => Self::resolve(repo_path, target.trim()),
+            None if head.is_empty() => None,
+            None => Some(head),
+        }
+    }
+
+    /// 解析引用内容，返回对应的哈希（符号引用返回 `ref: <目标>`）
+    ///
+    /// 没有松散的引用文件时，再到 `packed-refs` 中查找。
     pub fn resolve(repo_path: &str, ref_name: &str) -> Option<String> {
         let ref_path = Path::new(repo_path).join(".git").join(ref_name);
         if check_path_exists(ref_path.to_str().unwrap()) {
             let content = read_file(ref_path.to_str().unwrap())
                 .expect("Failed to read reference file");
             Some(content.trim().to_string())
+        } else if ref_name.starts_with("refs/") {
+            Self::packed_refs(repo_path).into_iter().find(|r| r.name == ref_name).map(|r| r.sha)
         } else {
             None
         }
     }
+
+    /// 解析修订名，返回对象哈希（不剥离附注标签）
+    ///
+    /// 查找顺序与 Git 一致：完整对象哈希 → `HEAD` → `refs/<rev>` → `refs/tags/<rev>` →
+    /// `refs/heads/<rev>` → `refs/remotes/<rev>`，最后尝试至少 4 位的唯一短哈希。
+    /// 短哈希不唯一时返回 `GitError::AmbiguousObject`。
+    pub fn try_resolve_revision(repo_path: &str, rev: &str) -> Result<String, GitError> {
+        if HashAlgorithm::for_repo(repo_path).is_full_hex(rev) {
+            let sha = rev.to_ascii_lowercase();
+            if Object::load_object(repo_path, &sha).is_some() {
+                return Ok(sha);
+            }
+        }
+
+        if rev == "HEAD" {
+            return Self::resolve_head(repo_path).ok_or_else(|| GitError::UnknownRevision(rev.to_string()));
+        }
+
+        let candidates = [
+            rev.to_string(),
+            format!("refs/{}", rev),
+            format!("refs/tags/{}", rev),
+            format!("refs/heads/{}", rev),
+            format!("refs/remotes/{}", rev),
+        ];
+        for name in candidates.iter().filter(|n| n.starts_with("refs/") && !n.contains("..")) {
+            if let Some(target) = Self::resolve(repo_path, name).filter(|t| !t.is_empty()) {
+                let sha = match target.strip_prefix("ref: ") {
+                    Some(symbolic) => Self::resolve(repo_path, symbolic.trim()),
+                    None => Some(target),
+                };
+                return sha.ok_or_else(|| GitError::UnknownRevision(rev.to_string()));
+            }
+        }
+
+        if rev.len() >= 4 && rev.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Object::resolve_prefix(repo_path, rev);
+        }
+        Err(GitError::UnknownRevision(rev.to_string()))
+    }
+
+    /// 解析修订名，返回对象哈希（失败时返回 None）
+    pub fn resolve_revision(repo_path: &str, rev: &str) -> Option<String> {
+        Self::try_resolve_revision(repo_path, rev).ok()
+    }
+
+    /// 解析修订名并剥离附注标签，返回其指向的提交哈希
+    pub fn try_resolve_commit(repo_path: &str, rev: &str) -> Result<String, GitError> {
+        let sha = Self::try_resolve_revision(repo_path, rev)?;
+        peel_to_commit(&LooseObjectDatabase::new(repo_path), &sha).ok_or_else(|| GitError::NotACommit(rev.to_string()))
+    }
+
+    /// 解析修订名并剥离附注标签，返回其指向的提交哈希（失败时返回 None）
+    pub fn resolve_commit(repo_path: &str, rev: &str) -> Option<String> {
+        Self::try_resolve_commit(repo_path, rev).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+
+    const PACKED: &str = "# pack-refs with: peeled fully-peeled sorted \n\
+        1111111111111111111111111111111111111111 refs/heads/main\n\
+        2222222222222222222222222222222222222222 refs/tags/v1\n\
+        ^3333333333333333333333333333333333333333\n\
+        4444444444444444444444444444444444444444 refs/tags/v2\n";
+
+    fn repo_with_packed_refs(name: &str) -> TempRepo {
+        let repo = TempRepo::new(name);
+        std::fs::write(Path::new(repo.path()).join(".git/packed-refs"), PACKED).unwrap();
+        repo
+    }
+
+    #[test]
+    fn parses_packed_refs_with_peeled_lines() {
+        let repo = repo_with_packed_refs("packed-refs-parse");
+        let refs = Reference::packed_refs(repo.path());
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[1].name, "refs/tags/v1");
+        assert_eq!(refs[1].peeled.as_deref(), Some("3333333333333333333333333333333333333333"));
+        assert_eq!(refs[2].peeled, None);
+    }
+
+    #[test]
+    fn loose_refs_take_precedence_over_packed_refs() {
+        let repo = repo_with_packed_refs("packed-refs-list");
+        Reference::create(repo.path(), "refs/heads/main", &"5".repeat(40));
+
+        assert_eq!(Reference::resolve(repo.path(), "refs/heads/main"), Some("5".repeat(40)));
+        assert_eq!(Reference::resolve(repo.path(), "refs/tags/v2"), Some("4".repeat(40)));
+        assert_eq!(
+            Reference::list_all(repo.path()),
+            vec![
+                ("refs/heads/main".to_string(), "5".repeat(40)),
+                ("refs/tags/v1".to_string(), "2".repeat(40)),
+                ("refs/tags/v2".to_string(), "4".repeat(40)),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_all_skips_temp_and_lock_files() {
+        let repo = TempRepo::new("refs-temp-files");
+        Reference::create(repo.path(), "refs/heads/main", &"5".repeat(40));
+        // 被中断的原子写入和其他进程持有的锁留下的文件
+        let heads = Path::new(repo.path()).join(".git/refs/heads");
+        let tmp = crate::utils::fs::temp_path(&heads.join("main"));
+        std::fs::write(&tmp, "6".repeat(40)).unwrap();
+        std::fs::write(heads.join("topic.lock"), "7".repeat(40)).unwrap();
+
+        assert_eq!(Reference::list_all(repo.path()), vec![("refs/heads/main".to_string(), "5".repeat(40))]);
+    }
+
+    #[test]
+    fn delete_removes_packed_ref_and_its_peeled_line() {
+        let repo = repo_with_packed_refs("packed-refs-delete");
+        Reference::delete(repo.path(), "refs/tags/v1");
+
+        assert_eq!(Reference::resolve(repo.path(), "refs/tags/v1"), None);
+        let content = std::fs::read_to_string(Path::new(repo.path()).join(".git/packed-refs")).unwrap();
+        assert!(content.starts_with("# pack-refs with:"));
+        assert!(!content.contains("3333"));
+        assert_eq!(Reference::packed_refs(repo.path()).len(), 2);
+    }
 }
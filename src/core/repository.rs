@@ -1,4 +1,6 @@
-use crate::utils::fs::{create_dir, create_file, check_path_exists};
+use crate::utils::fs::{create_dir, create_file, check_path_exists, write_file};
+use crate::utils::hash::HashAlgorithm;
+use std::io;
 use std::path::Path;
 
 /// Git 仓库结构体
@@ -7,22 +9,29 @@ pub struct Repository {
 }
 
 impl Repository {
-    /// 初始化一个新的 Git 仓库
-    pub fn init(path: &str) -> Self {
+    /// 初始化一个新的 Git 仓库（SHA-1 对象格式）
+    pub fn init(path: &str) -> io::Result<Self> {
+        Self::init_with_format(path, HashAlgorithm::Sha1)
+    }
+
+    /// 初始化一个新的 Git 仓库，并指定对象哈希算法
+    /// - SHA-256 仓库会在 config 中写入 `extensions.objectFormat = sha256`，
+    ///   并按 Git 要求把 `core.repositoryformatversion` 设为 1
+    pub fn init_with_format(path: &str, algo: HashAlgorithm) -> io::Result<Self> {
         let git_dir = Path::new(path).join(".git");
 
         // =========================
         // 创建核心目录结构
         // =========================
-        create_dir(git_dir.to_str().unwrap());                           // .git 根目录
-        create_dir(git_dir.join("objects").to_str().unwrap());           // 存储 Git 对象（blob/tree/commit/tag）
-        create_dir(git_dir.join("objects").join("info").to_str().unwrap()); // objects/info，存储附加信息
-        create_dir(git_dir.join("objects").join("pack").to_str().unwrap()); // objects/pack，存储打包对象
-        create_dir(git_dir.join("refs").to_str().unwrap());              // 存储指针引用目录
-        create_dir(git_dir.join("refs").join("heads").to_str().unwrap());   // 本地分支指针目录
-        create_dir(git_dir.join("refs").join("tags").to_str().unwrap());    // 标签指针目录
-        create_dir(git_dir.join("refs").join("remotes").to_str().unwrap()); // 远程跟踪分支指针目录
-        create_dir(git_dir.join("hooks").to_str().unwrap());             // 钩子脚本目录
+        create_dir(git_dir.to_str().unwrap())?;                           // .git 根目录
+        create_dir(git_dir.join("objects").to_str().unwrap())?;           // 存储 Git 对象（blob/tree/commit/tag）
+        create_dir(git_dir.join("objects").join("info").to_str().unwrap())?; // objects/info，存储附加信息
+        create_dir(git_dir.join("objects").join("pack").to_str().unwrap())?; // objects/pack，存储打包对象
+        create_dir(git_dir.join("refs").to_str().unwrap())?;              // 存储指针引用目录
+        create_dir(git_dir.join("refs").join("heads").to_str().unwrap())?;   // 本地分支指针目录
+        create_dir(git_dir.join("refs").join("tags").to_str().unwrap())?;    // 标签指针目录
+        create_dir(git_dir.join("refs").join("remotes").to_str().unwrap())?; // 远程跟踪分支指针目录
+        create_dir(git_dir.join("hooks").to_str().unwrap())?;             // 钩子脚本目录
 
         // =========================
         // 创建核心文件
@@ -30,12 +39,27 @@ impl Repository {
         create_file(git_dir.join("HEAD").to_str().unwrap());          // HEAD 文件
         create_file(git_dir.join("config").to_str().unwrap());        // 配置文件
         create_file(git_dir.join("description").to_str().unwrap());   // 仓库描述文件
-        create_file(git_dir.join("index").to_str().unwrap());         // 暂存区索引文件
 
         // 设置 HEAD 默认指向 main 分支
-        std::fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        write_file(git_dir.join("HEAD").to_str().unwrap(), "ref: refs/heads/main\n")?;
+
+        // 写入默认配置
+        write_file(git_dir.join("config").to_str().unwrap(), &Self::default_config(algo))?;
+
+        Ok(Repository { path: path.to_string() })
+    }
 
-        Repository { path: path.to_string() }
+    /// 默认配置内容
+    fn default_config(algo: HashAlgorithm) -> String {
+        let version = if algo == HashAlgorithm::Sha1 { 0 } else { 1 };
+        let mut config = format!(
+            "[core]\n\trepositoryformatversion = {}\n\tfilemode = true\n\tbare = false\n",
+            version
+        );
+        if algo != HashAlgorithm::Sha1 {
+            config.push_str(&format!("[extensions]\n\tobjectFormat = {}\n", algo.name()));
+        }
+        config
     }
 
     /// 检查指定目录是否是一个 Git 仓库
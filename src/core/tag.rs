@@ -0,0 +1,205 @@
+use crate::core::commit::{write_extra_header, Commit, ExtraHeader, Signature};
+use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::utils::error::GitError;
+
+/// 解析后的附注标签对象
+///
+/// 格式：`object`、`type`、`tag`、可选的 `tagger` 头部，之后可能还有其他头部，空行之后是标签信息
+/// （签名标签的 PGP 签名直接附在信息末尾）。与 `Commit` 一样，标签名、其他头部和标签信息都按原始字节保存，
+/// `parse` 之后再 `serialize` 得到的内容与原对象逐字节相同。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    /// 被标记对象的哈希
+    pub object: String,
+    /// 被标记对象的类型（通常为 commit）
+    pub kind: String,
+    /// 标签名（原始字节）
+    pub tag: Vec<u8>,
+    /// 打标签的人（早期 Git 创建的标签可能没有）
+    pub tagger: Option<Signature>,
+    /// `tagger` 之后的其他头部
+    pub extra_headers: Vec<ExtraHeader>,
+    pub message: Vec<u8>,
+    /// 头部与标签信息之间是否有空行
+    has_separator: bool,
+}
+
+impl Tag {
+    /// 创建新标签（没有其他头部）
+    pub fn new(object: String, kind: String, tag: &str, tagger: Option<Signature>, message: Vec<u8>) -> Self {
+        Tag {
+            object,
+            kind,
+            tag: tag.as_bytes().to_vec(),
+            tagger,
+            extra_headers: Vec::new(),
+            message,
+            has_separator: true,
+        }
+    }
+
+    /// 解析标签对象内容（不含 `tag <len>\0` header）
+    pub fn parse(data: &[u8]) -> Option<Tag> {
+        let (headers, message, has_separator) = match data.windows(2).position(|w| w == b"\n\n") {
+            Some(pos) => (&data[..pos], &data[pos + 2..], true),
+            None => (data.strip_suffix(b"\n")?, &data[data.len()..], false),
+        };
+
+        let mut lines = headers.split(|&b| b == b'\n').peekable();
+        let object = std::str::from_utf8(lines.next()?.strip_prefix(b"object ")?).ok()?.to_string();
+        let kind = std::str::from_utf8(lines.next()?.strip_prefix(b"type ")?).ok()?.to_string();
+        let tag = lines.next()?.strip_prefix(b"tag ")?.to_vec();
+        let tagger = match lines.next_if(|line| line.starts_with(b"tagger ")) {
+            Some(line) => Some(Signature::parse(&line[b"tagger ".len()..])?),
+            None => None,
+        };
+
+        // 其余头部（例如 SHA-256 仓库的 gpgsig-sha256），续行以空格开头
+        let standard = 3 + tagger.is_some() as usize;
+        let mut extra_headers: Vec<ExtraHeader> = Vec::new();
+        for line in lines {
+            if let Some(cont) = line.strip_prefix(b" ") {
+                let last = extra_headers.last_mut()?;
+                last.value.push(b'\n');
+                last.value.extend_from_slice(cont);
+                continue;
+            }
+            let space = line.iter().position(|&b| b == b' ')?;
+            extra_headers.push(ExtraHeader {
+                key: String::from_utf8(line[..space].to_vec()).ok()?,
+                value: line[space + 1..].to_vec(),
+                after: standard,
+            });
+        }
+
+        Some(Tag { object, kind, tag, tagger, extra_headers, message: message.to_vec(), has_separator })
+    }
+
+    /// 序列化为标签对象内容
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = format!("object {}\ntype {}\ntag ", self.object, self.kind).into_bytes();
+        out.extend_from_slice(&self.tag);
+        out.push(b'\n');
+        if let Some(tagger) = &self.tagger {
+            out.extend_from_slice(b"tagger ");
+            out.extend_from_slice(tagger.as_bytes());
+            out.push(b'\n');
+        }
+        for header in &self.extra_headers {
+            write_extra_header(&mut out, header);
+        }
+        if self.has_separator {
+            out.push(b'\n');
+        }
+        out.extend_from_slice(&self.message);
+        out
+    }
+
+    /// 标签名（非 UTF-8 字节按替换字符显示）
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.tag).into_owned()
+    }
+
+    /// 从对象库读取并解析标签对象
+    pub fn load(db: &dyn ObjectDatabase, sha: &str) -> Option<Tag> {
+        match db.read(sha)? {
+            Object::Tag(data) => Tag::parse(&data),
+            _ => None,
+        }
+    }
+
+    /// 保存到对象库，返回标签对象哈希
+    pub fn save(&self, db: &dyn ObjectDatabase) -> Result<String, GitError> {
+        db.write(&Object::Tag(self.serialize()))
+    }
+}
+
+/// 剥离附注标签：沿 tag 对象一路解引用，返回第一个非 tag 对象的 (哈希, 类型)
+pub fn peel(db: &dyn ObjectDatabase, sha: &str) -> Option<(String, &'static str)> {
+    let mut current = sha.to_string();
+    // 防止构造出的标签环导致死循环
+    for _ in 0..64 {
+        let obj = db.read(&current)?;
+        match obj {
+            Object::Tag(data) => current = Tag::parse(&data)?.object,
+            other => return Some((current, other.type_name())),
+        }
+    }
+    None
+}
+
+/// 剥离到提交：最终对象不是 commit 时返回 None
+pub fn peel_to_commit(db: &dyn ObjectDatabase, sha: &str) -> Option<String> {
+    match peel(db, sha)? {
+        (commit, "commit") => Some(commit),
+        _ => None,
+    }
+}
+
+/// 剥离到树：commit 取其 tree，tag 先剥离；最终对象不是 tree 时返回 None
+pub fn peel_to_tree(db: &dyn ObjectDatabase, sha: &str) -> Option<String> {
+    match peel(db, sha)? {
+        (tree, "tree") => Some(tree),
+        (commit, "commit") => Commit::load(db, &commit).map(|c| c.tree),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::HashAlgorithm;
+
+    /// 由 `git tag -a` 生成：打标签的人名字和标签信息都是 Latin-1
+    const GIT_TAG: &[u8] = b"object fbaf1cf84b434f6145d6033139c01f1614851bb2\n\
+        type commit\n\
+        tag v1.0\n\
+        tagger Jos\xe9 <j@e> 1700000000 +0800\n\
+        \n\
+        Release caf\xe9\n\
+        \n\
+        Second paragraph\n";
+
+    #[test]
+    fn round_trips_git_written_tag() {
+        let tag = Tag::parse(GIT_TAG).unwrap();
+        assert_eq!(tag.object, "fbaf1cf84b434f6145d6033139c01f1614851bb2");
+        assert_eq!(tag.kind, "commit");
+        assert_eq!(tag.name(), "v1.0");
+        let tagger = tag.tagger.as_ref().unwrap();
+        assert_eq!(tagger.name(), b"Jos\xe9");
+        assert_eq!(tagger.time(), 1700000000);
+        assert!(tag.message.starts_with(b"Release caf\xe9\n"));
+
+        assert_eq!(tag.serialize(), GIT_TAG);
+        let sha = Object::Tag(tag.serialize()).try_hash(HashAlgorithm::Sha1).unwrap();
+        assert_eq!(sha, "4d5936c10dfce8505610dcab344bccc9de509484");
+    }
+
+    #[test]
+    fn round_trips_tags_without_tagger_or_separator() {
+        let old = b"object fbaf1cf84b434f6145d6033139c01f1614851bb2\ntype commit\ntag v0.1\n\nold tag\n";
+        let tag = Tag::parse(old).unwrap();
+        assert_eq!(tag.tagger, None);
+        assert_eq!(tag.serialize(), old);
+
+        let bare = b"object fbaf1cf84b434f6145d6033139c01f1614851bb2\ntype commit\ntag v0.2\n";
+        assert_eq!(Tag::parse(bare).unwrap().serialize(), bare);
+    }
+
+    #[test]
+    fn keeps_extra_headers_after_tagger() {
+        let signed = b"object fbaf1cf84b434f6145d6033139c01f1614851bb2\n\
+            type commit\n\
+            tag v2\n\
+            tagger T <t@e> 1700000000 +0000\n\
+            gpgsig-sha256 -----BEGIN PGP SIGNATURE-----\n \n abc\n -----END PGP SIGNATURE-----\n\
+            \n\
+            signed\n";
+        let tag = Tag::parse(signed).unwrap();
+        assert_eq!(tag.extra_headers.len(), 1);
+        assert_eq!(tag.extra_headers[0].key, "gpgsig-sha256");
+        assert_eq!(tag.serialize(), signed);
+    }
+}
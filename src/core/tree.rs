@@ -1,17 +1,53 @@
 use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::core::cache_tree::CacheTree;
 use crate::core::index::IndexEntry;
+use crate::utils::error::GitError;
+use crate::utils::fs::path_to_bytes;
+use crate::utils::hash::HashAlgorithm;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 /// Tree 条目
 #[derive(Debug, Clone)]
 pub struct TreeEntry {
-    pub name: String,   // 文件名或目录名
-    pub hash: String,   // 对应对象的 SHA1 哈希
+    pub name: Vec<u8>,  // 文件名或目录名（原始字节，Git 不要求是 UTF-8）
+    pub hash: String,   // 对应对象的哈希（SHA-1 或 SHA-256）
     pub mode: u32,      // 文件模式（100644 普通文件，100755 可执行文件，40000 目录）
     pub is_dir: bool,   // 是否为目录
 }
 
+impl TreeEntry {
+    /// 条目指向的对象类型（子模块 gitlink 指向的是其他仓库的提交）
+    pub fn object_type(&self) -> &'static str {
+        match self.mode {
+            0o40000 => "tree",
+            0o160000 => "commit",
+            _ => "blob",
+        }
+    }
+
+    /// 是否为 Git 认可的条目模式
+    ///
+    /// 旧版 Git 写入的组可写文件模式 100664 也可以读取（fsck 对它只给出警告）。
+    pub fn has_valid_mode(&self) -> bool {
+        matches!(self.mode, 0o100644 | 0o100664 | 0o100755 | 0o120000 | 0o40000 | 0o160000)
+    }
+
+    /// Git 规定的条目排序：按名字字节序比较，目录名视为末尾带 `/`
+    pub fn git_cmp(&self, other: &TreeEntry) -> Ordering {
+        let key = |e: &TreeEntry| {
+            let mut k = e.name.clone();
+            if e.is_dir {
+                k.push(b'/');
+            }
+            k
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
 /// Tree 对象处理器
 pub struct TreeProcessor;
 
@@ -21,134 +57,220 @@ impl TreeProcessor {
     // =====================================
 
     /// 生成空 Tree 对象（用于空提交）
-    pub fn create_empty_tree(repo_path: &str) -> String {
-        let tree_obj = Object::Tree(Vec::new());
-        tree_obj.save(repo_path)
+    pub fn create_empty_tree(db: &dyn ObjectDatabase) -> Result<String, GitError> {
+        db.write(&Object::Tree(Vec::new()))
     }
 
     /// 根据 Index 条目生成 Tree 对象（递归）
-    /// - 输入：Index 哈希表（PathBuf -> IndexEntry）
-    /// - 输出：Tree 对象 SHA1
+    /// - 输入：Index 哈希表（PathBuf -> IndexEntry）、各目录的 tree 缓存
+    /// - 输出：Tree 对象哈希；写入对象失败时返回错误
+    ///
+    /// 缓存仍有效且对象库中确有该 tree 的目录直接复用其 tree 哈希，不再读取条目、生成对象
+    /// （缓存的 tree 可能已被 prune 删除，或 index 来自其他仓库）；重新生成的目录会写回 `cache`。
     pub fn create_tree_from_index(
-        repo_path: &str,
+        db: &dyn ObjectDatabase,
         index_entries: &HashMap<PathBuf, IndexEntry>,
-    ) -> String {
-        let mut dir_map: HashMap<PathBuf, Vec<&IndexEntry>> = HashMap::new();
-
-        // 将每个文件按照父目录分组
-        for (path, entry) in index_entries {
-            let parent = path.parent().unwrap_or(Path::new(""));
-            dir_map.entry(parent.to_path_buf()).or_default().push(entry);
+        cache: &mut CacheTree,
+    ) -> Result<String, GitError> {
+        if let Some(sha) = Self::cached_tree(db, cache) {
+            return Ok(sha.to_string());
         }
 
+        // 按路径字节序排列后，同一目录下的条目是连续的一段
+        let mut entries: Vec<(&[u8], &IndexEntry)> =
+            index_entries.iter().map(|(path, entry)| (path_to_bytes(path), entry)).collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
         // 从根目录开始递归生成 tree
-        Self::build_tree(repo_path, Path::new(""), &dir_map)
+        Self::build_tree(db, &entries, 0, cache)
     }
 
     /// 内部递归生成 Tree 对象
+    /// - `entries`：该目录下的所有条目（递归），`prefix_len` 为目录路径（含末尾 `/`）的长度
     fn build_tree(
-        repo_path: &str,
-        current_dir: &Path,
-        dir_map: &HashMap<PathBuf, Vec<&IndexEntry>>,
-    ) -> String {
-        let mut entries = Vec::new();
+        db: &dyn ObjectDatabase,
+        entries: &[(&[u8], &IndexEntry)],
+        prefix_len: usize,
+        cache: &mut CacheTree,
+    ) -> Result<String, GitError> {
+        let mut tree_entries = Vec::new();
+        let mut subdirs = HashSet::new();
+        let mut i = 0;
 
-        // 1️⃣ 处理当前目录下的文件
-        if let Some(files) = dir_map.get(current_dir) {
-            for entry in files {
-                if !entry.path.is_dir() {
-                    let name = entry
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or_else(|| entry.path.to_str().unwrap())
-                        .to_string();
-
-                    entries.push(TreeEntry {
-                        name,
+        while i < entries.len() {
+            let (path, entry) = entries[i];
+            let rest = &path[prefix_len..];
+            match rest.iter().position(|&b| b == b'/') {
+                // 1️⃣ 当前目录下的文件
+                None => {
+                    tree_entries.push(TreeEntry {
+                        name: rest.to_vec(),
                         hash: entry.sha.clone(),
                         mode: entry.mode,
                         is_dir: false,
                     });
+                    i += 1;
                 }
-            }
-        }
-
-        // 2️⃣ 处理子目录
-        let mut subdirs = HashSet::new();
-        for path in dir_map.keys() {
-            if let Some(parent) = path.parent() {
-                if parent == current_dir && path != current_dir {
-                    subdirs.insert(path.clone());
+                // 2️⃣ 子目录：取出属于它的连续条目，缓存失效时才递归生成
+                Some(slash) => {
+                    let dir_prefix = &path[..prefix_len + slash + 1];
+                    let count = entries[i..].iter().take_while(|(p, _)| p.starts_with(dir_prefix)).count();
+                    let name = rest[..slash].to_vec();
+                    let child = cache.children.entry(name.clone()).or_default();
+                    let hash = match Self::cached_tree(db, child) {
+                        Some(sha) => sha.to_string(),
+                        None => Self::build_tree(db, &entries[i..i + count], dir_prefix.len(), child)?,
+                    };
+                    subdirs.insert(name.clone());
+                    tree_entries.push(TreeEntry {
+                        name,
+                        hash,
+                        mode: 0o40000, // 目录模式
+                        is_dir: true,
+                    });
+                    i += count;
                 }
             }
         }
 
-        for subdir in subdirs {
-            let tree_hash = Self::build_tree(repo_path, &subdir, dir_map);
-            let name = subdir
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-                .to_string();
+        // 3️⃣ 按 Git 的顺序排列条目，否则 git fsck 会报 treeNotSorted
+        tree_entries.sort_by(|a, b| a.git_cmp(b));
 
-            entries.push(TreeEntry {
-                name,
-                hash: tree_hash,
-                mode: 0o40000, // 目录模式
-                is_dir: true,
-            });
-        }
+        // 4️⃣ 创建 tree 对象（二进制），结果写回缓存（已不存在的子目录一并移除）
+        let sha = Self::create_tree(db, tree_entries)?;
+        cache.children.retain(|name, _| subdirs.contains(name));
+        cache.entry_count = entries.len() as i32;
+        cache.sha = Some(sha.clone());
+        Ok(sha)
+    }
 
-        // 3️⃣ 创建 tree 对象（二进制）
-        Self::create_tree(repo_path, entries)
+    /// 可以直接复用的缓存 tree 哈希：缓存有效且对象仍在对象库中
+    fn cached_tree<'a>(db: &dyn ObjectDatabase, cache: &'a CacheTree) -> Option<&'a str> {
+        cache.sha.as_deref().filter(|sha| cache.is_valid() && db.contains(sha))
     }
 
-    /// 将 TreeEntry 列表写入 Tree 对象（二进制格式）
-    fn create_tree(repo_path: &str, entries: Vec<TreeEntry>) -> String {
+    /// 将 TreeEntry 列表写入 Tree 对象（二进制格式，条目需已按 Git 顺序排列）
+    pub fn create_tree(db: &dyn ObjectDatabase, entries: Vec<TreeEntry>) -> Result<String, GitError> {
         let mut buf = Vec::new();
         for entry in entries {
             let mode_str = format!("{:o}", entry.mode);
             buf.extend(mode_str.as_bytes());
             buf.push(b' ');
-            buf.extend(entry.name.as_bytes());
+            buf.extend(&entry.name);
             buf.push(0); // null 分隔符
-            buf.extend(hex::decode(&entry.hash).unwrap()); // 20 字节 SHA
+            buf.extend(hex::decode(&entry.hash).unwrap()); // 20 / 32 字节哈希
         }
 
-        let tree_obj = Object::Tree(buf);
-        tree_obj.save(repo_path)
+        db.write(&Object::Tree(buf))
     }
 
     // =====================================
     // ---------- 读操作（解析 Tree） ----------
     // =====================================
 
+    /// 从对象库读取并解析 Tree 对象
+    pub fn read_tree(db: &dyn ObjectDatabase, sha: &str) -> Option<Vec<TreeEntry>> {
+        match db.read(sha)? {
+            Object::Tree(data) => Self::try_parse_tree(&data, db.hash_algorithm()),
+            _ => None,
+        }
+    }
+
+    /// 按路径（`a/b/c`）查找 tree 中的条目，路径不存在时返回 None
+    pub fn entry_at(db: &dyn ObjectDatabase, tree_sha: &str, path: &str) -> Option<TreeEntry> {
+        let mut tree = tree_sha.to_string();
+        let mut parts = path.split('/').filter(|p| !p.is_empty()).peekable();
+        while let Some(name) = parts.next() {
+            let entry = Self::read_tree(db, &tree)?.into_iter().find(|e| e.name == name.as_bytes())?;
+            if parts.peek().is_none() {
+                return Some(entry);
+            }
+            if !entry.is_dir {
+                return None;
+            }
+            tree = entry.hash;
+        }
+        None
+    }
+
+    /// 比较两个 tree，递归列出内容或模式不同的文件路径（新增、删除、修改）
+    ///
+    /// `None` 表示空 tree（例如根提交没有父提交）。同名的文件和目录视为不同条目，
+    /// 与 Git 的 tree diff 一致。路径为原始字节。
+    pub fn changed_paths(db: &dyn ObjectDatabase, old: Option<&str>, new: Option<&str>) -> Vec<Vec<u8>> {
+        let mut paths = Vec::new();
+        Self::diff_into(db, old, new, b"", &mut paths);
+        paths
+    }
+
+    fn diff_into(db: &dyn ObjectDatabase, old: Option<&str>, new: Option<&str>, prefix: &[u8], out: &mut Vec<Vec<u8>>) {
+        let read = |sha: Option<&str>| sha.and_then(|s| Self::read_tree(db, s)).unwrap_or_default();
+        let (old, new) = (read(old), read(new));
+        let (mut i, mut j) = (0, 0);
+
+        while i < old.len() || j < new.len() {
+            let order = match (old.get(i), new.get(j)) {
+                (Some(a), Some(b)) => a.git_cmp(b),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            let (a, b) = match order {
+                Ordering::Less => (old.get(i), None),
+                Ordering::Greater => (None, new.get(j)),
+                Ordering::Equal => (old.get(i), new.get(j)),
+            };
+            if order != Ordering::Greater {
+                i += 1;
+            }
+            if order != Ordering::Less {
+                j += 1;
+            }
+            if let (Some(a), Some(b)) = (a, b)
+                && a.hash == b.hash
+                && a.mode == b.mode
+            {
+                continue;
+            }
+
+            let entry = a.or(b).unwrap();
+            let path = [prefix, &entry.name].concat();
+            if entry.is_dir {
+                let (a, b) = (a.map(|e| e.hash.as_str()), b.map(|e| e.hash.as_str()));
+                Self::diff_into(db, a, b, &[&path[..], b"/"].concat(), out);
+            } else {
+                out.push(path);
+            }
+        }
+    }
+
     /// 解析 Tree 对象二进制数据
-    /// - 输入：tree 对象二进制 Vec<u8>
-    /// - 输出：Vec<TreeEntry>，包含目录和文件条目信息
-    pub fn parse_tree(tree_data: &[u8]) -> Vec<TreeEntry> {
+    /// - 输入：tree 对象二进制 Vec<u8>，以及仓库的哈希算法（决定每个条目的哈希长度）
+    /// - 输出：Vec<TreeEntry>，包含目录和文件条目信息；格式错误时返回 None
+    ///
+    /// 条目名按原始字节保存，非 UTF-8 的文件名（例如 Latin-1 编码的 `caf\xe9`）也能正常解析。
+    pub fn try_parse_tree(tree_data: &[u8], algo: HashAlgorithm) -> Option<Vec<TreeEntry>> {
+        let hash_len = algo.raw_len();
         let mut entries = Vec::new();
         let mut i = 0;
 
         while i < tree_data.len() {
             // 1️⃣ 读取 mode 字符串
             let start = i;
-            while tree_data[i] != b' ' { i += 1; }
-            let mode_str = std::str::from_utf8(&tree_data[start..i]).unwrap();
-            let mode = u32::from_str_radix(mode_str, 8).unwrap(); // 8 = 八进制
+            i += tree_data[i..].iter().position(|&b| b == b' ')?;
+            let mode_str = std::str::from_utf8(&tree_data[start..i]).ok()?;
+            let mode = u32::from_str_radix(mode_str, 8).ok()?; // 8 = 八进制
             i += 1;
 
             // 2️⃣ 读取 name（以 null 结束）
             let start = i;
-            while tree_data[i] != 0 { i += 1; }
-            let name = String::from_utf8(tree_data[start..i].to_vec()).unwrap();
+            i += tree_data[i..].iter().position(|&b| b == 0)?;
+            let name = tree_data[start..i].to_vec();
             i += 1;
 
-            // 3️⃣ 读取 20 字节 SHA
-            let sha_bytes = &tree_data[i..i+20];
+            // 3️⃣ 读取 20 / 32 字节哈希
+            let sha_bytes = tree_data.get(i..i + hash_len)?;
             let hash = hex::encode(sha_bytes);
-            i += 20;
+            i += hash_len;
 
             // 4️⃣ 判断是否目录
             let is_dir = mode == 0o40000;
@@ -160,6 +282,67 @@ impl TreeProcessor {
             });
         }
 
-        entries
+        Some(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::odb::MemoryObjectDatabase;
+
+    #[test]
+    fn non_utf8_names_round_trip() {
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let blob = db.write(&Object::Blob(b"x\n".to_vec())).unwrap();
+        let entry = TreeEntry { name: b"caf\xe9".to_vec(), hash: blob.clone(), mode: 0o100644, is_dir: false };
+        let tree = TreeProcessor::create_tree(&db, vec![entry]).unwrap();
+        // 与 `git mktree` 的结果一致
+        assert_eq!(tree, "16004dd6e52de069a8455fdf2d3c33aa640d5b47");
+
+        let entries = TreeProcessor::read_tree(&db, &tree).unwrap();
+        assert_eq!(entries[0].name, b"caf\xe9");
+        assert_eq!(entries[0].hash, blob);
+        assert_eq!(TreeProcessor::changed_paths(&db, None, Some(&tree)), vec![b"caf\xe9".to_vec()]);
+    }
+
+    #[test]
+    fn cached_trees_missing_from_the_database_are_rebuilt() {
+        let db = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        let blob = db.write(&Object::Blob(b"x\n".to_vec())).unwrap();
+        let entry = IndexEntry {
+            path: PathBuf::from("d/f"),
+            sha: blob,
+            mode: 0o100644,
+            mtime: 0,
+            mtime_nsec: 0,
+            ctime: 0,
+            ctime_nsec: 0,
+            dev: 0,
+            ino: 0,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            flags: 0,
+            extended_flags: 0,
+            stage: 0,
+        };
+        let entries = HashMap::from([(entry.path.clone(), entry)]);
+        let mut cache = CacheTree::default();
+        let root = TreeProcessor::create_tree_from_index(&db, &entries, &mut cache).unwrap();
+        assert!(cache.is_valid());
+
+        // 缓存仍然有效，但 tree 对象不在另一个对象库中（例如已被 prune）：重新生成
+        let other = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        assert_eq!(TreeProcessor::create_tree_from_index(&other, &entries, &mut cache).unwrap(), root);
+        assert!(other.contains(&root));
+        let subtree = cache.children[b"d".as_slice()].sha.clone().unwrap();
+        assert!(other.contains(&subtree));
+
+        // 只缺子目录的 tree 时，根目录缓存失效后也会补写子目录
+        let third = MemoryObjectDatabase::new(HashAlgorithm::Sha1);
+        cache.invalidate();
+        TreeProcessor::create_tree_from_index(&third, &entries, &mut cache).unwrap();
+        assert!(third.contains(&subtree));
     }
 }
@@ -0,0 +1,332 @@
+use crate::core::commit::Commit;
+use crate::core::index::Index;
+use crate::core::object::Object;
+use crate::core::odb::ObjectDatabase;
+use crate::core::reference::Reference;
+use crate::core::tree::TreeProcessor;
+use crate::utils::error::GitError;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 可达对象（附带首次发现时的路径，供 delta 选择等启发式使用）
+#[derive(Debug, Clone)]
+pub struct ReachableObject {
+    pub sha: String,
+    pub kind: &'static str,
+    pub path: Option<String>,
+}
+
+/// 所有引用（含 packed-refs）和 HEAD 指向的对象，作为遍历历史的起点
+pub fn ref_roots(repo_path: &str) -> Vec<String> {
+    let mut roots: Vec<String> = Reference::list_all(repo_path)
+        .into_iter()
+        .map(|(_, sha)| sha)
+        .collect();
+    if let Some(head) = Reference::resolve_head(repo_path) {
+        roots.push(head);
+    }
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// 可达性起点的来源（fsck 据此报告无效的指针）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootSource {
+    /// 引用（含 packed-refs 中的引用）或 HEAD
+    Ref(String),
+    /// 暂存区条目（路径）
+    Index(PathBuf),
+    /// 暂存区 TREE 扩展中缓存的 tree
+    CacheTree,
+    /// reflog 中记录过的对象（日志对应的引用名）
+    Reflog(String),
+}
+
+/// 可达性遍历的一个起点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Root {
+    pub sha: String,
+    pub source: RootSource,
+}
+
+/// 仓库中所有可达性遍历的起点：引用（含 packed-refs）、HEAD、暂存区条目与缓存的 tree、reflog
+///
+/// fsck、gc / prune 与 repack 共用这组起点，对哪些对象可达的判断保持一致。
+/// index 损坏时返回错误：此时不知道暂存区引用了哪些对象，调用方不能据此删除对象。
+pub fn repo_roots(repo_path: &str) -> Result<Vec<Root>, GitError> {
+    let mut roots: Vec<Root> = Reference::list_all(repo_path)
+        .into_iter()
+        .map(|(name, sha)| Root { sha, source: RootSource::Ref(name) })
+        .collect();
+    if let Some(head) = Reference::resolve_head(repo_path) {
+        roots.push(Root { sha: head, source: RootSource::Ref("HEAD".to_string()) });
+    }
+
+    let index = Index::try_load(Path::new(repo_path))?;
+    // 子模块（gitlink）指向其他仓库的提交，不在本对象库中
+    for entry in index.all_entries().filter(|e| e.mode != 0o160000) {
+        roots.push(Root { sha: entry.sha.clone(), source: RootSource::Index(entry.path.clone()) });
+    }
+    // 缓存的 tree 可能还没有被任何提交引用
+    for sha in index.cache_tree.valid_shas() {
+        roots.push(Root { sha: sha.to_string(), source: RootSource::CacheTree });
+    }
+
+    roots.extend(reflog_roots(repo_path));
+    Ok(roots)
+}
+
+/// 起点指向的对象哈希（排序去重）
+pub fn root_shas(roots: &[Root]) -> Vec<String> {
+    let mut shas: Vec<String> = roots.iter().map(|r| r.sha.clone()).collect();
+    shas.sort();
+    shas.dedup();
+    shas
+}
+
+/// reflog（`.git/logs/` 下的所有日志）中记录过的对象
+///
+/// 每行格式：`<旧哈希> <新哈希> <签名>\t<说明>`，全零哈希表示引用被创建或删除。
+fn reflog_roots(repo_path: &str) -> Vec<Root> {
+    let logs_dir = Path::new(repo_path).join(".git").join("logs");
+    let mut roots = Vec::new();
+    for entry in walkdir::WalkDir::new(&logs_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let Ok(content) = std::fs::read_to_string(entry.path()) else { continue };
+        let name = entry.path().strip_prefix(&logs_dir).unwrap().to_string_lossy().replace('\\', "/");
+        for line in content.lines() {
+            for sha in line.split(' ').take(2) {
+                if !sha.is_empty() && sha.bytes().any(|b| b != b'0') && sha.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    roots.push(Root { sha: sha.to_string(), source: RootSource::Reflog(name.clone()) });
+                }
+            }
+        }
+    }
+    roots
+}
+
+/// 从给定起点出发的所有可达对象哈希（只需要集合、不需要路径时使用）
+///
+/// 有可达性位图时由位图直接得出，只遍历位图没有覆盖的新对象；否则完整遍历。
+/// 遇到缺失或损坏的对象时返回错误（见 `reachable_objects`）。
+pub fn reachable_set(db: &dyn ObjectDatabase, roots: &[String]) -> Result<HashSet<String>, GitError> {
+    match db.pack_bitmap() {
+        Some(bitmap) => {
+            let reachable = bitmap.reachable(db, roots)?;
+            let in_pack = reachable.bits.ones().map(|bit| bitmap.sha_at(bit).to_string());
+            Ok(in_pack.chain(reachable.extra.into_iter().map(|(sha, _)| sha)).collect())
+        }
+        None => Ok(reachable_objects(db, roots)?.into_iter().map(|o| o.sha).collect()),
+    }
+}
+
+/// 读取可达性遍历中遇到的对象：对象缺失或无法解压时返回错误
+///
+/// 读不出的对象不知道还引用了哪些对象，不能当作遍历的终点跳过，
+/// 否则 prune / repack 会把它之后的历史当作不可达对象删除。
+pub fn read_reachable(db: &dyn ObjectDatabase, sha: &str) -> Result<Object, GitError> {
+    db.read(sha).ok_or_else(|| {
+        if db.contains(sha) {
+            GitError::CorruptObject(sha.to_string())
+        } else {
+            GitError::ObjectNotFound(sha.to_string())
+        }
+    })
+}
+
+/// 遍历历史所需的提交信息
+#[derive(Debug, Clone)]
+pub struct CommitLinks {
+    pub tree: String,
+    pub parents: Vec<String>,
+    /// 提交者时间（Unix 时间戳）
+    pub commit_time: i64,
+}
+
+/// 提交的 tree、父提交和提交时间：commit-graph 中有该提交时直接读取，否则解析提交对象
+pub fn commit_links(db: &dyn ObjectDatabase, sha: &str) -> Option<CommitLinks> {
+    if let Some(commit) = db.commit_graph().and_then(|g| g.lookup(sha)) {
+        return Some(CommitLinks { tree: commit.tree, parents: commit.parents, commit_time: commit.commit_time as i64 });
+    }
+    let commit = Commit::load(db, sha)?;
+    Some(CommitLinks { tree: commit.tree, parents: commit.parents, commit_time: commit.committer.time() })
+}
+
+/// 从给定起点出发，遍历所有可达的提交（附注标签会被剥离，不进入 tree）
+///
+/// 缺失的提交会被跳过（由 fsck 负责报告）。
+pub fn reachable_commits(db: &dyn ObjectDatabase, roots: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    let mut stack: Vec<String> = roots.iter().rev().cloned().collect();
+
+    while let Some(sha) = stack.pop() {
+        if !seen.insert(sha.clone()) {
+            continue;
+        }
+        if let Some(links) = commit_links(db, &sha) {
+            stack.extend(links.parents.into_iter().rev());
+            result.push(sha);
+        } else if let Some(Object::Tag(data)) = db.read(&sha) {
+            let text = String::from_utf8_lossy(&data);
+            if let Some(target) = text.lines().find_map(|l| l.strip_prefix("object ")) {
+                stack.push(target.to_string());
+            }
+        }
+    }
+
+    result
+}
+
+/// 从给定起点出发，遍历所有可达对象（commit → tree → blob，tag → object）
+///
+/// 任何对象缺失、无法读取或无法解析时返回错误，而不是跳过：调用方（prune、repack）
+/// 会删除不在结果中的对象，不完整的结果会导致数据丢失。
+///
+/// tree 条目中的 blob 没有对外引用，只确认存在、不解压内容。
+pub fn reachable_objects(db: &dyn ObjectDatabase, roots: &[String]) -> Result<Vec<ReachableObject>, GitError> {
+    let algo = db.hash_algorithm();
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    // (哈希, 路径, 是否由 tree 条目得知是 blob)
+    let mut stack: Vec<(String, Option<String>, bool)> = roots.iter().rev().map(|r| (r.clone(), None, false)).collect();
+
+    let graph = db.commit_graph();
+
+    while let Some((sha, path, is_blob)) = stack.pop() {
+        if !seen.insert(sha.clone()) {
+            continue;
+        }
+        if is_blob {
+            if !db.contains(&sha) {
+                return Err(GitError::ObjectNotFound(sha));
+            }
+            result.push(ReachableObject { sha, kind: "blob", path });
+            continue;
+        }
+        // commit-graph 中的提交无需读取和解析对象
+        if let Some(commit) = graph.as_ref().and_then(|g| g.lookup(&sha)) {
+            for parent in commit.parents.into_iter().rev() {
+                stack.push((parent, None, false));
+            }
+            stack.push((commit.tree, Some(String::new()), false));
+            result.push(ReachableObject { sha, kind: "commit", path });
+            continue;
+        }
+        let obj = read_reachable(db, &sha)?;
+        let corrupt = || GitError::CorruptObject(sha.clone());
+
+        match &obj {
+            Object::Commit(data) => {
+                let commit = Commit::parse(data).ok_or_else(corrupt)?;
+                for parent in commit.parents.into_iter().rev() {
+                    stack.push((parent, None, false));
+                }
+                stack.push((commit.tree, Some(String::new()), false));
+            }
+            Object::Tag(data) => {
+                let text = String::from_utf8_lossy(data);
+                let target = text.lines().find_map(|l| l.strip_prefix("object ")).ok_or_else(corrupt)?;
+                stack.push((target.to_string(), None, false));
+            }
+            Object::Tree(data) => {
+                let prefix = path.clone().unwrap_or_default();
+                for entry in TreeProcessor::try_parse_tree(data, algo).ok_or_else(corrupt)? {
+                    // 子模块（gitlink）指向其他仓库的提交，不在本对象库中
+                    if entry.mode == 0o160000 {
+                        continue;
+                    }
+                    // 路径只用于 pack 中的名字哈希，非 UTF-8 的字节按替换字符处理即可
+                    let name = String::from_utf8_lossy(&entry.name);
+                    let child = if prefix.is_empty() {
+                        name.into_owned()
+                    } else {
+                        format!("{}/{}", prefix, name)
+                    };
+                    stack.push((entry.hash, Some(child), !entry.is_dir));
+                }
+            }
+            Object::Blob(_) => {}
+        }
+
+        result.push(ReachableObject { sha, kind: obj.type_name(), path });
+    }
+
+    Ok(result)
+}
+
+/// 按路径筛选历史：从 `starts` 出发，返回改动过 `path`（文件或目录）的提交，按提交时间从新到旧
+///
+/// `path` 为空时不做筛选，返回所有可达提交。
+/// 与 `git rev-list <commit> -- <path>` 的默认历史简化一致：
+/// - 与某个父提交在该路径上相同（TREESAME）的提交不输出，并且只沿这个父提交继续遍历
+/// - 根提交只有包含该路径时才输出
+///
+/// commit-graph 带有 changed-path Bloom 过滤器时，过滤器判定“一定没有改动”的单父提交
+/// 直接视为 TREESAME，不再比较 tree。
+pub fn path_history(db: &dyn ObjectDatabase, starts: &[String], path: &str) -> Vec<String> {
+    let path = path.trim_matches('/');
+    let graph = db.commit_graph();
+    let entry = |tree: &str| TreeProcessor::entry_at(db, tree, path).map(|e| (e.hash, e.mode));
+
+    let mut seen = HashSet::new();
+    let mut queue = BinaryHeap::new();
+    let mut order = 0u64;
+    let mut push = |queue: &mut BinaryHeap<(i64, Reverse<u64>, String)>, sha: String| {
+        if seen.insert(sha.clone())
+            && let Some(links) = commit_links(db, &sha)
+        {
+            queue.push((links.commit_time, Reverse(order), sha));
+            order += 1;
+        }
+    };
+    for sha in starts {
+        push(&mut queue, sha.clone());
+    }
+
+    let mut result = Vec::new();
+    while let Some((_, _, sha)) = queue.pop() {
+        let Some(links) = commit_links(db, &sha) else { continue };
+        if links.parents.is_empty() {
+            if path.is_empty() || entry(&links.tree).is_some() {
+                result.push(sha);
+            }
+            continue;
+        }
+
+        // Bloom 过滤器只记录相对第一个父提交的改动，因此只用于单父提交
+        let skipped = links.parents.len() == 1
+            && graph.as_ref().is_some_and(|g| {
+                g.position(&sha)
+                    .and_then(|pos| g.bloom_filter(pos))
+                    .is_some_and(|filter| !filter.maybe_contains(path))
+            });
+        let treesame_parent = if path.is_empty() {
+            None
+        } else if skipped {
+            Some(links.parents[0].clone())
+        } else {
+            let current = entry(&links.tree);
+            links.parents.iter().find(|parent| {
+                commit_links(db, parent).is_some_and(|p| entry(&p.tree) == current)
+            }).cloned()
+        };
+
+        match treesame_parent {
+            Some(parent) => push(&mut queue, parent),
+            None => {
+                result.push(sha);
+                for parent in links.parents {
+                    push(&mut queue, parent);
+                }
+            }
+        }
+    }
+
+    result
+}
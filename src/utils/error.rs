@@ -0,0 +1,79 @@
+// utils/error.rs
+use std::fmt;
+use std::io;
+
+/// 仓库操作中可向用户报告的错误
+#[derive(Debug)]
+pub enum GitError {
+    /// 对象不存在
+    ObjectNotFound(String),
+    /// 对象存在但无法读取或解析（附对象哈希）
+    CorruptObject(String),
+    /// 短哈希匹配到多个对象：(前缀, [(完整哈希, 对象类型)])
+    AmbiguousObject {
+        prefix: String,
+        candidates: Vec<(String, String)>,
+    },
+    /// 无法解析的修订名
+    UnknownRevision(String),
+    /// 修订名解析成功，但最终对象不是提交
+    NotACommit(String),
+    /// 对象内容带有 SHA-1 碰撞攻击的特征（附缓解处理后的哈希）
+    HashCollision(String),
+    /// index 文件格式错误或校验和不符
+    IndexCorrupt(String),
+    /// `index.lock` 已存在：另一个进程正在修改暂存区（附锁文件路径）
+    IndexLocked(String),
+    /// 写入 index 失败
+    IndexWrite(String),
+    /// `extensions.objectFormat` 的值无法识别（附配置的值）
+    UnknownObjectFormat(String),
+    /// 文件系统读写失败
+    Io(io::Error),
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::ObjectNotFound(sha) => write!(f, "object {} not found", sha),
+            GitError::CorruptObject(sha) => write!(f, "object {} is corrupt", sha),
+            GitError::AmbiguousObject { prefix, candidates } => {
+                writeln!(f, "short object ID {} is ambiguous", prefix)?;
+                write!(f, "hint: The candidates are:")?;
+                for (sha, kind) in candidates {
+                    write!(f, "\nhint:   {} {}", sha, kind)?;
+                }
+                Ok(())
+            }
+            GitError::UnknownRevision(rev) => {
+                write!(f, "'{}' did not match any branch, tag or object", rev)
+            }
+            GitError::NotACommit(rev) => write!(f, "'{}' does not point to a commit", rev),
+            GitError::HashCollision(sha) => {
+                write!(f, "SHA-1 appears to be part of a collision attack: {}", sha)
+            }
+            GitError::IndexCorrupt(reason) => write!(f, "index file corrupt: {}", reason),
+            GitError::IndexLocked(path) => write!(
+                f,
+                "Unable to create '{}': File exists.\n\n\
+                 Another process seems to be running in this repository. Please make sure\n\
+                 all processes are terminated then try again. If it still fails, a process\n\
+                 may have crashed in this repository earlier: remove the file manually to continue.",
+                path
+            ),
+            GitError::IndexWrite(reason) => write!(f, "unable to write new index file: {}", reason),
+            GitError::UnknownObjectFormat(name) => {
+                write!(f, "invalid value for 'extensions.objectformat': '{}'", name)
+            }
+            GitError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<io::Error> for GitError {
+    fn from(e: io::Error) -> Self {
+        GitError::Io(e)
+    }
+}
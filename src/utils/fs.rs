@@ -2,12 +2,18 @@ use std::fs;
 use std::env;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// 临时文件序号，保证同一进程内的临时文件名不重复
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// 创建目录（递归创建）
-pub fn create_dir(path: &str) {
+pub fn create_dir(path: &str) -> io::Result<()> {
     if !Path::new(path).exists() {
-        fs::create_dir_all(path).unwrap();
+        fs::create_dir_all(path)?;
     }
+    Ok(())
 }
 
 /// 递归创建目录（如果不存在）
@@ -73,9 +79,7 @@ pub fn read_file(path: &str) -> io::Result<String> {
 
 /// 写入内容到文件（覆盖模式）
 pub fn write_file(path: &str, data: &str) -> io::Result<()> {
-    let mut file = fs::File::create(path)?;
-    file.write_all(data.as_bytes())?;
-    Ok(())
+    write_file_atomic(Path::new(path), data.as_bytes(), false)
 }
 
 /// 追加内容到文件末尾
@@ -103,7 +107,130 @@ pub fn read_file_bytes(path: &str) -> std::io::Result<Vec<u8>> {
 
 /// 写入二进制文件
 pub fn write_file_bytes(path: &str, data: &[u8]) -> std::io::Result<()> {
-    std::fs::write(path, data)
+    write_file_atomic(Path::new(path), data, false)
+}
+
+/// 为 `target` 生成同目录下的临时文件路径（同一文件系统内 rename 才是原子的）
+pub fn temp_path(target: &Path) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    target.with_file_name(format!(
+        ".{}.tmp-{}-{}",
+        name,
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// 把写好的临时文件替换为目标文件：可选 fsync 文件内容，rename 后再 fsync 所在目录
+///
+/// 读者只会看到旧文件或完整的新文件，不会看到写了一半的内容。
+pub fn persist_temp_file(file: fs::File, tmp: &Path, target: &Path, fsync: bool) -> io::Result<()> {
+    let result = (|| {
+        if fsync {
+            file.sync_all()?;
+        }
+        drop(file);
+        fs::rename(tmp, target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(tmp);
+        return result;
+    }
+    if fsync {
+        // 目录项本身也要落盘，否则崩溃后 rename 可能丢失（部分平台不支持打开目录，忽略错误）
+        if let Some(dir) = target.parent().and_then(|d| fs::File::open(d).ok()) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// 原子写入：先写同目录下的临时文件，再 rename 覆盖目标文件
+///
+/// 中途崩溃或被中断时，目标文件保持原样；`fsync` 为 true 时保证返回前数据已落盘。
+pub fn write_file_atomic(path: &Path, data: &[u8], fsync: bool) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+    if let Err(e) = file.write_all(data) {
+        drop(file);
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    persist_temp_file(file, &tmp, path, fsync)
+}
+
+/// `<目标文件>.lock` 锁文件（与 Git 的 lockfile 机制相同）
+///
+/// 以独占方式创建锁文件，持有期间其他进程无法获取同一把锁；新内容写入锁文件后
+/// rename 覆盖目标文件即提交。未提交就被丢弃时（出错返回、panic）自动删除锁文件，
+/// 不会留下陈旧的锁。
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+    target: PathBuf,
+    file: Option<fs::File>,
+}
+
+/// 当前进程持有的锁文件，收到终止信号时由 `remove_locks_on_signal` 安装的处理器删除
+static HELD_LOCKS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn held_locks() -> MutexGuard<'static, Vec<PathBuf>> {
+    HELD_LOCKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 安装 SIGINT / SIGTERM / SIGHUP 处理器：删除进程持有的锁文件后退出
+///
+/// `LockFile` 的 Drop 只在正常返回或 panic 展开时运行，被信号终止的进程会留下 `index.lock`，
+/// 之后的命令都会因为锁已存在而失败。
+pub fn remove_locks_on_signal() {
+    let _ = ctrlc::set_handler(|| {
+        for path in held_locks().drain(..) {
+            let _ = fs::remove_file(path);
+        }
+        std::process::exit(130);
+    });
+}
+
+impl LockFile {
+    /// 获取 `target` 的锁；锁文件已存在（其他进程持有）时返回 `ErrorKind::AlreadyExists`
+    pub fn acquire(target: &Path) -> io::Result<Self> {
+        let mut name = target.as_os_str().to_owned();
+        name.push(".lock");
+        let path = PathBuf::from(name);
+        let file = fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+        held_locks().push(path.clone());
+        Ok(LockFile { path, target: target.to_path_buf(), file: Some(file) })
+    }
+
+    /// 锁文件已提交或删除，不再需要在收到信号时清理
+    fn release(&self) {
+        held_locks().retain(|p| *p != self.path);
+    }
+
+    /// 写入新内容并 rename 为目标文件，同时释放锁
+    pub fn commit(mut self, data: &[u8], fsync: bool) -> io::Result<()> {
+        let mut file = self.file.take().expect("lock already released");
+        let result = match file.write_all(data) {
+            Ok(()) => persist_temp_file(file, &self.path, &self.target, fsync),
+            Err(e) => {
+                drop(file);
+                let _ = fs::remove_file(&self.path);
+                Err(e)
+            }
+        };
+        self.release();
+        result
+    }
+}
+
+impl Drop for LockFile {
+    /// 未提交的锁：删除锁文件，目标文件保持原样
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.path);
+            self.release();
+        }
+    }
 }
 
 /// 读取二进制文件
@@ -116,9 +243,7 @@ pub fn read_binary(path: &str) -> std::io::Result<Vec<u8>> {
 
 /// 写入二进制文件
 pub fn write_binary(path: &str, data: &[u8]) -> std::io::Result<()> {
-    let mut file = fs::File::create(path)?;
-    file.write_all(data)?;
-    Ok(())
+    write_file_atomic(Path::new(path), data, false)
 }
 
 /// 检查目录或文件是否存在
@@ -126,6 +251,61 @@ pub fn check_path_exists(path: &str) -> bool {
     Path::new(path).exists()
 }
 
+/// 把 Git 对象中的路径字节转换为文件系统路径（Git 不要求路径是 UTF-8）
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// 把文件系统路径转换为 Git 使用的路径字节（`path_from_bytes` 的逆操作）
+pub fn path_to_bytes(path: &Path) -> &[u8] {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes()
+    }
+    #[cfg(not(unix))]
+    {
+        path.to_str().unwrap().as_bytes()
+    }
+}
+
+/// 按 Git 默认的 `core.quotePath` 规则输出路径
+///
+/// 含控制字符、`"`、`\` 或非 ASCII 字节的路径用双引号括起，特殊字节转义为 C 风格
+/// （非 ASCII 字节为三位八进制，例如 `"caf\351"`）；其他路径原样输出。
+pub fn quote_path(path: &[u8]) -> String {
+    let needs_quote = |b: u8| b < 0x20 || b == b'"' || b == b'\\' || b >= 0x7f;
+    if !path.iter().any(|&b| needs_quote(b)) {
+        return String::from_utf8_lossy(path).into_owned();
+    }
+    let mut out = String::from("\"");
+    for &b in path {
+        match b {
+            b'\x07' => out.push_str("\\a"),
+            b'\x08' => out.push_str("\\b"),
+            b'\t' => out.push_str("\\t"),
+            b'\n' => out.push_str("\\n"),
+            b'\x0b' => out.push_str("\\v"),
+            b'\x0c' => out.push_str("\\f"),
+            b'\r' => out.push_str("\\r"),
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b if needs_quote(b) => out.push_str(&format!("\\{:03o}", b)),
+            b => out.push(b as char),
+        }
+    }
+    out.push('"');
+    out
+}
+
 
 /// 递归向上查找 `.git` 文件夹，找到则返回仓库根路径
 pub fn find_repo_root(start: &Path) -> Option<PathBuf> {
@@ -157,9 +337,56 @@ pub fn get_current_branch(repo_path: &Path) -> Option<String> {
     let head_path = repo_path.join(".git").join("HEAD");
     if head_path.exists() {
         let content = read_file(head_path.to_str().unwrap()).ok()?;
-        if content.starts_with("ref: refs/heads/") {
-            return Some(content["ref: refs/heads/".len()..].trim().to_string());
+        if let Some(branch) = content.strip_prefix("ref: refs/heads/") {
+            return Some(branch.trim().to_string());
         }
     }
     None
-}
\ No newline at end of file
+}
+/// 测试用的临时仓库目录（含空的 `.git`），离开作用域时删除
+#[cfg(test)]
+pub struct TempRepo(PathBuf);
+
+#[cfg(test)]
+impl TempRepo {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("rust-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        TempRepo(dir)
+    }
+
+    pub fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_locks_are_tracked_until_committed_or_dropped() {
+        let repo = TempRepo::new("lock-registry");
+        let target = Path::new(repo.path()).join("file");
+        let lock_path = Path::new(repo.path()).join("file.lock");
+
+        let lock = LockFile::acquire(&target).unwrap();
+        assert!(held_locks().contains(&lock_path));
+        lock.commit(b"data", false).unwrap();
+        assert!(!held_locks().contains(&lock_path));
+
+        let lock = LockFile::acquire(&target).unwrap();
+        assert!(held_locks().contains(&lock_path));
+        drop(lock);
+        assert!(!held_locks().contains(&lock_path));
+        assert_eq!(fs::read(&target).unwrap(), b"data");
+    }
+}
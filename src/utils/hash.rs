@@ -1,9 +1,211 @@
 // utils/hash.rs
-use sha1::{Sha1, Digest};
+use crate::core::config::Config;
+use crate::utils::error::GitError;
+use sha1collisiondetection::Sha1CD;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use hex;
 
-pub fn sha1(data: &[u8]) -> String {
-    let mut hasher = Sha1::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
-}
\ No newline at end of file
+/// 仓库使用的对象哈希算法（`extensions.objectFormat`）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+thread_local! {
+    /// 仓库路径 -> 哈希算法，避免每次读写对象都解析 config
+    static REPO_ALGORITHMS: RefCell<HashMap<String, HashAlgorithm>> = RefCell::new(HashMap::new());
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 测试用：新建的 SHA-1 哈希器先读入的数据（见 `with_shattered_prefix`）
+    static HASHER_PREFIX: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+impl HashAlgorithm {
+    /// 根据名称解析（"sha1" / "sha256"）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha1" => Some(HashAlgorithm::Sha1),
+            "sha256" => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    /// 二进制哈希长度（20 / 32 字节）
+    pub fn raw_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+        }
+    }
+
+    /// 十六进制哈希长度（40 / 64 字符）
+    pub fn hex_len(&self) -> usize {
+        self.raw_len() * 2
+    }
+
+    /// 计算对象的十六进制哈希
+    ///
+    /// SHA-1 检测到碰撞攻击（例如 SHAttered）时返回错误，避免伪造的对象进入仓库。
+    pub fn try_hash(&self, data: &[u8]) -> Result<String, GitError> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize_checked().map(hex::encode)
+    }
+
+    /// 计算二进制哈希（用于 pack、索引等文件的校验和，不检查碰撞）
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// 创建增量哈希器
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            HashAlgorithm::Sha1 => {
+                #[allow(unused_mut)]
+                let mut hasher = Hasher::Sha1(Box::default());
+                #[cfg(test)]
+                HASHER_PREFIX.with(|p| hasher.update(&p.borrow()));
+                hasher
+            }
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    /// 读取仓库配置的哈希算法（未配置时为 SHA-1）
+    ///
+    /// 对象格式无法识别时终止，不猜测算法；需要自行报告错误时使用 `try_for_repo`。
+    pub fn for_repo(repo_path: &str) -> Self {
+        Self::try_for_repo(repo_path).unwrap_or_else(|e| panic!("❌ {}", e))
+    }
+
+    /// 读取仓库配置的哈希算法，`extensions.objectFormat` 无法识别时返回错误（Git 拒绝打开这样的仓库）
+    pub fn try_for_repo(repo_path: &str) -> Result<Self, GitError> {
+        if let Some(algo) = REPO_ALGORITHMS.with(|m| m.borrow().get(repo_path).copied()) {
+            return Ok(algo);
+        }
+        let algo = match Config::load(repo_path).get("extensions.objectFormat") {
+            Some(name) => Self::from_name(name).ok_or_else(|| GitError::UnknownObjectFormat(name.to_string()))?,
+            None => HashAlgorithm::Sha1,
+        };
+        REPO_ALGORITHMS.with(|m| m.borrow_mut().insert(repo_path.to_string(), algo));
+        Ok(algo)
+    }
+
+    /// 判断字符串是否为该算法的完整十六进制哈希
+    pub fn is_full_hex(&self, s: &str) -> bool {
+        s.len() == self.hex_len() && s.chars().all(|c| c.is_ascii_hexdigit())
+    }
+}
+
+/// 增量哈希器（分块计算哈希）
+///
+/// SHA-1 使用 sha1dc：在计算过程中检查每个分块是否带有已知碰撞攻击的扰动向量。
+/// 检测到攻击时，得到的是经过缓解处理的哈希（与伪造者期望的值不同）。
+pub enum Hasher {
+    Sha1(Box<Sha1CD>),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+        }
+    }
+
+    /// 计算最终哈希（不报告碰撞，用于文件校验和）
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha1(mut h) => {
+                let mut out = Default::default();
+                // 碰撞时 out 中是缓解后的哈希，校验和场景下直接使用即可
+                let _ = h.finalize_into_dirty_cd(&mut out);
+                out.to_vec()
+            }
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+        }
+    }
+
+    /// 计算最终哈希，SHA-1 检测到碰撞攻击时返回错误（用于计算对象哈希）
+    pub fn finalize_checked(self) -> Result<Vec<u8>, GitError> {
+        match self {
+            Hasher::Sha1(mut h) => {
+                let mut out = Default::default();
+                match h.finalize_into_dirty_cd(&mut out) {
+                    Ok(()) => Ok(out.to_vec()),
+                    Err(_) => Err(GitError::HashCollision(hex::encode(out))),
+                }
+            }
+            Hasher::Sha256(h) => Ok(h.finalize().to_vec()),
+        }
+    }
+}
+
+/// 测试用：执行 `f` 期间，每个新建的 SHA-1 哈希器都先读入 SHAttered 的碰撞块，
+/// 之后计算的任何哈希都会触发碰撞检测
+///
+/// 碰撞块只在 SHAttered 的 PDF 前缀之后成立，真实对象的 `<type> <size>\0` 头部无法复现它，
+/// 因此写入路径对碰撞的处理只能这样验证。
+#[cfg(test)]
+pub fn with_shattered_prefix<T>(f: impl FnOnce() -> T) -> T {
+    HASHER_PREFIX.with(|p| *p.borrow_mut() = shattered_prefix());
+    let result = f();
+    HASHER_PREFIX.with(|p| p.borrow_mut().clear());
+    result
+}
+
+/// shattered-1.pdf 的前 320 字节：192 字节的 PDF 前缀与两个近似碰撞块
+#[cfg(test)]
+fn shattered_prefix() -> Vec<u8> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sha1dc/shattered-1-prefix.bin");
+    std::fs::read(path).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::TempRepo;
+    use std::path::Path;
+
+    #[test]
+    fn shattered_input_is_reported_as_collision() {
+        let prefix = shattered_prefix();
+        let err = HashAlgorithm::Sha1.try_hash(&prefix).unwrap_err();
+        assert!(matches!(err, GitError::HashCollision(_)));
+
+        // 同样的输入用于校验和时不报错，得到的是缓解后的哈希
+        assert_eq!(HashAlgorithm::Sha1.digest(&prefix).len(), 20);
+        // SHA-256 不受影响
+        assert!(HashAlgorithm::Sha256.try_hash(&prefix).is_ok());
+        // 普通输入不会误报
+        assert!(HashAlgorithm::Sha1.try_hash(&prefix[..192]).is_ok());
+    }
+
+    #[test]
+    fn unknown_object_format_is_refused() {
+        let repo = TempRepo::new("unknown-object-format");
+        std::fs::write(
+            Path::new(repo.path()).join(".git/config"),
+            "[core]\n\trepositoryformatversion = 1\n[extensions]\n\tobjectFormat = sha3\n",
+        )
+        .unwrap();
+        let err = HashAlgorithm::try_for_repo(repo.path()).unwrap_err();
+        assert!(matches!(err, GitError::UnknownObjectFormat(ref name) if name == "sha3"));
+    }
+}
@@ -0,0 +1,30 @@
+// utils/zlib.rs
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{self, Read, Write};
+
+/// zlib 压缩（Git 对象默认压缩级别）
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).expect("zlib compress into Vec cannot fail");
+    encoder.finish().expect("zlib compress into Vec cannot fail")
+}
+
+/// zlib 解压整个数据流
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = ZlibDecoder::new(data);
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// 流式 zlib 压缩：写入的数据压缩后写到 `writer`，结束时需调用 `finish`
+pub fn encoder<W: Write>(writer: W) -> ZlibEncoder<W> {
+    ZlibEncoder::new(writer, Compression::default())
+}
+
+/// 流式 zlib 解压：从 `reader` 读取压缩数据，读出解压后的内容
+pub fn decoder<R: Read>(reader: R) -> ZlibDecoder<R> {
+    ZlibDecoder::new(reader)
+}